@@ -0,0 +1,140 @@
+//! Reads the memory the bot writes to RawMemory and its segments, without the game running.
+//!
+//! Dump memory from the console with `copy(RawMemory.get())`, or a segment with `copy(RawMemory.segments[10])`
+//! after making it active, and save it to a file. Pass `-` instead of a file to read stdin.
+//!
+//! ```text
+//! memory_inspector decode <blob> [--out <json>]   Export memory as pretty JSON
+//! memory_inspector query <blob> <key>...          Print the value at a path, such as `rooms W1N1`
+//! memory_inspector remotes <blob> <commune>       Show the remotes of a commune
+//! memory_inspector encode <json>                  Re-encode exported JSON into a blob for RawMemory.set
+//! memory_inspector segment <segment> <section>    Decode a cold section, such as harvestable_rooms
+//! ```
+//!
+//! Snapshot segments decode the same way as RawMemory. Sections kept in their own segments,
+//! such as harvestable_rooms, are not part of the main blob, and editing them in exported JSON has no effect.
+
+use std::{
+    env, fs,
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use screeps::RoomName;
+use the_international_rust::inspector::{self, InspectorError};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+
+    let result = match args.as_slice() {
+        ["decode", input] => decode(input, None),
+        ["decode", input, "--out", out] => decode(input, Some(out)),
+        ["query", input, path @ ..] => query(input, path),
+        ["remotes", input, commune] => remotes(input, commune),
+        ["encode", input] => encode(input),
+        ["segment", input, section] => segment(input, section, true),
+        ["segment", input, section, "--json"] => segment(input, section, false),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match result {
+        Ok(output) => {
+            println!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+const USAGE: &str = "usage:
+  memory_inspector decode <blob> [--out <json>]
+  memory_inspector query <blob> <key>...
+  memory_inspector remotes <blob> <commune>
+  memory_inspector encode <json>
+  memory_inspector segment <segment> <section> [--json]";
+
+fn read_input(input: &str) -> Result<String, String> {
+    if input == "-" {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| format!("Failed to read stdin: {}", e))?;
+
+        return Ok(text);
+    }
+
+    fs::read_to_string(input).map_err(|e| format!("Failed to read {}: {}", input, e))
+}
+
+fn describe(error: InspectorError) -> String {
+    match error {
+        InspectorError::NotBase32768 => "The blob is not valid base32768".to_string(),
+        InspectorError::Memory(e) => format!("Failed to decode memory: {:?}", e),
+        InspectorError::UnknownSection(section) => format!("Unknown section {}", section),
+        InspectorError::Json(e) => format!("JSON error: {}", e),
+    }
+}
+
+fn decode(input: &str, out: Option<&str>) -> Result<String, String> {
+    let memory = inspector::decode_blob(&read_input(input)?).map_err(describe)?;
+    let json = inspector::memory_to_json(&memory).map_err(describe)?;
+
+    let Some(out) = out else {
+        return Ok(json);
+    };
+
+    fs::write(out, json).map_err(|e| format!("Failed to write {}: {}", out, e))?;
+    Ok(format!("Wrote memory to {}", out))
+}
+
+fn query(input: &str, path: &[&str]) -> Result<String, String> {
+    let memory = inspector::decode_blob(&read_input(input)?).map_err(describe)?;
+
+    match inspector::query(&memory, path).map_err(describe)? {
+        Some(value) => serde_json::to_string_pretty(&value).map_err(|e| e.to_string()),
+        None => Err(format!("Nothing at {}", path.join(" "))),
+    }
+}
+
+fn remotes(input: &str, commune: &str) -> Result<String, String> {
+    let memory = inspector::decode_blob(&read_input(input)?).map_err(describe)?;
+    let commune = commune
+        .parse::<RoomName>()
+        .map_err(|_| format!("{} is not a room name", commune))?;
+
+    let mut lines = Vec::new();
+    for (room_name, remote) in inspector::remotes_for(&memory, &commune) {
+        lines.push(format!(
+            "{} cost {} sources {} abandon {:?}",
+            room_name,
+            remote.cost,
+            remote.source_paths.len(),
+            remote.abandon
+        ));
+    }
+
+    if lines.is_empty() {
+        return Ok(format!("{} has no remotes", commune));
+    }
+
+    Ok(lines.join("\n"))
+}
+
+fn encode(input: &str) -> Result<String, String> {
+    let memory = inspector::memory_from_json(&read_input(input)?).map_err(describe)?;
+
+    inspector::encode_blob(&memory).map_err(describe)
+}
+
+fn segment(input: &str, section: &str, compressed: bool) -> Result<String, String> {
+    let value = inspector::decode_cold_segment(&read_input(input)?, section, compressed).map_err(describe)?;
+
+    serde_json::to_string_pretty(&value).map_err(|e| e.to_string())
+}
@@ -0,0 +1,128 @@
+//! Reruns a tick recorded by a bot built with the `record` feature, without the game running.
+//!
+//! Records rotate through segments 20 to 29, each chunked across a pair of them. Dump one from the console
+//! with `copy(RawMemory.segments[20] + RawMemory.segments[21])` after making both active, and save it to a file.
+//! Pass `-` instead of a file to read stdin.
+//!
+//! ```text
+//! replayer <record> [--log <level>]   Replay the tick, printing the bot's logs at the level, such as debug
+//! replayer show <record>              Summarize what the record holds without running it
+//! ```
+//!
+//! A panic in the recorded tick happens again here, with a native backtrace when RUST_BACKTRACE=1 is set.
+
+use std::{
+    env, fs,
+    io::{self, Read},
+    process::ExitCode,
+};
+
+use log::LevelFilter;
+use the_international_rust::recorder::{self, RecordError, TickRecord};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(|arg| arg.as_str()).collect();
+
+    let result = match args.as_slice() {
+        ["show", input] => show(input),
+        [input] => replay(input, LevelFilter::Off),
+        [input, "--log", level] => match level.parse::<LevelFilter>() {
+            Ok(level) => replay(input, level),
+            Err(_) => Err(format!("{} is not a log level", level)),
+        },
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match result {
+        Ok(output) => {
+            println!("{}", output);
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+const USAGE: &str = "usage:
+  replayer <record> [--log <level>]
+  replayer show <record>";
+
+fn read_input(input: &str) -> Result<String, String> {
+    if input == "-" {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|e| format!("Failed to read stdin: {}", e))?;
+
+        return Ok(text);
+    }
+
+    fs::read_to_string(input).map_err(|e| format!("Failed to read {}: {}", input, e))
+}
+
+fn read_record(input: &str) -> Result<TickRecord, String> {
+    recorder::decode_record(&read_input(input)?).map_err(|error| match error {
+        RecordError::NotBase32768 => "The record is not valid base32768".to_string(),
+        RecordError::Undecodable => {
+            "Failed to decode the record, was it written by another version of the bot?".to_string()
+        }
+    })
+}
+
+fn summarize(record: &TickRecord) -> String {
+    format!(
+        "tick {} on {}: {} creeps, {} rooms, {} flags, {} segments, {} characters of memory",
+        record.time,
+        record.shard,
+        record.creeps.len(),
+        record.rooms.len(),
+        record.flags.len(),
+        record.segments.len(),
+        record.raw_memory.chars().count()
+    )
+}
+
+fn show(input: &str) -> Result<String, String> {
+    let record = read_record(input)?;
+
+    let mut lines = vec![summarize(&record)];
+    for recorded_room in &record.rooms {
+        lines.push(format!(
+            "  {} level {} with {} structures",
+            recorded_room.room.name,
+            recorded_room
+                .room
+                .controller
+                .as_ref()
+                .map_or(0, |controller| controller.level),
+            recorded_room.structures.len()
+        ));
+    }
+
+    Ok(lines.join("\n"))
+}
+
+fn replay(input: &str, level: LevelFilter) -> Result<String, String> {
+    let record = read_record(input)?;
+
+    fern::Dispatch::new()
+        .level(level)
+        .chain(io::stderr())
+        .apply()
+        .map_err(|e| format!("Failed to set up logging: {}", e))?;
+
+    eprintln!("Replaying {}", summarize(&record));
+    let world = recorder::replay(&record);
+
+    Ok(format!(
+        "Tick {} finished with {} intents",
+        record.time,
+        world.intents.len()
+    ))
+}
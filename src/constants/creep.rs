@@ -156,4 +156,32 @@ thread_local! {
     };
 }
 
-pub type MoveTargets = HashMap<Position, String>;
\ No newline at end of file
+pub type MoveTargets = HashMap<Position, String>;
+
+thread_local! {
+    /// Creeps with higher priority move first, and shove idle creeps of equal or lower priority out of their way
+    pub static MOVE_PRIORITY_BY_ROLE: EnumMap<CreepRole, u8> = enum_map! {
+        CreepRole::SourceHarvester => 6,
+        CreepRole::Hauler => 4,
+        CreepRole::Upgrader => 2,
+        CreepRole::Builder => 2,
+        CreepRole::MineralHarvester => 5,
+        CreepRole::Repairer => 2,
+        CreepRole::FastFill => 7,
+        CreepRole::Hub => 7,
+        CreepRole::RemoteHauler => 4,
+        CreepRole::RemoteSourceHarvester => 6,
+        CreepRole::RemoteMineralHarvester => 5,
+        CreepRole::RemoteReserver => 3,
+        CreepRole::RemoteBuilder => 2,
+        CreepRole::Scout => 1,
+        CreepRole::Claimer => 3,
+        CreepRole::Vanguard => 3,
+        CreepRole::Antifa => 8,
+        CreepRole::Downgraders => 3,
+        CreepRole::Unknown => 0,
+    };
+}
+
+/// Power creeps work the structures in the commune core, so they move alongside fast fillers and hub creeps
+pub const POWER_CREEP_MOVE_PRIORITY: u8 = 7;
\ No newline at end of file
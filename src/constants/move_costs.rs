@@ -2,4 +2,15 @@ pub const MAX_COST: u8 = 255;
 
 pub const DEFAULT_WALL_COST: u8 = 255;
 pub const DEFAULT_SWAMP_COST: u8 = 5;
-pub const DEFAULT_LAND_COST: u8 = 1;
\ No newline at end of file
+pub const DEFAULT_LAND_COST: u8 = 1;
+
+/// Enemy tower damage at a tile that adds one to its cost
+pub const TOWER_DAMAGE_PER_COST: u32 = 30;
+
+/// Ticks a shared path is kept for, even if nothing it crosses changes
+pub const PATH_CACHE_LIFETIME: u32 = 1500;
+/// Most shared paths kept to one goal. The oldest is dropped for a new one
+pub const MAX_CACHED_PATHS_PER_GOAL: usize = 8;
+
+/// Added to tiles our creeps stand on when a stuck creep finds a way around them
+pub const MY_CREEP_COST: u8 = 10;
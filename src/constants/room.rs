@@ -1,9 +1,9 @@
-use screeps::Creep;
+use crate::world::objects::CreepData;
 
 #[derive(Clone, Debug)]
 pub struct NotMyCreeps {
-    pub ally: Vec<Creep>,
-    pub enemy: Vec<Creep>
+    pub ally: Vec<CreepData>,
+    pub enemy: Vec<CreepData>
 }
 
 impl NotMyCreeps {
@@ -17,4 +17,6 @@ impl NotMyCreeps {
 
 pub const MAX_REMOTE_ROOM_DISTANCE: u8 = 5;
 pub const ROOM_DIMENSIONS: u8 = 50;
-pub const NO_VISION_STATE_EXPIRATION: u32 = 100;
\ No newline at end of file
+pub const NO_VISION_STATE_EXPIRATION: u32 = 100;
+/// Danger still in sight is restamped once it is this percent of the way through its decay
+pub const DANGER_REFRESH_PERCENT: u32 = 25;
\ No newline at end of file
@@ -6,6 +6,26 @@ use serde::{Deserialize, Serialize};
 pub const ALLIES_SEGMENT: u8 = 90;
 pub const STATS_SEGMENT: u8 = 1;
 
+// Segments holding cold sections of game memory, in the order their chunks are written.
+// Tampering with these values will orphan the data already stored in them.
+pub const HARVESTABLE_ROOMS_SEGMENTS: [u8; 3] = [10, 11, 12];
+pub const KEEPER_ROOMS_SEGMENTS: [u8; 1] = [13];
+pub const PORTAL_ROOMS_SEGMENTS: [u8; 1] = [14];
+pub const CONSTRUCTION_SITES_SEGMENTS: [u8; 1] = [15];
+/// Two slots of last known good main memory, written to alternately
+pub const SNAPSHOT_SEGMENTS: [[u8; 2]; 2] = [[16, 17], [18, 19]];
+/// Rolling buffer of tick records, written to in turn when the record feature is on.
+/// Each record is chunked across the segments of its slot
+pub const RECORDING_SEGMENTS: [[u8; 2]; 5] = [[20, 21], [22, 23], [24, 25], [26, 27], [28, 29]];
+/// The most characters the game will store in a single segment
+pub const MAX_SEGMENT_LENGTH: usize = 100 * 1024;
+/// The game refuses to save more than 10 segments in a tick. Leave room for stats, allies and a memory snapshot
+#[cfg(not(feature = "record"))]
+pub const MAX_MEMORY_SEGMENT_WRITES: usize = 6;
+/// Two less, leaving room for the slot of the tick record
+#[cfg(feature = "record")]
+pub const MAX_MEMORY_SEGMENT_WRITES: usize = 4;
+
 #[derive(Debug, Serialize, Deserialize)]
 /// A list of commands the collective can exact on collaborators
 /// All people who use this bot are collaborators. Only MarvinTMB is a collectivizer
@@ -3,7 +3,7 @@ use std::{
     fmt::{self, Debug}, u32,
 };
 
-use enum_map::{Enum, EnumMap};
+use enum_map::{enum_map, Enum, EnumMap};
 use screeps::{constants::creep::Part, BodyPart, Position, SpawnOptions};
 
 use crate::memory::creep_memory::CreepMemory;
@@ -107,6 +107,24 @@ impl Debug for SpawnRequestArgs {
     }
 }
 
+impl SpawnRequestArgs {
+    pub fn role(&self) -> CreepRole {
+        match self {
+            SpawnRequestArgs::IndividualUniform(args) => args.role,
+            SpawnRequestArgs::GroupUniform(args) => args.role,
+            SpawnRequestArgs::GroupDiverse(args) => args.role,
+        }
+    }
+
+    pub fn priority(&self) -> f32 {
+        match self {
+            SpawnRequestArgs::IndividualUniform(args) => args.priority,
+            SpawnRequestArgs::GroupUniform(args) => args.priority,
+            SpawnRequestArgs::GroupDiverse(args) => args.priority,
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub enum SpawnRequestTypes {
     #[default]
@@ -181,10 +199,7 @@ pub mod spawn_priority_bounds {
     pub const REMOTE_HAULER: (f32, f32) = (10.3, 1000.0);
 
     // Still need to figure these out. Ideally we spawn them in between efficiency peaks. As in, when we can spawn very efficient creeps don't spawn these guys, otherwise spawn them
-    // Determine how much repair, upgrade, build combat and scout spawn time we need
-    // Compare to predictions of spawn time for remoting and other needs
-    // Cut down on remotes until we have sufficient spawn time for these creeps
-    // Then, when we would have spent spawn time on the less efficient remotes, we instead spawn these guys
+    // Spawn time for them is budgeted ahead of remotes, see SPAWN_TIME_BUDGET_BY_ROLE and spawn_time_ops
 
     pub const NORMAL_REPAIRER: (f32, f32) = (0.0, 1000.0);
     pub const UPGRADER: (f32, f32) = (0.0, 1000.0);
@@ -194,5 +209,81 @@ pub mod spawn_priority_bounds {
     pub const SCOUT: (f32, f32) = (0.0, 1000.0);
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+/// The order roles are given spawn time in when there isn't enough for everyone
+pub enum SpawnTimeBudget {
+    /// Always given spawn time, even beyond what the spawns can provide
+    Essential,
+    /// Given what essential roles leave
+    Commune,
+    /// Given only what is left after commune roles, so remotes are cut first
+    Remote,
+}
+
+thread_local! {
+    pub static SPAWN_TIME_BUDGET_BY_ROLE: EnumMap<CreepRole, SpawnTimeBudget> = enum_map! {
+        CreepRole::SourceHarvester => SpawnTimeBudget::Essential,
+        CreepRole::Hauler => SpawnTimeBudget::Essential,
+        CreepRole::Upgrader => SpawnTimeBudget::Commune,
+        CreepRole::Builder => SpawnTimeBudget::Commune,
+        CreepRole::MineralHarvester => SpawnTimeBudget::Commune,
+        CreepRole::Repairer => SpawnTimeBudget::Commune,
+        CreepRole::FastFill => SpawnTimeBudget::Essential,
+        CreepRole::Hub => SpawnTimeBudget::Essential,
+        CreepRole::RemoteHauler => SpawnTimeBudget::Remote,
+        CreepRole::RemoteSourceHarvester => SpawnTimeBudget::Remote,
+        CreepRole::RemoteMineralHarvester => SpawnTimeBudget::Remote,
+        CreepRole::RemoteReserver => SpawnTimeBudget::Remote,
+        CreepRole::RemoteBuilder => SpawnTimeBudget::Remote,
+        CreepRole::Scout => SpawnTimeBudget::Commune,
+        CreepRole::Claimer => SpawnTimeBudget::Essential,
+        CreepRole::Vanguard => SpawnTimeBudget::Essential,
+        CreepRole::Antifa => SpawnTimeBudget::Essential,
+        CreepRole::Downgraders => SpawnTimeBudget::Essential,
+        CreepRole::Unknown => SpawnTimeBudget::Essential,
+    };
+    /// Roles whose bodies grow with the commune's spawn energy capacity, so old small bodies become obsolete
+    pub static BODY_SCALES_WITH_ENERGY_BY_ROLE: EnumMap<CreepRole, bool> = enum_map! {
+        CreepRole::SourceHarvester => true,
+        CreepRole::Hauler => true,
+        CreepRole::Upgrader => true,
+        CreepRole::Builder => true,
+        CreepRole::MineralHarvester => true,
+        CreepRole::Repairer => true,
+        CreepRole::FastFill => false,
+        CreepRole::Hub => false,
+        CreepRole::RemoteHauler => false,
+        CreepRole::RemoteSourceHarvester => false,
+        CreepRole::RemoteMineralHarvester => false,
+        CreepRole::RemoteReserver => false,
+        CreepRole::RemoteBuilder => false,
+        CreepRole::Scout => false,
+        CreepRole::Claimer => false,
+        CreepRole::Vanguard => false,
+        CreepRole::Antifa => false,
+        CreepRole::Downgraders => false,
+        CreepRole::Unknown => false,
+    };
+}
+
+/// Creeps with more ticks to live than their spawn time plus this are nowhere near needing a replacement,
+/// so the travel time of one isn't found yet
+pub const PRE_SPAWN_CHECK_TICKS: u32 = 300;
+
+/// Creeps worth renewing go to a spawn once they have this few ticks to live
+pub const RENEW_TICKS_TO_LIVE: u32 = 200;
+
+/// Spawn usage at which the spawn time renewing saves is worth paying extra energy for
+pub const RENEW_SPAWN_USAGE_PERCENT: u32 = 80;
+
+/// How much more energy per tick of life renewing may cost than spawning the body fresh
+pub const RENEW_MAX_EXTRA_COST_PERCENT: u32 = 10;
+
+/// Creeps costing at most this share of the commune's spawn energy capacity have an obsolete body
+pub const OBSOLETE_BODY_COST_PERCENT: u32 = 50;
+
+/// Obsolete creeps are only recycled while spawn usage is below this, so their replacements can spawn
+pub const RECYCLE_SPAWN_USAGE_PERCENT: u32 = 50;
+
 /// The minimum cost to spawn a creep (say, with 1 MOVE part)
 pub const MIN_SPAWN_COST: u32 = 50;
\ No newline at end of file
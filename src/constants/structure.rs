@@ -10,6 +10,8 @@ use screeps::{
     StructureTerminal, StructureTower, StructureType, StructureWall,
 };
 
+use crate::world::objects::StructureData;
+
 pub type OldOrganizedStructures = HashMap<StructureType, Vec<StructureObject>>;
 
 pub const IMPASSIBLE_STRUCTURES: [StructureType; 8] = [
@@ -106,29 +108,29 @@ impl CustomStructureType {
 
 #[derive(Debug, Default)]
 pub struct OrganizedStructures {
-    pub spawn: Vec<StructureSpawn>,
-    pub extension: Vec<StructureExtension>,
-    pub road: Vec<StructureRoad>,
-    pub wall: Vec<StructureWall>,
-    pub rampart: Vec<StructureRampart>,
-    pub keeper_lair: Vec<StructureKeeperLair>,
-    pub portal: Vec<StructurePortal>,
-    pub link: Vec<StructureLink>,
-    pub tower: Vec<StructureTower>,
-    pub observer: Vec<StructureObserver>,
-    pub power_bank: Vec<StructurePowerBank>,
-    pub lab: Vec<StructureLab>,
-    pub container: Vec<StructureContainer>,
-    pub invader_core: Vec<StructureInvaderCore>,
-    pub power_spawn: Vec<StructurePowerSpawn>,
-    pub factory: Vec<StructureFactory>,
-    pub nuker: Vec<StructureNuker>,
+    pub spawn: Vec<StructureData>,
+    pub extension: Vec<StructureData>,
+    pub road: Vec<StructureData>,
+    pub wall: Vec<StructureData>,
+    pub rampart: Vec<StructureData>,
+    pub keeper_lair: Vec<StructureData>,
+    pub portal: Vec<StructureData>,
+    pub link: Vec<StructureData>,
+    pub tower: Vec<StructureData>,
+    pub observer: Vec<StructureData>,
+    pub power_bank: Vec<StructureData>,
+    pub lab: Vec<StructureData>,
+    pub container: Vec<StructureData>,
+    pub invader_core: Vec<StructureData>,
+    pub power_spawn: Vec<StructureData>,
+    pub factory: Vec<StructureData>,
+    pub nuker: Vec<StructureData>,
 }
 
 #[derive(Debug, Clone)]
 pub struct SpawnsByActivity {
-    pub active: Vec<StructureSpawn>,
-    pub inactive: Vec<StructureSpawn>,
+    pub active: Vec<StructureData>,
+    pub inactive: Vec<StructureData>,
 }
 
 impl SpawnsByActivity {
@@ -1,25 +1,26 @@
 use std::collections::{HashMap, HashSet};
 
 use log::error;
-use screeps::{Creep, HasPosition, LocalRoomTerrain, Part, Position, RoomName, Terrain, game};
+use screeps::{LocalRoomTerrain, Part, Position, StructureType, Terrain};
 
 use crate::{
     constants::{
-        creep::MoveTargets,
-        general::{GeneralError, GeneralResult},
+        creep::{MoveTargets, MOVE_PRIORITY_BY_ROLE, POWER_CREEP_MOVE_PRIORITY},
+        general::{GeneralError, GeneralResult, DIRECTIONS},
         move_costs::MAX_COST,
     },
     memory::{creep_memory, game_memory::GameMemory},
     pathfinding::{
-        pathfinding_services_multi::try_find_path, pathfinding_services_single, room_pather_multi::PathGoals, room_pather_single::PathGoal, PathfindingOpts
+        path_cache_ops, pathfinding_services_multi::try_find_path, pathfinding_services_single, room_pather_multi::PathGoals, room_pather_single::PathGoal, CostProfile, PathfindingOpts
     },
     room::room_ops::{self, default_move_costs},
-    state::game::GameState,
+    state::{game::GameState, path_cache::PathCacheKey},
     utils::{
         self,
-        general::{pos_range, GeneralUtils},
-        pos::{get_adjacent_positions_conditional, is_xy_exit}, visuals::visualize_path,
+        general::GeneralUtils,
+        pos::{exit_destination, is_xy_exit}, visuals::visualize_path,
     },
+    world, SETTINGS,
 };
 
 use super::my_creep_ops;
@@ -45,6 +46,8 @@ pub fn create_move_request(
         return Err(GeneralError::Fail);
     }
 
+    let stuck_ticks = update_stuck_ticks(creep_name, game_state, memory);
+
     // If we are at the goal
     let my_creep_state = game_state.my_creep_states.get_mut(creep_name).unwrap();
 
@@ -61,12 +64,25 @@ pub fn create_move_request(
         return Err(GeneralError::Fail);
     }
 
+    // If we have been stuck for too long, find a path around the creeps in the way
+
+    if let Some(cost_profile) = stuck_cost_profile(stuck_ticks) {
+        return repath_around_creeps(creep_name, goal, opts, cost_profile, game_state, memory);
+    }
+
     // If we have a valid path, continue to use it
 
     if try_use_existing_path(creep_name, goal, game_state, memory).is_ok() {
         return Ok(GeneralResult::Success);
     }
 
+    // If a creep going to the same goal found a path through here, follow it
+
+    let key = PathCacheKey::new(goal, &opts);
+    if try_use_cached_path(creep_name, goal, &key, game_state, memory).is_ok() {
+        return Ok(GeneralResult::Success);
+    }
+
     // If there is no existing path, create one
 
     let my_creep_state = game_state.my_creep_states.get(creep_name).unwrap();
@@ -85,14 +101,128 @@ pub fn create_move_request(
     let creep_state = game_state.my_creep_states.get_mut(creep_name).unwrap();
     creep_state.move_request = Some(path[1]);
 
+    // Share the path with creeps going to the same goal, or keep our own when it can't be shared
+
+    let cached_path = path_cache_ops::cache_path(key, path.clone(), game_state, memory);
+
     let creep_memory = memory.creeps.get_mut(creep_name).unwrap();
+    creep_memory.move_goal_pos = Some(goal.pos);
+    creep_memory.move_path = match cached_path {
+        Some(_) => None,
+        None => Some(path),
+    };
+
+    let creep_state = game_state.my_creep_states.get_mut(creep_name).unwrap();
+    creep_state.cached_path = cached_path;
+
+    Ok(GeneralResult::Success)
+}
+
+/// Count the consecutive ticks we asked to move and stayed where we were.
+/// The count is mirrored to creep memory only when it changes, so it survives a global reset
+/// without dirtying the creeps section for every creep that moves
+fn update_stuck_ticks(creep_name: &str, game_state: &mut GameState, memory: &mut GameMemory) -> u8 {
+    let my_creep_state = game_state.my_creep_states.get_mut(creep_name).unwrap();
+    let stuck = my_creep_state.last_move_request.is_some() && my_creep_state.pos == my_creep_state.last_pos;
+
+    let remembered_stuck_ticks = memory
+        .creeps
+        .get(creep_name)
+        .map_or(0, |creep_memory| creep_memory.stuck_ticks);
+
+    // After a global reset, carry on from the count in memory
+    let stuck_ticks = my_creep_state.stuck_ticks.unwrap_or(remembered_stuck_ticks);
+    let stuck_ticks = match stuck {
+        true => stuck_ticks.saturating_add(1),
+        false => 0,
+    };
+    my_creep_state.stuck_ticks = Some(stuck_ticks);
+
+    if stuck_ticks != remembered_stuck_ticks {
+        if let Some(creep_memory) = memory.creeps.get_mut(creep_name) {
+            creep_memory.stuck_ticks = stuck_ticks;
+        }
+    }
+
+    stuck_ticks
+}
+
+/// Costs to find a new path with when we have been stuck for a threshold of ticks
+fn stuck_cost_profile(stuck_ticks: u8) -> Option<CostProfile> {
+    if stuck_ticks == 0 {
+        return None;
+    }
+
+    let (repath_ticks, block_ticks) = SETTINGS.with_borrow(|settings| {
+        (settings.stuck_repath_ticks, settings.stuck_block_ticks.max(1))
+    });
+
+    if stuck_ticks % block_ticks == 0 {
+        return Some(CostProfile::BlockCreeps);
+    }
 
+    if stuck_ticks == repath_ticks {
+        return Some(CostProfile::AvoidCreeps);
+    }
+
+    None
+}
+
+/// Find a path of our own with costs that account for creeps. It is not shared, since the creeps will move
+fn repath_around_creeps(
+    creep_name: &str,
+    goal: &PathGoal,
+    mut opts: PathfindingOpts,
+    cost_profile: CostProfile,
+    game_state: &mut GameState,
+    memory: &mut GameMemory,
+) -> Result<GeneralResult, GeneralError> {
+    game_state.segments.stats.stuck_repaths += 1;
+    opts.room_pathfinder_opts.cost_profile = cost_profile;
+
+    let pos = game_state.my_creep_states.get(creep_name).unwrap().pos;
+    let Ok(path) = pathfinding_services_single::try_find_path(pos, goal, opts, game_state, memory) else {
+        return Err(GeneralError::Fail);
+    };
+
+    let my_creep_state = game_state.my_creep_states.get_mut(creep_name).unwrap();
+    my_creep_state.move_request = Some(path[1]);
+    my_creep_state.cached_path = None;
+
+    let creep_memory = memory.creeps.get_mut(creep_name).unwrap();
     creep_memory.move_goal_pos = Some(goal.pos);
     creep_memory.move_path = Some(path);
 
     Ok(GeneralResult::Success)
 }
 
+/// Follow a shared path to the goal that passes through our position
+fn try_use_cached_path(
+    creep_name: &str,
+    goal: &PathGoal,
+    key: &PathCacheKey,
+    game_state: &mut GameState,
+    memory: &mut GameMemory,
+) -> Result<(), GeneralError> {
+    let pos = game_state.my_creep_states.get(creep_name).unwrap().pos;
+
+    let Some((cached_path, index)) = path_cache_ops::find_cached_path(pos, key, game_state, memory) else {
+        return Err(GeneralError::Fail);
+    };
+
+    visualize_path(&cached_path.path[index..]);
+
+    let my_creep_state = game_state.my_creep_states.get_mut(creep_name).unwrap();
+    my_creep_state.move_request = Some(cached_path.path[index + 1]);
+    my_creep_state.cached_path = Some(cached_path);
+
+    let creep_memory = memory.creeps.get_mut(creep_name).unwrap();
+    creep_memory.move_goal_pos = Some(goal.pos);
+    creep_memory.move_path = None;
+
+    Ok(())
+}
+
 /// Make sure that we have a valid path that aligns with a specified goal
 fn try_use_existing_path(
     creep_name: &str,
@@ -160,21 +290,68 @@ fn try_use_existing_path(
 
 fn assign_move_request(creep_name: &str) {}
 
+/// What traffic needs to know about a creep or power creep of ours
+struct Mover {
+    pos: Position,
+    action_pos: Option<Position>,
+    move_request: Option<Position>,
+    /// Without fatigue and with parts to move with. Power creeps can always move
+    can_move: bool,
+    harvest_pos: Option<Position>,
+    spawning: bool,
+}
+
+fn mover(creep_name: &str, game_state: &GameState) -> Mover {
+    if let Some(creep_state) = game_state.my_creep_states.get(creep_name) {
+        let move_parts = game_state
+            .creeps
+            .get(creep_name)
+            .map_or(0, |creep| creep.inner().get_active_bodyparts(Part::Move));
+
+        return Mover {
+            pos: creep_state.pos,
+            action_pos: creep_state.action_pos,
+            move_request: creep_state.move_request,
+            can_move: creep_state.fatigue == 0 && move_parts > 0,
+            harvest_pos: creep_state.harvest_pos,
+            spawning: creep_state.spawning,
+        };
+    }
+
+    let power_creep_state = game_state.my_power_creep_states.get(creep_name).unwrap();
+    Mover {
+        pos: power_creep_state.pos,
+        action_pos: power_creep_state.action_pos,
+        move_request: power_creep_state.move_request,
+        can_move: true,
+        harvest_pos: None,
+        spawning: false,
+    }
+}
+
+fn set_action_pos(creep_name: &str, pos: Position, game_state: &mut GameState) {
+    if let Some(creep_state) = game_state.my_creep_states.get_mut(creep_name) {
+        creep_state.action_pos = Some(pos);
+        return;
+    }
+
+    let power_creep_state = game_state.my_power_creep_states.get_mut(creep_name).unwrap();
+    power_creep_state.action_pos = Some(pos);
+}
+
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
 pub fn assign_move_target_as_pos(
     creep_name: &str,
     game_state: &mut GameState,
     move_targets: &mut MoveTargets,
 ) {
-    let creep = game_state.creeps.get(creep_name).unwrap();
-    let pos = creep.inner().pos();
+    let pos = mover(creep_name, game_state).pos;
 
     move_targets.insert(pos, creep_name.to_string());
-
-    let creep_state = game_state.my_creep_states.get_mut(creep_name).unwrap();
-    creep_state.action_pos = Some(pos);
+    set_action_pos(creep_name, pos, game_state);
 }
 
+/// Claim a position for the creep to end the tick on, giving up the one it claimed before
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
 pub fn assign_move_target(
     creep_name: &str,
@@ -182,222 +359,378 @@ pub fn assign_move_target(
     game_state: &mut GameState,
     move_targets: &mut MoveTargets,
 ) {
-    move_targets.insert(position, creep_name.to_string());
+    if let Some(previous) = mover(creep_name, game_state).action_pos {
+        if move_targets.get(&previous).is_some_and(|name| name == creep_name) {
+            move_targets.remove(&previous);
+        }
+    }
 
-    let creep_state = game_state.my_creep_states.get_mut(creep_name).unwrap();
-    creep_state.action_pos = Some(position);
+    move_targets.insert(position, creep_name.to_string());
+    set_action_pos(creep_name, position, game_state);
 }
 
+/// Move the creep or power creep to its move request, swapping with or shoving those of ours in the way.
+/// Creeps should be run in descending move priority
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
 pub fn try_run_move_request(
     creep_name: &str,
-    room_name: &RoomName,
     game_state: &mut GameState,
     memory: &GameMemory,
     move_targets: &mut MoveTargets,
 ) {
-    let creep_state = game_state.my_creep_states.get_mut(creep_name).unwrap();
+    let creep_mover = mover(creep_name, game_state);
 
-    let Some(move_request) = creep_state.move_request else {
+    let Some(move_request) = creep_mover.move_request else {
         return;
     };
 
-    if let Some(move_target) = creep_state.action_pos {
-        if move_request == move_target {
-            return;
-        }
+    if !creep_mover.can_move {
+        return;
+    }
 
-        // If we do have a move target, delete it so we can run the move request
+    // We may have already been moved there, such as by a swap with a creep that ran before us
+    if creep_mover.action_pos == Some(move_request) {
+        return;
+    }
 
-        move_targets.remove(&move_request);
-        creep_state.action_pos = None;
+    let pos = creep_mover.pos;
+    let mut visited_creeps = HashSet::from([creep_name.to_string()]);
+
+    // Crossing the border from an exit happens without a move, we only need somewhere to arrive
+    if exit_destination(&pos) == Some(move_request) {
+        try_claim_exit_destination(
+            creep_name,
+            move_request,
+            game_state,
+            memory,
+            move_targets,
+            &mut visited_creeps,
+        );
+        return;
+    }
+
+    // Moving along the edge would take us back across the border, so step off it instead
+    let move_request = match is_pos_exit(pos) && is_pos_exit(move_request) && pos.room_name() == move_request.room_name() {
+        true => {
+            let Some(step) = edge_step(creep_name, move_request, game_state, memory) else {
+                return;
+            };
+            step
+        }
+        false => move_request,
     };
 
-    let cost = run_move_request(
+    try_claim_move_target(
         creep_name,
-        room_name,
+        move_request,
         game_state,
         memory,
         move_targets,
-        &mut HashSet::new(),
-        0,
+        &mut visited_creeps,
     );
-    if cost < 0 {
-        return;
-    }
+}
 
-    assign_move_target_as_pos(creep_name, game_state, move_targets)
+fn is_pos_exit(pos: Position) -> bool {
+    is_xy_exit(pos.x().u8(), pos.y().u8())
 }
 
-#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-// Use a sparse cost matrix to optimize cost calculations
-fn run_move_request(
+/// A position off the edge next to the creep, closest to where it wanted to go
+fn edge_step(
     creep_name: &str,
-    room_name: &RoomName,
+    move_request: Position,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+) -> Option<Position> {
+    let pos = mover(creep_name, game_state).pos;
+    let move_costs = default_move_costs(&pos.room_name(), game_state, memory);
+
+    DIRECTIONS
+        .iter()
+        .filter_map(|direction| pos.checked_add((*direction).into()).ok())
+        .filter(|step| {
+            step.room_name() == pos.room_name() && !is_pos_exit(*step) && move_costs.get(step.xy()) != MAX_COST
+        })
+        .min_by_key(|step| step.get_range_to(move_request))
+}
+
+/// Claim a position for the creep to move to. A creep in the way is swapped with us if it wants our position,
+/// otherwise it is shoved to another position, recursively shoving creeps in its way.
+/// Moving onto an exit also claims where we will arrive in the adjacent room.
+/// If the claim fails, every claim made while trying is given back and the creeps we moved keep their positions
+fn try_claim_move_target(
+    creep_name: &str,
+    pos: Position,
     game_state: &mut GameState,
     memory: &GameMemory,
     move_targets: &mut MoveTargets,
     visited_creeps: &mut HashSet<String>,
-    cost: i32,
-) -> i32 {
-    visited_creeps.insert(creep_name.to_string());
+) -> bool {
+    let previous_move_targets = move_targets.clone();
+    let previous_action_pos = mover(creep_name, game_state).action_pos;
+    let previous_visited_creeps = visited_creeps.clone();
+
+    let claimed = match exit_destination(&pos) {
+        Some(destination) => {
+            try_claim_exit_destination(creep_name, destination, game_state, memory, move_targets, visited_creeps)
+                && try_claim_pos(creep_name, pos, game_state, memory, move_targets, visited_creeps)
+        }
+        None => try_claim_pos(creep_name, pos, game_state, memory, move_targets, visited_creeps),
+    };
+    if claimed {
+        return true;
+    }
 
-    let creep_memory = memory.creeps.get(creep_name).unwrap();
+    *move_targets = previous_move_targets;
+    if let Some(action_pos) = previous_action_pos {
+        set_action_pos(creep_name, action_pos, game_state);
+    }
+    // Creeps can only be shoved while they are staying where they are
+    for shoved_creep_name in visited_creeps.difference(&previous_visited_creeps) {
+        let shoved_pos = mover(shoved_creep_name, game_state).pos;
+        set_action_pos(shoved_creep_name, shoved_pos, game_state);
+    }
 
-    let target_coord = {
-        let creep_state = game_state.my_creep_states.get(creep_name).unwrap();
-        creep_state.move_request
-    };
-    let move_options = get_move_options(creep_name, room_name, game_state, memory, target_coord);
+    false
+}
 
-    // TODO: Many of these conditionals should be moved to get_move_options so they can be cached
-    for pos in move_options {
-        let creep_in_way_name = move_targets.get(&pos);
+/// Whether the creep in the way can be moved to where we are. Our position may already be claimed
+/// by whoever is shoving us
+fn can_take_from(from: Position, creep_name: &str, move_targets: &MoveTargets) -> bool {
+    move_targets.get(&from).map_or(true, |name| name == creep_name)
+}
 
-        if let Some(creep_in_way_name) = creep_in_way_name {
-            if visited_creeps.contains(creep_in_way_name) {
-                continue;
-            }
+fn try_claim_pos(
+    creep_name: &str,
+    pos: Position,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+    move_targets: &mut MoveTargets,
+    visited_creeps: &mut HashSet<String>,
+) -> bool {
+    let Some(creep_in_way_name) = move_targets.get(&pos).cloned() else {
+        assign_move_target(creep_name, pos, game_state, move_targets);
+        return true;
+    };
 
-            // Could be a power creep
-            let creep_in_way = game_state.creeps.get(creep_in_way_name);
+    if creep_in_way_name == creep_name {
+        return true;
+    }
 
-            if let Some(creep_in_way) = creep_in_way {
-                if creep_in_way.inner().get_active_bodyparts(Part::Move) == 0 {
-                    continue;
-                }
-            }
-        }
+    if visited_creeps.contains(&creep_in_way_name)
+        || !can_shove(creep_name, &creep_in_way_name, game_state, memory)
+    {
+        return false;
+    }
+    visited_creeps.insert(creep_in_way_name.clone());
+
+    let from = mover(creep_name, game_state).pos;
+
+    // Claim the position first, so nothing the creep in the way goes on to shove can be moved onto it
+    assign_move_target(creep_name, pos, game_state, move_targets);
+
+    // Swap if the creep in the way wants to go where we are
+    if mover(&creep_in_way_name, game_state).move_request == Some(from)
+        && can_take_from(from, creep_name, move_targets)
+        && can_stand_shoved(&creep_in_way_name, from, game_state, memory)
+        && exit_destination(&from).map_or(true, |destination| {
+            try_claim_exit_destination(
+                &creep_in_way_name,
+                destination,
+                game_state,
+                memory,
+                move_targets,
+                visited_creeps,
+            )
+        })
+    {
+        assign_move_target(&creep_in_way_name, from, game_state, move_targets);
+        return true;
+    }
 
-        let creep_state = game_state.my_creep_states.get(creep_name).unwrap();
-
-        // Don't allow exits unless we are actively trying to move onto one
-        match creep_state.move_request {
-            Some(move_request) => {
-                if move_request != pos && pos.is_room_edge() {
-                    continue;
-                }
-            }
-            None => {
-                if pos.is_room_edge() {
-                    continue;
-                }
-            }
-        }
+    // Shove it somewhere else
+    if try_shove(&creep_in_way_name, from, game_state, memory, move_targets, visited_creeps) {
+        return true;
+    }
 
-        let mut potential_cost = cost;
-        {
-            let creep_state = game_state.my_creep_states.get(creep_name).unwrap();
+    // Otherwise swap anyway, as long as that wouldn't push it across the border
+    if !is_pos_exit(from)
+        && can_take_from(from, creep_name, move_targets)
+        && can_stand_shoved(&creep_in_way_name, from, game_state, memory)
+    {
+        assign_move_target(&creep_in_way_name, from, game_state, move_targets);
+        return true;
+    }
 
-            if let Some(move_request) = creep_state.move_request {
-                if move_request == pos {
-                    potential_cost -= 1;
-                }
-            }
+    false
+}
+
+/// Make sure the position a creep crossing the border arrives at is free for it, shoving a creep of ours off it.
+/// The creep keeps its claim on the exit, and holds the destination in the adjacent room's move targets
+fn try_claim_exit_destination(
+    creep_name: &str,
+    destination: Position,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+    move_targets: &mut MoveTargets,
+    visited_creeps: &mut HashSet<String>,
+) -> bool {
+    if let Some(creep_in_way_name) = move_targets.get(&destination).cloned() {
+        if creep_in_way_name == creep_name {
+            return true;
         }
 
-        if let Some(creep_in_way_name) = creep_in_way_name {
-            // Could be a power creep
-            let creep_in_way = game_state.creeps.get(creep_in_way_name);
-
-            if let Some(creep_in_way) = creep_in_way {
-                let creep_in_way_state = game_state.my_creep_states.get(creep_in_way_name).unwrap();
-
-                if creep_in_way_state.move_request == Some(pos) {
-                    potential_cost += 1;
-                }
-
-                let creep_in_way_cost = run_move_request(
-                    creep_name,
-                    room_name,
-                    game_state,
-                    memory,
-                    move_targets,
-                    visited_creeps,
-                    potential_cost,
-                );
-
-                if creep_in_way_cost >= 0 {
-                    continue;
-                }
-
-                assign_move_target(creep_name, pos, game_state, move_targets);
-                return creep_in_way_cost;
-            }
+        if visited_creeps.contains(&creep_in_way_name)
+            || !can_shove(creep_name, &creep_in_way_name, game_state, memory)
+        {
+            return false;
         }
+        visited_creeps.insert(creep_in_way_name.clone());
 
-        if potential_cost < 0 {
-            assign_move_target(creep_name, pos, game_state, move_targets);
+        if !try_shove(&creep_in_way_name, destination, game_state, memory, move_targets, visited_creeps) {
+            return false;
         }
-        return potential_cost;
     }
 
-    i32::MAX
+    move_targets.insert(destination, creep_name.to_string());
+    true
 }
 
-#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-pub fn get_move_options(
+/// Move a creep to the first of its shove options it can claim
+fn try_shove(
     creep_name: &str,
-    room_name: &RoomName,
+    shover_pos: Position,
     game_state: &mut GameState,
     memory: &GameMemory,
-    target_coord: Option<Position>,
-) -> Vec<Position> {
-    let creep_state = game_state.my_creep_states.get_mut(creep_name).unwrap();
+    move_targets: &mut MoveTargets,
+    visited_creeps: &mut HashSet<String>,
+) -> bool {
+    for shove_pos in shove_options(creep_name, shover_pos, game_state, memory) {
+        if try_claim_move_target(creep_name, shove_pos, game_state, memory, move_targets, visited_creeps) {
+            return true;
+        }
+    }
 
-    if let Some(move_options) = &creep_state.move_options {
-        return move_options.clone();
-    };
+    false
+}
 
-    let mut move_options: Vec<Position> = Vec::new();
+/// Whether a creep or power creep of ours can be moved out of the way by another
+fn can_shove(
+    creep_name: &str,
+    creep_in_way_name: &str,
+    game_state: &GameState,
+    memory: &GameMemory,
+) -> bool {
+    let creep_in_way_state = mover(creep_in_way_name, game_state);
 
-    let creep = game_state.creeps.get(creep_name).unwrap();
-    // Would be nice to cache move options on creep state
-    // let creep_state = game_state.my_creep_states.get_mut(creep_name).unwrap();
+    // It has already moved this tick
+    if creep_in_way_state.action_pos != Some(creep_in_way_state.pos) {
+        return false;
+    }
 
-    if creep.inner().fatigue() > 0 {
-        return move_options;
+    if creep_in_way_state.spawning {
+        return false;
     }
 
-    let creep_pos = creep.inner().pos();
+    if !creep_in_way_state.can_move {
+        return false;
+    }
 
-    if let Some(target_coord) = target_coord {
-        if creep_pos == target_coord {
-            return move_options;
-        };
+    // It is crossing the border from an exit
+    if creep_in_way_state.move_request.is_some()
+        && creep_in_way_state.move_request == exit_destination(&creep_in_way_state.pos)
+    {
+        return false;
+    }
 
-        move_options.insert(0, target_coord);
-        return move_options;
+    // Harvesters stay on their harvest positions
+    if creep_in_way_state.harvest_pos == Some(creep_in_way_state.pos) {
+        return false;
     }
 
-    // Add adjacent positions that are not exits or market to avoid
+    // Creeps that want to move anyway can be pushed along
+    if creep_in_way_state.move_request.is_some() {
+        return true;
+    }
 
-    let move_costs = default_move_costs(room_name, game_state, memory);
+    move_priority(creep_name, game_state, memory) >= move_priority(creep_in_way_name, game_state, memory)
+}
 
-    move_options.extend(get_adjacent_positions_conditional(&creep_pos, &|pos| {
-        move_costs.get(pos.xy()) != MAX_COST && !is_xy_exit(pos.x().0, pos.y().0)
-    }));
+pub fn move_priority(creep_name: &str, game_state: &GameState, memory: &GameMemory) -> u8 {
+    if let Some(creep_memory) = memory.creeps.get(creep_name) {
+        return MOVE_PRIORITY_BY_ROLE.with(|priorities| priorities[creep_memory.role]);
+    }
 
-    // Sort by range to an action pos if there is one. Otherwise, shuffle randomly
+    if game_state.my_power_creep_states.contains_key(creep_name) {
+        return POWER_CREEP_MOVE_PRIORITY;
+    }
 
-    let creep_state = game_state.my_creep_states.get(creep_name).unwrap();
+    0
+}
 
-    if let Some(action_pos) = creep_state.action_pos {
-        move_options.sort_by(|a, b| {
-            pos_range(a, &action_pos)
-                .partial_cmp(&pos_range(b, &action_pos))
-                .unwrap()
+/// Positions a shoved creep could move to: its move request first, then adjacent positions it can stand on,
+/// closest to its move request first
+fn shove_options(
+    creep_name: &str,
+    shover_pos: Position,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+) -> Vec<Position> {
+    let creep_state = mover(creep_name, game_state);
+    let creep_pos = creep_state.pos;
+    let move_request = creep_state.move_request;
+    let room_name = creep_pos.room_name();
+
+    let move_costs = default_move_costs(&room_name, game_state, memory);
+
+    let mut shove_options: Vec<Position> = DIRECTIONS
+        .iter()
+        .filter_map(|direction| creep_pos.checked_add((*direction).into()).ok())
+        .filter(|pos| {
+            pos.room_name() == room_name
+                && *pos != shover_pos
+                && Some(*pos) != move_request
+                && move_costs.get(pos.xy()) != MAX_COST
+                && !is_xy_exit(pos.x().u8(), pos.y().u8())
         })
-    } else {
-        fastrand::shuffle(&mut move_options)
+        .collect();
+    shove_options.retain(|pos| can_stand_shoved(creep_name, *pos, game_state, memory));
+
+    if let Some(move_request) = move_request {
+        shove_options.sort_by_key(|pos| pos.get_range_to(move_request));
+
+        if move_request != shover_pos {
+            shove_options.insert(0, move_request);
+        }
     }
 
-    move_options
+    shove_options
+}
+
+/// Creeps that only shove to ramparts, such as defenders during a siege, can't be shoved off them
+fn can_stand_shoved(
+    creep_name: &str,
+    pos: Position,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+) -> bool {
+    let rampart_only = memory
+        .creeps
+        .get(creep_name)
+        .is_some_and(|creep_memory| creep_memory.rampart_only_shoving == Some(true));
+    if !rampart_only {
+        return true;
+    }
+
+    room_ops::structures(&pos.room_name(), game_state).iter().any(|structure| {
+        structure.structure_type == StructureType::Rampart
+            && structure.my == Some(true)
+            && structure.pos == pos
+    })
 }
 
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
 pub fn try_run_move_target(creep_name: &str, game_state: &mut GameState) {
-    let my_creep_state = game_state.my_creep_states.get(creep_name).unwrap();
+    let my_creep_state = mover(creep_name, game_state);
     let Some(move_target) = my_creep_state.action_pos else {
         return;
     };
@@ -411,8 +744,400 @@ pub fn try_run_move_target(creep_name: &str, game_state: &mut GameState) {
         return;
     };
     
-    let creep = game_state.creeps.get(creep_name).unwrap();
-    creep.inner().move_direction(direction);
+    let _ = match game_state.my_creep_states.contains_key(creep_name) {
+        true => world::creep_move(creep_name, direction),
+        false => world::power_creep_move(creep_name, direction),
+    };
     
     game_state.segments.stats.intents += 1;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use screeps::{Part, Position, RoomName, StructureType};
+
+    use super::{create_move_request, update_stuck_ticks};
+    use crate::{
+        constants::creep::CreepRole,
+        creep::my_creep_services,
+        memory::{creep_memory::CreepMemory, game_memory::GameMemory},
+        pathfinding::{room_pather_single::PathGoal, PathfindingOpts},
+        state::game::GameState,
+        utils::pos::is_xy_exit,
+        world::{
+            mock_world::{self, Intent, MockWorld},
+            simulation::world_from_map,
+        },
+    };
+
+    fn room_name() -> RoomName {
+        RoomName::new("W1N1").unwrap()
+    }
+
+    fn goal() -> PathGoal {
+        PathGoal::new(mock_world::pos(room_name(), 40, 25), 0)
+    }
+
+    fn next_tick(game_state: &mut GameState, memory: &mut GameMemory) {
+        mock_world::with(|mock| mock.next_tick());
+        game_state.tick_update(memory);
+    }
+
+    fn request(game_state: &mut GameState, memory: &mut GameMemory) -> Option<screeps::Position> {
+        let _ = create_move_request("hauler", &goal(), PathfindingOpts::new(), game_state, memory);
+
+        game_state.my_creep_states.get("hauler").unwrap().move_request
+    }
+
+    #[test]
+    fn stuck_creeps_repath_around_creeps() {
+        let mut world = MockWorld::new();
+        world.add_room(room_name());
+        world.add_creep("hauler", mock_world::pos(room_name(), 10, 25), &[Part::Carry, Part::Move]);
+        mock_world::install(world);
+
+        let mut game_state = GameState::new();
+        let mut memory = mock_world::new_memory();
+        memory
+            .creeps
+            .insert("hauler".to_string(), CreepMemory::new(CreepRole::Hauler, room_name()));
+        game_state.tick_update(&mut memory);
+
+        let blocked = request(&mut game_state, &mut memory).unwrap();
+
+        // A creep that never moves takes the tile we want, and we stay put
+        mock_world::with(|mock| {
+            mock.add_creep("idler", blocked, &[Part::Work]);
+        });
+
+        next_tick(&mut game_state, &mut memory);
+        assert_eq!(request(&mut game_state, &mut memory), Some(blocked));
+        assert_eq!(memory.creeps.get("hauler").unwrap().stuck_ticks, 1);
+        assert_eq!(game_state.segments.stats.stuck_repaths, 0);
+
+        next_tick(&mut game_state, &mut memory);
+        let around = request(&mut game_state, &mut memory).unwrap();
+        assert_ne!(around, blocked);
+        assert_eq!(memory.creeps.get("hauler").unwrap().stuck_ticks, 2);
+        assert_eq!(game_state.segments.stats.stuck_repaths, 1);
+
+        // Moving clears the count
+        mock_world::with(|mock| mock.creeps.get_mut("hauler").unwrap().pos = around);
+        next_tick(&mut game_state, &mut memory);
+        request(&mut game_state, &mut memory);
+        assert_eq!(memory.creeps.get("hauler").unwrap().stuck_ticks, 0);
+
+        // An unchanged count leaves creep memory untouched
+        let generation = memory.creeps.generation();
+        assert_eq!(update_stuck_ticks("hauler", &mut game_state, &mut memory), 0);
+        assert_eq!(memory.creeps.generation(), generation);
+    }
+
+    /// Run the traffic manager over creeps of ours, each at a position and maybe with a move request.
+    /// Returns where each creep ends up
+    fn run_traffic(
+        world: MockWorld,
+        creeps: &[(&str, CreepRole, Position, Option<Position>)],
+        setup: impl FnOnce(&mut GameState, &mut GameMemory),
+    ) -> HashMap<String, Position> {
+        let mut world = world;
+        for (name, _, pos, _) in creeps {
+            world.add_creep(name, *pos, &[Part::Work, Part::Move]);
+        }
+        mock_world::install(world);
+
+        let mut game_state = GameState::new();
+        let mut memory = mock_world::new_memory();
+        for (name, role, _, _) in creeps {
+            memory
+                .creeps
+                .insert(name.to_string(), CreepMemory::new(*role, room_name()));
+        }
+        game_state.tick_update(&mut memory);
+        for room_name in game_state.rooms.keys().cloned().collect::<Vec<_>>() {
+            game_state.get_or_create_room_state_mut(&room_name);
+        }
+        my_creep_services::organize_creeps(&mut game_state, &mut memory);
+
+        for (name, _, _, move_request) in creeps {
+            game_state.my_creep_states.get_mut(*name).unwrap().move_request = *move_request;
+        }
+        setup(&mut game_state, &mut memory);
+
+        my_creep_services::move_creeps(&mut game_state, &mut memory);
+
+        let power_creep_ends = game_state
+            .my_power_creep_states
+            .iter()
+            .map(|(name, state)| (name.clone(), state.action_pos.unwrap()));
+
+        game_state
+            .my_creep_states
+            .iter()
+            .map(|(name, state)| (name.clone(), state.action_pos.unwrap()))
+            .chain(power_creep_ends)
+            .collect()
+    }
+
+    fn open_room() -> MockWorld {
+        let mut world = MockWorld::new();
+        world.add_room(room_name());
+        world
+    }
+
+    fn at(x: u8, y: u8) -> Position {
+        mock_world::pos(room_name(), x, y)
+    }
+
+    #[test]
+    fn creeps_that_want_each_others_positions_swap() {
+        let ends = run_traffic(
+            open_room(),
+            &[
+                ("a", CreepRole::Hauler, at(10, 25), Some(at(11, 25))),
+                ("b", CreepRole::Upgrader, at(11, 25), Some(at(10, 25))),
+            ],
+            |_, _| {},
+        );
+
+        assert_eq!(ends["a"], at(11, 25));
+        assert_eq!(ends["b"], at(10, 25));
+    }
+
+    #[test]
+    fn a_queue_of_creeps_moves_together() {
+        let ends = run_traffic(
+            open_room(),
+            &[
+                ("a", CreepRole::Hauler, at(10, 25), Some(at(11, 25))),
+                ("b", CreepRole::Hauler, at(11, 25), Some(at(12, 25))),
+                ("c", CreepRole::Hauler, at(12, 25), Some(at(13, 25))),
+            ],
+            |_, _| {},
+        );
+
+        assert_eq!(ends["a"], at(11, 25));
+        assert_eq!(ends["b"], at(12, 25));
+        assert_eq!(ends["c"], at(13, 25));
+    }
+
+    #[test]
+    fn idle_creeps_are_shoved_aside() {
+        let ends = run_traffic(
+            open_room(),
+            &[
+                ("hauler", CreepRole::Hauler, at(10, 25), Some(at(11, 25))),
+                ("upgrader", CreepRole::Upgrader, at(11, 25), None),
+            ],
+            |_, _| {},
+        );
+
+        assert_eq!(ends["hauler"], at(11, 25));
+        assert_ne!(ends["upgrader"], at(10, 25));
+        assert_eq!(ends["upgrader"].get_range_to(at(11, 25)), 1);
+    }
+
+    #[test]
+    fn lower_priority_creeps_wait_for_idle_higher_priority_ones() {
+        let ends = run_traffic(
+            open_room(),
+            &[
+                ("hauler", CreepRole::Hauler, at(10, 25), Some(at(11, 25))),
+                ("filler", CreepRole::FastFill, at(11, 25), None),
+            ],
+            |_, _| {},
+        );
+
+        assert_eq!(ends["hauler"], at(10, 25));
+        assert_eq!(ends["filler"], at(11, 25));
+    }
+
+    #[test]
+    fn harvesters_keep_their_harvest_positions() {
+        let ends = run_traffic(
+            open_room(),
+            &[
+                ("antifa", CreepRole::Antifa, at(10, 25), Some(at(11, 25))),
+                ("harvester", CreepRole::SourceHarvester, at(11, 25), None),
+            ],
+            |game_state, _| {
+                game_state.my_creep_states.get_mut("harvester").unwrap().harvest_pos = Some(at(11, 25));
+            },
+        );
+
+        assert_eq!(ends["antifa"], at(10, 25));
+        assert_eq!(ends["harvester"], at(11, 25));
+    }
+
+    #[test]
+    fn rampart_only_creeps_are_only_shoved_onto_ramparts() {
+        let creeps = [
+            ("hauler", CreepRole::Hauler, at(10, 25), Some(at(11, 25))),
+            ("defender", CreepRole::Hauler, at(11, 25), None),
+        ];
+        let rampart_only = |_: &mut GameState, memory: &mut GameMemory| {
+            memory.creeps.get_mut("defender").unwrap().rampart_only_shoving = Some(true);
+        };
+
+        let ends = run_traffic(open_room(), &creeps, rampart_only);
+
+        assert_eq!(ends["hauler"], at(10, 25));
+        assert_eq!(ends["defender"], at(11, 25));
+
+        let mut world = open_room();
+        world.add_structure(StructureType::Rampart, at(12, 24));
+        let ends = run_traffic(world, &creeps, rampart_only);
+
+        assert_eq!(ends["hauler"], at(11, 25));
+        assert_eq!(ends["defender"], at(12, 24));
+    }
+
+    fn west_room_name() -> RoomName {
+        RoomName::new("W2N1").unwrap()
+    }
+
+    fn two_rooms() -> MockWorld {
+        let mut world = open_room();
+        world.add_room(west_room_name());
+        world
+    }
+
+    #[test]
+    fn creeps_moving_onto_exits_make_room_where_they_arrive() {
+        let arrival = mock_world::pos(west_room_name(), 49, 25);
+        let ends = run_traffic(
+            two_rooms(),
+            &[
+                ("hauler", CreepRole::Hauler, at(1, 25), Some(at(0, 25))),
+                ("upgrader", CreepRole::Upgrader, arrival, None),
+            ],
+            |_, _| {},
+        );
+
+        assert_eq!(ends["hauler"], at(0, 25));
+        assert_eq!(ends["upgrader"].room_name(), west_room_name());
+        assert_eq!(ends["upgrader"].x().u8(), 48);
+    }
+
+    #[test]
+    fn creeps_waiting_on_exits_keep_where_they_arrive() {
+        let arrival = mock_world::pos(west_room_name(), 49, 25);
+        let inside = mock_world::pos(west_room_name(), 48, 25);
+        let ends = run_traffic(
+            two_rooms(),
+            &[
+                ("a", CreepRole::Hauler, at(0, 25), Some(arrival)),
+                ("b", CreepRole::Hauler, inside, Some(arrival)),
+            ],
+            |_, _| {},
+        );
+
+        assert_eq!(ends["a"], at(0, 25));
+        assert_eq!(ends["b"], inside);
+    }
+
+    #[test]
+    fn creeps_on_exits_step_off_the_edge_instead_of_along_it() {
+        let ends = run_traffic(
+            two_rooms(),
+            &[("a", CreepRole::Hauler, at(0, 25), Some(at(0, 26)))],
+            |_, _| {},
+        );
+
+        assert!(!is_xy_exit(ends["a"].x().u8(), ends["a"].y().u8()));
+        assert_eq!(ends["a"].get_range_to(at(0, 26)), 1);
+    }
+
+    #[test]
+    fn power_creeps_shove_and_are_shoved_by_creeps() {
+        let mut world = open_room();
+        world.add_power_creep("operator", at(10, 25));
+        let ends = run_traffic(
+            world,
+            &[("upgrader", CreepRole::Upgrader, at(11, 25), None)],
+            |game_state, _| {
+                game_state.my_power_creep_states.get_mut("operator").unwrap().move_request = Some(at(11, 25));
+            },
+        );
+
+        assert_eq!(ends["operator"], at(11, 25));
+        assert_ne!(ends["upgrader"], at(11, 25));
+        assert!(mock_world::with(|mock| mock.intents.iter().any(|intent| matches!(
+            intent,
+            Intent::PowerCreepMove { power_creep_name, .. } if power_creep_name == "operator"
+        ))));
+
+        let mut world = open_room();
+        world.add_power_creep("operator", at(11, 25));
+        let ends = run_traffic(
+            world,
+            &[("antifa", CreepRole::Antifa, at(10, 25), Some(at(11, 25)))],
+            |_, _| {},
+        );
+
+        assert_eq!(ends["antifa"], at(11, 25));
+        assert_ne!(ends["operator"], at(11, 25));
+    }
+
+    #[test]
+    fn lower_priority_creeps_wait_for_idle_power_creeps() {
+        let mut world = open_room();
+        world.add_power_creep("operator", at(11, 25));
+        let ends = run_traffic(
+            world,
+            &[("hauler", CreepRole::Hauler, at(10, 25), Some(at(11, 25)))],
+            |_, _| {},
+        );
+
+        assert_eq!(ends["hauler"], at(10, 25));
+        assert_eq!(ends["operator"], at(11, 25));
+    }
+
+    #[test]
+    fn failed_shove_chains_leave_creeps_where_they_are() {
+        // A dead end off the west exit, full of creeps
+        let map = (0..50u8)
+            .map(|y| {
+                (0..50u8)
+                    .map(|x| if y == 25 && x <= 3 { '.' } else { '#' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        let ends = run_traffic(
+            world_from_map(room_name(), &map),
+            &[
+                ("hauler", CreepRole::Hauler, at(0, 25), Some(at(1, 25))),
+                ("a", CreepRole::Upgrader, at(1, 25), None),
+                ("b", CreepRole::Upgrader, at(2, 25), None),
+                ("c", CreepRole::Upgrader, at(3, 25), None),
+            ],
+            |_, _| {},
+        );
+
+        // Nobody keeps a claim from the chain that failed, nor swaps onto a position another creep is taking
+        assert_eq!(ends["hauler"], at(0, 25));
+        assert_eq!(ends["a"], at(1, 25));
+        assert_eq!(ends["b"], at(2, 25));
+        assert_eq!(ends["c"], at(3, 25));
+    }
+
+    #[test]
+    fn spawning_creeps_are_not_shoved() {
+        let ends = run_traffic(
+            open_room(),
+            &[
+                ("hauler", CreepRole::Hauler, at(10, 25), Some(at(11, 25))),
+                ("upgrader", CreepRole::Upgrader, at(11, 25), None),
+            ],
+            |game_state, _| {
+                game_state.my_creep_states.get_mut("upgrader").unwrap().spawning = true;
+            },
+        );
+
+        assert_eq!(ends["hauler"], at(10, 25));
+        assert_eq!(ends["upgrader"], at(11, 25));
+    }
+}
@@ -5,5 +5,6 @@ pub mod my_creep;
 pub mod my_creep_ops;
 pub mod my_creep_services;
 pub mod owned_creep;
+pub mod renewal_ops;
 pub mod role_services;
 pub mod roles;
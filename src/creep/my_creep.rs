@@ -1,24 +1,25 @@
-use screeps::{Creep, HasPosition, SharedCreepProperties};
-
-use crate::state::{game::GameState, my_creep::MyCreepState};
+use crate::{
+    state::{game::GameState, my_creep::MyCreepState},
+    world::objects::CreepData,
+};
 
 use super::creep_functions::CreepFunctions;
 
 #[derive(Clone, Debug)]
 #[repr(transparent)]
-pub struct MyCreep(Creep);
+pub struct MyCreep(CreepData);
 
 impl MyCreep {
-    pub fn new(creep: &Creep) -> Result<Self, ()> {
-        if creep.my() {
-            Ok(Self(creep.clone()))
+    pub fn new(creep: CreepData) -> Result<Self, ()> {
+        if creep.my {
+            Ok(Self(creep))
         } else {
             Err(())
         }
     }
 
     #[inline]
-    pub fn inner(&self) -> &Creep {
+    pub fn inner(&self) -> &CreepData {
         &self.0
     }
 
@@ -1,5 +1,5 @@
 use log::{info, warn};
-use screeps::{ErrorCode, HasPosition, ObjectId, Part, SharedCreepProperties, Source};
+use screeps::{ErrorCode, ObjectId, Part};
 
 use crate::{
     constants::creep::{
@@ -10,6 +10,7 @@ use crate::{
     memory::game_memory::GameMemory,
     pathfinding::{PathfindingOpts, room_pather_multi::PathGoals, room_pather_single::PathGoal},
     state::{game::GameState, my_creep::MyCreepState},
+    world::{self, objects::SourceData},
 };
 
 use super::{creep_move_ops, roles::source_harvester_ops};
@@ -18,7 +19,7 @@ use super::{creep_move_ops, roles::source_harvester_ops};
 
 pub fn drop_harvest(
     creep_name: &String,
-    source: &Source,
+    source: &SourceData,
     game_state: &mut GameState,
     memory: &mut GameMemory,
 ) -> CreepOperationResult {
@@ -31,11 +32,11 @@ pub fn drop_harvest(
     // If we are adjacent to the source, try to harvest it
     if my_creep_state.pos == harvest_pos {
         // If the source has no energy in it, don't try to harvest it
-        if source.energy() == 0 {
+        if source.energy == 0 {
             return CreepOperationResult::InProgress;
         };
 
-        return match creep.inner().harvest(source) {
+        return match world::creep_harvest(creep_name, source.id) {
             Ok(e) => {
                 // let creep_state = game_state.creep_states.get(creep_name).unwrap();
                 // let parts = any_creep_ops::parts
@@ -61,11 +62,14 @@ pub fn drop_harvest(
         "creep is moving to harvest pos {}",
         harvest_pos
     );
-    // The creep needs to move to the source to harvest it.
+    // The creep needs to move to the source to harvest it, without walking into enemies on the way
+    let mut opts = PathfindingOpts::new();
+    opts.room_pathfinder_opts.avoid_enemy_attackers = true;
+    opts.room_pathfinder_opts.avoid_enemy_creeps = true;
     creep_move_ops::create_move_request(
         creep_name,
         &PathGoal::new(harvest_pos, 0),
-        PathfindingOpts::new(),
+        opts,
         game_state,
         memory,
     );
@@ -92,9 +96,9 @@ pub fn get_parts(creep_name: &str, game_state: &mut GameState) -> Vec<Part> {
     let creep = game_state.creeps.get(creep_name).unwrap();
     let parts: Vec<Part> = creep
         .inner()
-        .body()
+        .body
         .iter()
-        .map(|body_part| body_part.part())
+        .map(|body_part| body_part.part)
         .collect();
 
     let creep_state = game_state.my_creep_states.get_mut(creep_name).unwrap();
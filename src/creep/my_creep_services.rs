@@ -1,14 +1,19 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
 
 use log::{debug, info};
-use screeps::{Creep, Position, RoomName, SharedCreepProperties};
+use screeps::{ObjectId, RoomName, Structure};
 
 use super::{
     creep_move_ops::{self, assign_move_target_as_pos},
-    my_creep_ops,
+    my_creep_ops, renewal_ops,
 };
 use crate::{
-    constants::creep::MoveTargets, memory::game_memory::GameMemory, state::game::GameState,
+    constants::creep::MoveTargets,
+    memory::game_memory::GameMemory,
+    state::game::GameState,
 };
 
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
@@ -27,7 +32,7 @@ pub fn organize_creeps(game_state: &mut GameState, memory: &mut GameMemory) {
     
     for creep_name in creep_names {
         let creep = game_state.creeps.get_mut(&creep_name).unwrap();
-        let room_state = game_state.room_states.get_mut(&creep.inner().room().unwrap().name()).unwrap();
+        let room_state = game_state.room_states.get_mut(&creep.inner().pos.room_name()).unwrap();
         
         let Some(creep_memory) = memory.creeps.get(&creep_name) else {
             continue;
@@ -36,6 +41,57 @@ pub fn organize_creeps(game_state: &mut GameState, memory: &mut GameMemory) {
         room_state.my_creeps.push(creep_name.clone());
         room_state.creeps_by_role[creep_memory.role].push(creep_name);
     }
+
+    for (power_creep_name, power_creep) in &game_state.power_creeps {
+        let Some(room_state) = game_state.room_states.get_mut(&power_creep.pos.room_name()) else {
+            continue;
+        };
+
+        room_state.my_power_creeps.push(power_creep_name.clone());
+    }
+}
+
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+/// Send creeps worth renewing to their commune's spawns, and surplus or obsolete creeps to be recycled
+pub fn renew_and_recycle_creeps(game_state: &mut GameState, memory: &mut GameMemory) {
+    let mut creep_names = game_state.creeps.keys().cloned().collect::<Vec<_>>();
+    creep_names.sort();
+
+    let mut recycling_communes: HashSet<RoomName> = creep_names
+        .iter()
+        .filter(|creep_name| game_state.my_creep_states[*creep_name].recycling)
+        .filter_map(|creep_name| memory.creeps.get(creep_name).map(|creep_memory| creep_memory.room_from))
+        .collect();
+    // A spawn told to spawn this tick can't renew as well
+    let mut renewing_spawns: HashSet<ObjectId<Structure>> = game_state
+        .commune_states
+        .values()
+        .flat_map(|commune_state| commune_state.spawns_used.iter().copied())
+        .collect();
+
+    for creep_name in creep_names {
+        let Some(creep_memory) = memory.creeps.get(&creep_name) else {
+            continue;
+        };
+        let commune_name = creep_memory.room_from;
+        if !game_state.commune_states.contains_key(&commune_name) {
+            continue;
+        }
+        if game_state.my_creep_states[&creep_name].spawning {
+            continue;
+        }
+
+        renewal_ops::update_renewal(&creep_name, &commune_name, &mut recycling_communes, game_state, memory);
+
+        let my_creep_state = &game_state.my_creep_states[&creep_name];
+        let (recycling, renewing) = (my_creep_state.recycling, my_creep_state.renewing);
+
+        if recycling {
+            renewal_ops::try_recycle(&creep_name, &commune_name, game_state, memory);
+        } else if renewing {
+            renewal_ops::try_renew(&creep_name, &commune_name, &mut renewing_spawns, game_state, memory);
+        }
+    }
 }
 
 // Not part of design philosphy
@@ -64,27 +120,30 @@ pub fn clean_creep_memories(game_state: &GameState, memory: &mut GameMemory) {
 
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
 pub fn move_creeps(game_state: &mut GameState, memory: &mut GameMemory) {
-    let room_names = game_state.rooms.keys().cloned().collect::<Vec<_>>();
-    for room_name in room_names {
-        let mut move_targets: HashMap<Position, String> = HashMap::new();
-        
-        register_move_targets(game_state, &room_name, &mut move_targets);
+    // Creeps on edges claim positions in adjacent rooms, so traffic is resolved for every room at once.
+    // Power creeps of ours take part like creeps do
+    let creep_names = game_state
+        .rooms
+        .keys()
+        .filter_map(|room_name| game_state.room_states.get(room_name))
+        .flat_map(|room_state| room_state.my_creeps.iter().chain(room_state.my_power_creeps.iter()).cloned())
+        .collect::<Vec<_>>();
 
-        run_move_requests(game_state, memory, &room_name, &mut move_targets);
-        
-        run_move_targets(&room_name, game_state);
-    }
+    let mut move_targets: MoveTargets = HashMap::new();
+
+    register_move_targets(game_state, &creep_names, &mut move_targets);
+
+    run_move_requests(game_state, memory, creep_names.clone(), &mut move_targets);
+
+    run_move_targets(&creep_names, game_state);
 }
 
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
 fn register_move_targets(
     game_state: &mut GameState,
-    room_name: &RoomName,
+    creep_names: &[String],
     move_targets: &mut MoveTargets,
 ) {
-    let room_state = game_state.room_states.get(room_name).unwrap();
-    let creep_names: Vec<String> = room_state.my_creeps.to_vec();
-    
     for creep_name in creep_names {
         assign_move_target_as_pos(creep_name.as_str(), game_state, move_targets)
     }
@@ -94,27 +153,22 @@ fn register_move_targets(
 fn run_move_requests(
     game_state: &mut GameState,
     memory: &GameMemory,
-    room_name: &RoomName,
+    mut creep_names: Vec<String>,
     move_targets: &mut MoveTargets,
 ) {
-    let room_state = game_state.room_states.get(room_name).unwrap();
-    let creep_names: Vec<String> = room_state.my_creeps.to_vec();
+    // Higher priority creeps move first, so lower priority ones are shoved around them
+    creep_names.sort_by_key(|creep_name| {
+        let priority = creep_move_ops::move_priority(creep_name, game_state, memory);
+
+        (Reverse(priority), creep_name.clone())
+    });
 
     for creep_name in creep_names {
-        creep_move_ops::try_run_move_request(
-            creep_name.as_str(),
-            room_name,
-            game_state,
-            memory,
-            move_targets,
-        );
+        creep_move_ops::try_run_move_request(creep_name.as_str(), game_state, memory, move_targets);
     }
 }
 
-fn run_move_targets(room_name: &RoomName, game_state: &mut GameState) {
-    let room_state = game_state.room_states.get(room_name).unwrap();
-    let creep_names: Vec<String> = room_state.my_creeps.to_vec();
-
+fn run_move_targets(creep_names: &[String], game_state: &mut GameState) {
     for creep_name in creep_names {
         creep_move_ops::try_run_move_target(creep_name.as_str(), game_state);
     }
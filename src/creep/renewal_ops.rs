@@ -0,0 +1,279 @@
+use std::collections::HashSet;
+
+use log::warn;
+use screeps::{ErrorCode, ObjectId, Part, Position, RoomName, Structure, CREEP_LIFE_TIME};
+
+use crate::{
+    constants::spawning::{
+        BODY_SCALES_WITH_ENERGY_BY_ROLE, OBSOLETE_BODY_COST_PERCENT, RECYCLE_SPAWN_USAGE_PERCENT,
+        RENEW_MAX_EXTRA_COST_PERCENT, RENEW_SPAWN_USAGE_PERCENT, RENEW_TICKS_TO_LIVE,
+    },
+    memory::game_memory::GameMemory,
+    pathfinding::{room_pather_single::PathGoal, PathfindingOpts},
+    room::room_ops,
+    state::{commune::CommuneState, game::GameState, stats_segment::CommuneSegmentStats},
+    utils::general::pos_range,
+    world::{self, objects::CreepData},
+};
+
+use super::creep_move_ops;
+
+/// Whether renewing the creep's body beats spawning it fresh.
+/// Bodies the commune can no longer afford are always worth keeping, otherwise renewing only pays off
+/// while spawns are short on time and each tick of life it buys costs little more energy than spawning would
+pub fn renew_beats_spawning(creep: &CreepData, spawn_energy_capacity: u32, spawn_usage_percent: u32) -> bool {
+    let cost = creep.body_cost();
+    if cost > spawn_energy_capacity {
+        return true;
+    }
+
+    if spawn_usage_percent < RENEW_SPAWN_USAGE_PERCENT {
+        return false;
+    }
+
+    // Energy per tick of life from renewing against spawning, cross multiplied to stay in integers
+    creep.renew_energy() * CREEP_LIFE_TIME * 100 <= cost * creep.renew_ticks() * (100 + RENEW_MAX_EXTRA_COST_PERCENT)
+}
+
+/// Creeps near the end of their life with a body worth renewing.
+/// Boosted creeps are left alone as renewing removes boosts, and claim parts can't be renewed
+fn should_renew(creep: &CreepData, commune_state: &CommuneState) -> bool {
+    let Some(ticks_to_live) = creep.ticks_to_live else {
+        return false;
+    };
+    if ticks_to_live > RENEW_TICKS_TO_LIVE {
+        return false;
+    }
+
+    if creep.is_boosted() || creep.body.iter().any(|body_part| body_part.part == Part::Claim) {
+        return false;
+    }
+
+    renew_beats_spawning(creep, commune_state.spawn_energy_capacity, commune_state.spawn_usage_percent)
+}
+
+/// Creeps much smaller than the commune can now spawn, with enough life left that recycling recovers energy
+/// and spawns with time to replace them
+fn is_obsolete(creep: &CreepData, commune_state: &CommuneState) -> bool {
+    if !creep.ticks_to_live.is_some_and(|ticks_to_live| ticks_to_live > RENEW_TICKS_TO_LIVE) {
+        return false;
+    }
+
+    commune_state.spawn_usage_percent < RECYCLE_SPAWN_USAGE_PERCENT
+        && creep.body_cost() * 100 <= commune_state.spawn_energy_capacity * OBSOLETE_BODY_COST_PERCENT
+}
+
+/// Decide whether the creep should leave its work to be renewed or recycled.
+/// Communes recycle one obsolete creep at a time so their work doesn't stop all at once
+pub fn update_renewal(
+    creep_name: &str,
+    commune_name: &RoomName,
+    recycling_communes: &mut HashSet<RoomName>,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+) {
+    let my_creep_state = game_state.my_creep_states.get(creep_name).unwrap();
+    if my_creep_state.renewing || my_creep_state.recycling {
+        return;
+    }
+
+    let role = memory.creeps.get(creep_name).unwrap().role;
+    let creep = game_state.creeps.get(creep_name).unwrap().inner();
+    let commune_state = game_state.commune_states.get(commune_name).unwrap();
+
+    let recycling = !recycling_communes.contains(commune_name)
+        && BODY_SCALES_WITH_ENERGY_BY_ROLE.with(|scales_by_role| scales_by_role[role])
+        && is_obsolete(creep, commune_state);
+    // Only renew at home, a trip across rooms would cost more than the renewal saves
+    let renewing = !recycling && creep.pos.room_name() == *commune_name && should_renew(creep, commune_state);
+
+    if recycling {
+        recycling_communes.insert(*commune_name);
+    }
+
+    let my_creep_state = game_state.my_creep_states.get_mut(creep_name).unwrap();
+    my_creep_state.recycling = recycling;
+    my_creep_state.renewing = renewing;
+}
+
+/// Move next to the nearest spawn free to renew this tick and renew until topped up
+pub fn try_renew(
+    creep_name: &str,
+    commune_name: &RoomName,
+    renewing_spawns: &mut HashSet<ObjectId<Structure>>,
+    game_state: &mut GameState,
+    memory: &mut GameMemory,
+) {
+    let creep_pos = game_state.my_creep_states.get(creep_name).unwrap().pos;
+
+    let spawn = room_ops::structures_by_type(commune_name, game_state)
+        .spawn
+        .iter()
+        .filter(|spawn| spawn.spawning.is_none() && !renewing_spawns.contains(&spawn.id))
+        .min_by_key(|spawn| pos_range(&spawn.pos, &creep_pos))
+        .map(|spawn| (spawn.id, spawn.pos));
+
+    // Every spawn is busy, so wait for one
+    let Some((spawn_id, spawn_pos)) = spawn else {
+        return;
+    };
+
+    if pos_range(&creep_pos, &spawn_pos) > 1 {
+        let _ = creep_move_ops::create_move_request(
+            creep_name,
+            &PathGoal::new(spawn_pos, 1),
+            PathfindingOpts::new(),
+            game_state,
+            memory,
+        );
+        return;
+    }
+
+    let creep = game_state.creeps.get(creep_name).unwrap().inner();
+    let renew_energy = creep.renew_energy();
+    let renew_ticks = creep.renew_ticks();
+    let ticks_to_live = creep.ticks_to_live.unwrap_or(0);
+
+    match world::renew_creep(spawn_id, creep_name) {
+        Ok(()) => {
+            renewing_spawns.insert(spawn_id);
+
+            game_state.segments.stats.intents += 1;
+            game_state
+                .segments
+                .stats
+                .communes
+                .entry(*commune_name)
+                .or_insert_with(CommuneSegmentStats::new)
+                .energy_out_renew += renew_energy;
+
+            // Stop once another renew would overflow the creep's life
+            if ticks_to_live + renew_ticks * 2 > CREEP_LIFE_TIME {
+                game_state.my_creep_states.get_mut(creep_name).unwrap().renewing = false;
+            }
+        }
+        // Topped up, or the commune can't afford it and the creep is left to be replaced
+        Err(ErrorCode::Full) | Err(ErrorCode::NotEnough) => {
+            game_state.my_creep_states.get_mut(creep_name).unwrap().renewing = false;
+        }
+        Err(e) => {
+            warn!("creep {} unexpected error {:?} when renewing", creep_name, e);
+        }
+    }
+}
+
+/// Move onto a container next to a spawn, so the recycled energy is caught for haulers, and recycle there.
+/// Without such a container the creep is recycled next to the nearest spawn
+pub fn try_recycle(creep_name: &str, commune_name: &RoomName, game_state: &mut GameState, memory: &mut GameMemory) {
+    let creep_pos = game_state.my_creep_states.get(creep_name).unwrap().pos;
+
+    let structures = room_ops::structures_by_type(commune_name, game_state);
+    let spawns: Vec<(ObjectId<Structure>, Position)> =
+        structures.spawn.iter().map(|spawn| (spawn.id, spawn.pos)).collect();
+
+    let container_pos = structures
+        .container
+        .iter()
+        .map(|container| container.pos)
+        .filter(|pos| spawns.iter().any(|(_, spawn_pos)| pos_range(spawn_pos, pos) <= 1))
+        .min_by_key(|pos| pos_range(pos, &creep_pos));
+
+    let goal = match container_pos {
+        Some(container_pos) => PathGoal::new(container_pos, 0),
+        None => {
+            let Some((_, spawn_pos)) = spawns.iter().min_by_key(|(_, spawn_pos)| pos_range(spawn_pos, &creep_pos))
+            else {
+                return;
+            };
+
+            PathGoal::new(*spawn_pos, 1)
+        }
+    };
+
+    if pos_range(&creep_pos, &goal.pos) > goal.range as u32 {
+        let _ = creep_move_ops::create_move_request(creep_name, &goal, PathfindingOpts::new(), game_state, memory);
+        return;
+    }
+
+    let Some((spawn_id, _)) = spawns.iter().find(|(_, spawn_pos)| pos_range(spawn_pos, &creep_pos) <= 1) else {
+        return;
+    };
+
+    match world::recycle_creep(*spawn_id, creep_name) {
+        Ok(()) => {
+            game_state.segments.stats.intents += 1;
+        }
+        Err(e) => {
+            warn!("creep {} unexpected error {:?} when recycling", creep_name, e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use screeps::{Part, RoomName, StructureType};
+
+    use crate::{
+        constants::creep::CreepRole,
+        memory::creep_memory::CreepMemory,
+        world::mock_world::{self, new_creep, pos, Intent, MockWorld, MOCK_USERNAME},
+    };
+
+    use super::{renew_beats_spawning, try_recycle, update_renewal};
+
+    #[test]
+    fn renews_unaffordable_bodies_and_otherwise_only_under_spawn_pressure() {
+        let room_name = RoomName::new("W1N1").unwrap();
+        let body = [Part::Work, Part::Work, Part::Work, Part::Work, Part::Work, Part::Move];
+        let creep = new_creep("harvester", None, MOCK_USERNAME, pos(room_name, 25, 25), &body);
+
+        // 550 energy of body
+        assert!(renew_beats_spawning(&creep, 300, 0));
+        assert!(!renew_beats_spawning(&creep, 800, 50));
+        assert!(renew_beats_spawning(&creep, 800, 90));
+    }
+
+    #[test]
+    fn obsolete_creeps_are_recycled_on_the_container_by_the_spawn() {
+        let room_name = RoomName::new("W1N1").unwrap();
+        let mut world = MockWorld::with_commune(room_name);
+        world.add_structure(StructureType::Container, pos(room_name, 26, 26));
+        world.add_creep("old_hauler", pos(room_name, 26, 26), &[Part::Carry, Part::Move]);
+        world.add_creep("old_builder", pos(room_name, 30, 30), &[Part::Carry, Part::Move]);
+        let (mut game_state, mut memory) = mock_world::install_commune(world, room_name);
+        memory
+            .creeps
+            .insert("old_hauler".to_string(), CreepMemory::new(CreepRole::Hauler, room_name));
+        memory
+            .creeps
+            .insert("old_builder".to_string(), CreepMemory::new(CreepRole::Builder, room_name));
+
+        let mut recycling_communes = HashSet::new();
+        update_renewal("old_hauler", &room_name, &mut recycling_communes, &mut game_state, &memory);
+        update_renewal("old_builder", &room_name, &mut recycling_communes, &mut game_state, &memory);
+
+        // One obsolete creep at a time
+        assert!(game_state.my_creep_states["old_hauler"].recycling);
+        assert!(!game_state.my_creep_states["old_builder"].recycling);
+
+        try_recycle("old_hauler", &room_name, &mut game_state, &mut memory);
+
+        let spawn = mock_world::with(|mock| {
+            mock.rooms[&room_name]
+                .structures
+                .iter()
+                .find(|structure| structure.structure_type == StructureType::Spawn)
+                .unwrap()
+                .id
+        });
+        let recycled = mock_world::with(|mock| {
+            mock.intents.iter().any(|intent| {
+                matches!(intent, Intent::RecycleCreep { spawn: intent_spawn, creep_name }
+                    if *intent_spawn == spawn && creep_name == "old_hauler")
+            })
+        });
+        assert!(recycled);
+    }
+}
@@ -26,6 +26,11 @@ pub fn try_scouts(game_state: &mut GameState, memory: &mut GameMemory) {
 
         let creep_names = room_state.creeps_by_role[CreepRole::Scout].to_vec();
         for creep_name in creep_names {
+            let my_creep_state = game_state.my_creep_states.get(creep_name.as_str()).unwrap();
+            if my_creep_state.renewing || my_creep_state.recycling {
+                continue;
+            }
+
             scout_ops::try_scout(&creep_name, &room_name, game_state, memory);
         }
     }
@@ -62,6 +67,13 @@ pub fn register_commune_harvest_strength(game_state: &mut GameState, memory: &mu
                 memory,
             )
         }
+
+        // Once every harvester is registered we know which are surplus
+
+        let room_state = game_state.room_states.get(&room_name).unwrap();
+        for creep_name in room_state.creeps_by_role[CreepRole::SourceHarvester].clone() {
+            source_harvester_ops::try_mark_surplus(creep_name.as_str(), &room_name, game_state, memory);
+        }
     }
 }
 
@@ -72,7 +84,7 @@ pub fn try_harvest_commune_sources(game_state: &mut GameState, memory: &mut Game
         let room_state = game_state.room_states.get(&room_name).unwrap();
         for creep_name in room_state.creeps_by_role[CreepRole::SourceHarvester].clone() {
             let my_creep_state = game_state.my_creep_states.get_mut(creep_name.as_str()).unwrap();
-            if my_creep_state.spawning {
+            if my_creep_state.spawning || my_creep_state.renewing || my_creep_state.recycling {
                 continue;
             }
 
@@ -1,10 +1,10 @@
-use screeps::{game::map::describe_exits, RoomName};
+use screeps::RoomName;
 
 use crate::{
     memory::{creep_memory, game_memory::GameMemory, room_memory::StaticRoomType},
     room::room_ops,
     state::game::GameState,
-    utils,
+    utils, world,
 };
 
 pub fn try_register_scout_target(
@@ -53,8 +53,8 @@ fn find_scout_target(
 
     let room_status = room_ops::room_status(room_name, game_state);
 
-    let exits = describe_exits(*room_name);
-    for (direction, exit_room_name) in exits.entries() {
+    let exits = world::exits(*room_name);
+    for (direction, exit_room_name) in exits {
         // If the room statuses do not match
         if room_status != room_ops::room_status(&exit_room_name, game_state) {
             continue;
@@ -1,10 +1,10 @@
-use screeps::{ObjectId, Position, RoomName, SharedCreepProperties};
+use screeps::{ObjectId, Position, RoomName};
 
 use crate::{
     constants::creep::CreepPart,
     creep::{any_creep_ops, my_creep_ops},
     memory::game_memory::GameMemory,
-    room::room_ops,
+    room::{commune::spawning::spawn_request_arg_services, room_ops},
     state::game::GameState,
 };
 
@@ -19,17 +19,83 @@ pub fn register_source(
         return;
     };
 
-    // Register harvest strength
+    // Creeps on their way to be recycled leave their harvest position and strength to others
+
+    let my_creep_state = game_state.my_creep_states.get_mut(creep_name).unwrap();
+    if my_creep_state.recycling {
+        my_creep_state.harvest_pos = None;
+        return;
+    }
+
+    // Register source position
+    try_register_harvest_pos(creep_name, room_name, source_index, game_state, memory);
+
+    // Register harvest strength, unless the creep is old enough that its replacement should be spawning
+
+    let work_pos = game_state
+        .my_creep_states
+        .get(creep_name)
+        .unwrap()
+        .harvest_pos
+        .or_else(|| {
+            memory
+                .harvestable_rooms
+                .get(room_name)
+                .and_then(|harvestable_room_memory| harvestable_room_memory.source_positions.get(source_index).copied())
+        });
+    if let Some(work_pos) = work_pos {
+        if !spawn_request_arg_services::counts_toward_strength(creep_name, &work_pos, room_name, game_state, memory) {
+            return;
+        }
+    }
 
-    let creep = game_state.creeps.get(creep_name).unwrap();
     let work_parts = my_creep_ops::get_parts_by_type(creep_name, game_state)[CreepPart::Work];
 
     let commune_state = game_state.commune_states.get_mut(room_name).unwrap();
     commune_state.source_harvest_strengths[source_index] += work_parts;
     commune_state.source_harvest_creeps[source_index] += 1;
+}
 
-    // Register source position
-    try_register_harvest_pos(creep_name, room_name, source_index, game_state, memory)
+/// Harvesters left without a harvest position while the rest already meet their source's work need are surplus,
+/// so they are sent to be recycled
+pub fn try_mark_surplus(creep_name: &str, room_name: &RoomName, game_state: &mut GameState, memory: &GameMemory) {
+    let my_creep_state = game_state.my_creep_states.get(creep_name).unwrap();
+    if my_creep_state.spawning || my_creep_state.recycling || my_creep_state.harvest_pos.is_some() {
+        return;
+    }
+
+    let Some(source_index) = memory.creeps.get(creep_name).unwrap().source_index else {
+        return;
+    };
+
+    // Only when every harvest position is taken, not when they haven't loaded yet
+    let Some(harvest_positions) = room_ops::harvest_positions(room_name, game_state, memory) else {
+        return;
+    };
+    let Some(source_harvest_positions) = harvest_positions.get(source_index) else {
+        return;
+    };
+    let commune_state = game_state.commune_states.get(room_name).unwrap();
+    if !source_harvest_positions
+        .iter()
+        .all(|pos| commune_state.reserved_positions.contains(pos))
+    {
+        return;
+    }
+
+    let work_parts = my_creep_ops::get_parts_by_type(creep_name, game_state)[CreepPart::Work];
+
+    let commune_state = game_state.commune_states.get_mut(room_name).unwrap();
+    let strength = commune_state.source_harvest_strengths[source_index];
+    if strength.saturating_sub(work_parts) < spawn_request_arg_services::source_work_need() {
+        return;
+    }
+
+    commune_state.source_harvest_strengths[source_index] -= work_parts;
+    commune_state.source_harvest_creeps[source_index] =
+        commune_state.source_harvest_creeps[source_index].saturating_sub(1);
+
+    game_state.my_creep_states.get_mut(creep_name).unwrap().recycling = true;
 }
 
 fn try_register_harvest_pos(
@@ -51,10 +117,11 @@ fn try_register_harvest_pos(
 
     // Try to find a new harvest pos
 
-    let harvestable_room_memory = memory.harvestable_rooms.get(room_name).unwrap();
-
     let room_state = game_state.room_states.get(room_name).unwrap();
-    let harvest_positions = room_ops::harvest_positions(room_name, game_state, memory).unwrap();
+    // Harvestable room memory may not have loaded from its segments yet
+    let Some(harvest_positions) = room_ops::harvest_positions(room_name, game_state, memory) else {
+        return;
+    };
 
     let harvest_positions = &harvest_positions[source_index];
     let mut harvest_pos: Option<Position> = None;
@@ -92,11 +159,8 @@ pub fn try_harvest(creep_name: &str, game_state: &mut GameState, memory: &mut Ga
     let creep = game_state.creeps.get(creep_name).unwrap();
     let creep_memory = memory.creeps.get(creep_name).unwrap();
 
-    let room = game_state
-        .rooms
-        .get(&creep.inner().room().unwrap().name().clone())
-        .unwrap();
-    let sources = room_ops::get_sources(&room.name(), game_state);
+    let room_name = creep.inner().pos.room_name();
+    let sources = room_ops::get_sources(&room_name, game_state);
 
     let Some(source_index) = creep_memory.source_index else {
         return;
@@ -1,7 +1,12 @@
 use log::error;
-use screeps::{HasPosition, RoomName, RoomVisual};
+use screeps::RoomName;
 
-use crate::{memory::game_memory::GameMemory, room::room_ops, state::game::GameState};
+use crate::{
+    memory::game_memory::GameMemory,
+    room::room_ops,
+    state::game::GameState,
+    world::{self, objects::WorldVisual},
+};
 
 pub fn run_flags(game_state: &mut GameState, memory: &mut GameMemory) {
     // Implement flag-related logic here
@@ -16,7 +21,7 @@ pub fn run_flags(game_state: &mut GameState, memory: &mut GameMemory) {
 
         let flag_pos = {
             let flag = game_state.flags.get(&flag_name).unwrap();
-            flag.pos()
+            flag.pos
         };
 
         match *flag_type {
@@ -37,8 +42,6 @@ fn visualize_reserved_positions(
     game_state: &mut GameState,
     memory: &mut GameMemory,
 ) {
-    let room_visual = RoomVisual::new(Some(room_name));
-    
     let Some(commune_state) = game_state.commune_states.get(&room_name) else {
         error!(
             "Placed reserved_positions flag in invalid room {}",
@@ -48,7 +51,13 @@ fn visualize_reserved_positions(
     };
 
     for pos in &commune_state.reserved_positions {
-        room_visual.circle(pos.x().0 as f32, pos.y().0 as f32, None);
+        world::draw(
+            room_name,
+            WorldVisual::Circle {
+                x: pos.x().0 as f32,
+                y: pos.y().0 as f32,
+            },
+        );
     }
 }
 
@@ -58,8 +67,6 @@ fn visualize_harvest_positions(
     game_state: &mut GameState,
     memory: &mut GameMemory,
 ) {
-    let room_visual = RoomVisual::new(Some(room_name));
-
     let Some(harvest_positions) = room_ops::harvest_positions(&room_name, game_state, memory)
     else {
         error!(
@@ -71,7 +78,13 @@ fn visualize_harvest_positions(
 
     for vec in harvest_positions {
         for pos in vec {
-            room_visual.circle(pos.x().0 as f32, pos.y().0 as f32, None);
+            world::draw(
+                room_name,
+                WorldVisual::Circle {
+                    x: pos.x().0 as f32,
+                    y: pos.y().0 as f32,
+                },
+            );
         }
     }
 }
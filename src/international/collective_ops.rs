@@ -1,4 +1,4 @@
-use crate::{constants::general::{GeneralResult, NON_COMMUNE_SIGNS}, memory::game_memory::GameMemory, state::game::GameState};
+use crate::{constants::general::{GeneralResult, NON_COMMUNE_SIGNS}, memory::game_memory::GameMemory, state::game::GameState, world};
 
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
 pub fn funnel_order(game_state: &GameState) {}
@@ -6,7 +6,7 @@ pub fn funnel_order(game_state: &GameState) {}
 pub fn kill_all_creeps(game_state: &GameState) {
     let creeps = &game_state.creeps;
     for (creep_name, creep) in creeps {
-        let _ = creep.inner().suicide();
+        let _ = world::creep_suicide(creep_name);
     }
 }
 
@@ -1,12 +1,13 @@
 use std::collections::{HashMap, HashSet};
 
-use screeps::{game, ConstructionSite, MaybeHasId, ObjectId};
+use screeps::{ConstructionSite, ObjectId};
 
 use crate::{
     constants::general::{CONSTRUCTION_PROGRESS_AGE_MULTIPLIER, MIN_CONSTRUCTION_SITE_AGE},
     memory::game_memory::GameMemory,
     state::game::GameState,
     utils::{self, general::GeneralUtils},
+    world::{self, objects::ConstructionSiteData},
 };
 
 /// Register new construction sites,
@@ -25,14 +26,11 @@ pub fn manage_sites(game_state: &mut GameState, memory: &mut GameMemory) {
     }
 
     /// Construction site ids that are known to exist
-    let mut construction_sites: HashMap<ObjectId<ConstructionSite>, ConstructionSite> =
+    let mut construction_sites: HashMap<ObjectId<ConstructionSite>, ConstructionSiteData> =
         HashMap::new();
-    let js_construction_sites = game::construction_sites();
 
-    // Move the values from a js hashmap to the rust hashmap
-
-    for site in js_construction_sites.values() {
-        let Some(id) = site.try_id() else {
+    for site in world::my_construction_sites() {
+        let Some(id) = site.id else {
             continue;
         };
         construction_sites.insert(id, site);
@@ -46,8 +44,8 @@ pub fn manage_sites(game_state: &mut GameState, memory: &mut GameMemory) {
             return false;
         };
 
-        if *age > max_site_age(site.progress()) {
-            site.remove();
+        if *age > max_site_age(site.progress) {
+            let _ = world::remove_construction_site(*id);
             return false;
         }
 
@@ -56,7 +54,7 @@ pub fn manage_sites(game_state: &mut GameState, memory: &mut GameMemory) {
 
     // Increment the age of cached construction sites
 
-    for (id, age) in &mut memory.construction_sites {
+    for (id, age) in memory.construction_sites.iter_mut() {
         // times inveral because we only run the code every interval, but we cant to track how many ticks have passed
         *age += game_state.intervals.construction_sites_update;
     }
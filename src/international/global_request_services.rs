@@ -1,8 +1,8 @@
 use js_sys::global;
-use screeps::{game, HasPosition};
+use screeps::HasPosition;
 
 use crate::{
-    memory::game_memory::GameMemory, state::game::GameState, utils::{self, general::GeneralUtils},
+    memory::game_memory::GameMemory, state::game::GameState, utils::{self, general::GeneralUtils}, world,
 };
 
 use super::global_request_ops;
@@ -26,9 +26,12 @@ fn update_requests(game_state: &GameState, memory: &mut GameMemory) {
 }
 
 fn update_claim_requests(game_state: &GameState, memory: &mut GameMemory) {
-    let requests = &mut memory.claim_requests;
+    // Only borrow the requests mutably if one is counting down, so the section is not needlessly rewritten
+    if !memory.claim_requests.values().any(|request| request.abandon.is_some_and(|abandon| abandon > 0)) {
+        return;
+    }
 
-    for (room_name, request) in requests {
+    for (room_name, request) in memory.claim_requests.iter_mut() {
         if let Some(mut abandon) = request.abandon {
             if abandon > 0 {
                 request.abandon = Some(abandon - 1);
@@ -38,9 +41,12 @@ fn update_claim_requests(game_state: &GameState, memory: &mut GameMemory) {
 }
 
 fn update_work_requests(game_state: &GameState, memory: &mut GameMemory) {
-    let requests = &mut memory.work_requests;
+    // Only borrow the requests mutably if one is counting down, so the section is not needlessly rewritten
+    if !memory.work_requests.values().any(|request| request.abandon.is_some_and(|abandon| abandon > 0)) {
+        return;
+    }
 
-    for (room_name, request) in requests {
+    for (room_name, request) in memory.work_requests.iter_mut() {
         if let Some(mut abandon) = request.abandon {
             if abandon > 0 {
                 request.abandon = Some(abandon - 1);
@@ -64,20 +70,20 @@ fn try_assign_requests(game_state: &GameState, memory: &mut GameMemory) {
 // Use portal router to pathfindg to the closest commune
 
 fn try_assign_claim_requests(game_state: &GameState, memory: &mut GameMemory) {
-    let gcl_level = game::gcl::level();
+    let gcl_level = world::control_levels().gcl_level;
     // If our capacity to claim is more or equal to our claim count, stop
     if gcl_level >= game_state.communes.len() as u32 {
         return;
     }
 
-    let claim_requests = &memory.claim_requests;
+    let claim_requests = memory.claim_requests.iter();
     for (room_name, request) in claim_requests {
         // Find a commune in range
     }
 }
 
 fn try_assign_work_requests(game_state: &GameState, memory: &mut GameMemory) {
-    let work_requests = &memory.work_requests;
+    let work_requests = memory.work_requests.iter();
 
     for (room_name, request) in work_requests {
         if request.is_abandoned() {
@@ -91,7 +97,7 @@ fn try_assign_work_requests(game_state: &GameState, memory: &mut GameMemory) {
 fn try_assign_attack_requests(game_state: &GameState, memory: &mut GameMemory) {
     // TODO: implement
 
-    let combat_requests = &memory.attack_requests;
+    let combat_requests = memory.attack_requests.iter();
 
     for (room_name, request) in combat_requests {
         if request.is_abandoned() {
@@ -105,7 +111,7 @@ fn try_assign_attack_requests(game_state: &GameState, memory: &mut GameMemory) {
 fn try_assign_defense_requests(game_state: &GameState, memory: &mut GameMemory) {
     // TODO: implement
 
-    let defense_requests = &memory.defense_requests;
+    let defense_requests = memory.defense_requests.iter();
 
     for (room_name, request) in defense_requests {
         if request.is_abandoned() {
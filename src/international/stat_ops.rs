@@ -11,11 +11,11 @@ use crate::state::game::GameState;
                 continue;
             };
 
-            let Some(controller) = room.controller() else {
+            let Some(controller) = &room.controller else {
                 continue;
             };
 
-            combined_rcl += controller.level() as u32;
+            combined_rcl += controller.level as u32;
         }
 
         combined_rcl
@@ -1,6 +1,6 @@
-use screeps::{control, game, raw_memory, RoomName};
+use screeps::RoomName;
 
-use crate::{constants::segments::STATS_SEGMENT, memory::game_memory::GameMemory, state::game::GameState, utils::general::is_tick_interval};
+use crate::{constants::segments::STATS_SEGMENT, memory::game_memory::GameMemory, state::game::GameState, utils::general::is_tick_interval, world};
 
 use super::stat_ops;
 
@@ -9,14 +9,16 @@ use super::stat_ops;
 pub fn tick_update(game_state: &mut GameState, memory: &mut GameMemory) {
     {
         let stats = &mut game_state.segments.stats;
+        let control_levels = world::control_levels();
 
-        stats.gcl_progress = game::gcl::progress() as u64;
-        stats.gcl_total = game::gcl::progress_total() as u64;
-        stats.gpl_progress = game::gpl::progress() as u64;
-        stats.gpl_total = game::gpl::progress_total() as u64;
+        stats.gcl_progress = control_levels.gcl_progress as u64;
+        stats.gcl_total = control_levels.gcl_progress_total as u64;
+        stats.gpl_progress = control_levels.gpl_progress as u64;
+        stats.gpl_total = control_levels.gpl_progress_total as u64;
         stats.total_creeps = game_state.creeps.len() as u32;
         stats.intents = 0;
         stats.energy_harvested = 0;
+        stats.stuck_repaths = 0;
     }
 
     game_state.segments.stats.combined_rcl = stat_ops::find_combined_rcl(game_state)
@@ -44,5 +46,5 @@ pub fn try_write_stats(game_state: &mut GameState, memory: &mut GameMemory) {
         return;
     };
 
-    raw_memory::segments().set(STATS_SEGMENT, serde_json::to_string(&game_state.segments.stats).unwrap());
+    world::set_segment(STATS_SEGMENT, serde_json::to_string(&game_state.segments.stats).unwrap());
 } 
\ No newline at end of file
@@ -1,6 +1,10 @@
 // FIXME: remove this, but right now it's just causing warning fatigue
 #![allow(unused)]
 #![feature(int_roundings)]
+#![cfg_attr(test, feature(test))]
+
+#[cfg(test)]
+extern crate test;
 
 use core::cell::RefCell;
 use std::collections::{HashMap, HashSet};
@@ -16,7 +20,7 @@ use room::{
     commune::{commune_services, defense_ops, my_room::MyRoom, spawning::spawn_services},
     room_services,
 };
-use screeps::{RoomName, game};
+use screeps::RoomName;
 use state::{creep::CreepState, room::RoomState};
 use wasm_bindgen::prelude::*;
 
@@ -38,6 +42,12 @@ mod state;
 mod structures;
 mod tick_init;
 mod utils;
+mod world;
+
+/// Used by the memory inspector binary to read memory outside of the game
+pub use memory::inspector;
+/// Used by the replayer binary to rerun recorded ticks outside of the game
+pub use world::recorder;
 
 thread_local! {
     static GAME_STATE: RefCell<GameState> = RefCell::new(GameState::new());
@@ -54,15 +64,15 @@ static INIT: std::sync::Once = std::sync::Once::new();
 
 #[wasm_bindgen(js_name = loop)]
 pub fn game_loop() {
-    let start_cpu = game::cpu::get_used();
+    let start_cpu = world::cpu_used();
 
     #[cfg(feature = "profile")]
     {
-        screeps_timing::start_trace(Box::new(|| (game::cpu::get_used() * 1000.0) as u64));
+        screeps_timing::start_trace(Box::new(|| (world::cpu_used() * 1000.0) as u64));
     }
 
-    let tick = game::time();
-    let bucket = game::cpu::bucket();
+    let tick = world::time();
+    let bucket = world::cpu_bucket();
     info!(
         "Starting game tick {} with {} bucket starting at used CPU: {}",
         tick, bucket, start_cpu
@@ -72,6 +82,10 @@ pub fn game_loop() {
         init::init();
     });
 
+    // Record before running anything, so the record survives a panic later in the tick
+    #[cfg(feature = "record")]
+    world::recorder::record_tick();
+
     MEMORY.with_borrow_mut(|memory| {
         SETTINGS.with_borrow(|settings| {
             GAME_STATE.with_borrow_mut(|game_state| {
@@ -80,7 +94,7 @@ pub fn game_loop() {
                 loop_with_params(memory, game_state, settings);
 
                 let min_intents_cost = 0.2 * game_state.segments.stats.intents as f64;
-                let end_cpu = game::cpu::get_used();
+                let end_cpu = world::cpu_used();
 
                 info!(
                     "Ending tick: {} \n lost CPU: {:.3} \n used CPU: {:.3} \n spent at least {:.3} on intents \n spent {:.3} on calculations",
@@ -133,6 +147,7 @@ fn loop_with_params(memory: &mut GameMemory, game_state: &mut GameState, setting
     role_services::register_commune_harvest_strength(game_state, memory);
 
     commune_services::run_spawning(game_state, memory);
+    my_creep_services::renew_and_recycle_creeps(game_state, memory);
 
     role_services::try_scouts(game_state, memory);
     role_services::try_harvest_commune_sources(game_state, memory);
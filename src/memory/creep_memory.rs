@@ -1,7 +1,7 @@
 use screeps::{Position, RoomName};
 use serde::{Deserialize, Serialize};
 
-use crate::constants::creep::CreepRole;
+use crate::{constants::creep::CreepRole, utils::serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct CreepMemory {
@@ -12,7 +12,10 @@ pub struct CreepMemory {
     pub scout_target: Option<RoomName>,
     pub rampart_only_shoving: Option<bool>,
     pub move_goal_pos: Option<Position>,
+    #[serde(with = "serialize::option_path_packed")]
     pub move_path: Option<Vec<Position>>,
+    /// Consecutive ticks the creep asked to move and stayed where it was
+    pub stuck_ticks: u8,
 }
 
 impl CreepMemory {
@@ -25,6 +28,7 @@ impl CreepMemory {
             rampart_only_shoving: None,
             move_goal_pos: None,
             move_path: None,
+            stuck_ticks: 0,
         }
     }
 }
@@ -1,24 +1,28 @@
 use std::{collections::HashMap, mem};
 
-use js_sys::JsString;
 use log::{error, info, warn};
-use screeps::{ConstructionSite, ObjectId, RoomName, raw_memory};
+use screeps::{ConstructionSite, ObjectId, RoomName};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    constants::general::{GeneralError, GeneralResult}, international::collective_ops, memory::global_requests::DefenseRequests, room::room_ops::try_scout_room, settings::Settings, state::game::GameState, utils::{self, general::{is_tick_interval, GeneralUtils}}, SETTINGS
+    constants::general::{GeneralError, GeneralResult}, international::collective_ops, memory::global_requests::DefenseRequests, room::room_ops::{try_scout_room, update_room_intel}, settings::Settings, state::{game::GameState, stats_segment::{MemorySectionStats, StatsSegment}}, utils::{self, general::{is_tick_interval, GeneralUtils}}, world, SETTINGS
 };
 
 use super::{
     ally::AllyMemory,
     creep_memory::{CreepMemory, PowerCreepMemory},
     enemy::EnemyMemory,
+    memory_recovery,
+    memory_sections::{self, SectionCache, MEMORY_SECTIONS},
+    memory_segments,
+    migrations::{self, ColdSections, MemoryEncoding, MigrationError, MIGRATIONS},
     global_requests::{AttackRequests, ClaimRequests, WorkRequests},
     room_memory::{
         AllyRoomMemory, CommuneRoomMemory, EnemyRoomMemory, HarvestableRoomMemory,
         HighwayRoomMemory, PortalRoomMemory, RemoteRoomMemory, RoomMemory,
     },
     static_room_memory::{ClaimableRoomMemory, KeeperRoomMemory},
+    tracked::Tracked,
 };
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -27,97 +31,126 @@ pub struct GameMemory {
     pub breaking_version: u32,
     pub me: String,
     pub compressed_memory: bool,
-    pub rooms: HashMap<RoomName, RoomMemory>,
-    pub remotes: HashMap<RoomName, RemoteRoomMemory>,
-    pub communes: HashMap<RoomName, CommuneRoomMemory>,
-    pub claimable_rooms: HashMap<RoomName, ClaimableRoomMemory>,
-    pub highway: HashMap<RoomName, HighwayRoomMemory>,
-    pub portal_rooms: HashMap<RoomName, PortalRoomMemory>,
-    pub harvestable_rooms: HashMap<RoomName, HarvestableRoomMemory>,
-    pub keeper: HashMap<RoomName, KeeperRoomMemory>,
-    pub ally: HashMap<RoomName, AllyRoomMemory>,
-    pub enemy: HashMap<RoomName, EnemyRoomMemory>,
-    pub creeps: HashMap<String, CreepMemory>,
-    pub power_creeps: HashMap<String, PowerCreepMemory>,
-    pub work_requests: WorkRequests,
-    pub claim_requests: ClaimRequests,
-    pub attack_requests: AttackRequests,
-    pub defense_requests: DefenseRequests,
-    pub combat_stats: HashMap<String, u32>,
-    // Consider putting construction sites in a segment
-    pub construction_sites: HashMap<ObjectId<ConstructionSite>, u32>,
-    pub allies: HashMap<String, AllyMemory>,
-    pub enemies: HashMap<String, EnemyMemory>,
+    pub rooms: Tracked<HashMap<RoomName, RoomMemory>>,
+    pub remotes: Tracked<HashMap<RoomName, RemoteRoomMemory>>,
+    pub communes: Tracked<HashMap<RoomName, CommuneRoomMemory>>,
+    pub claimable_rooms: Tracked<HashMap<RoomName, ClaimableRoomMemory>>,
+    pub highway: Tracked<HashMap<RoomName, HighwayRoomMemory>>,
+    /// Stored in its own segments, see memory_segments
+    #[serde(skip)]
+    pub portal_rooms: Tracked<HashMap<RoomName, PortalRoomMemory>>,
+    /// Stored in its own segments, see memory_segments
+    #[serde(skip)]
+    pub harvestable_rooms: Tracked<HashMap<RoomName, HarvestableRoomMemory>>,
+    /// Stored in its own segments, see memory_segments
+    #[serde(skip)]
+    pub keeper: Tracked<HashMap<RoomName, KeeperRoomMemory>>,
+    pub ally: Tracked<HashMap<RoomName, AllyRoomMemory>>,
+    pub enemy: Tracked<HashMap<RoomName, EnemyRoomMemory>>,
+    pub creeps: Tracked<HashMap<String, CreepMemory>>,
+    pub power_creeps: Tracked<HashMap<String, PowerCreepMemory>>,
+    pub work_requests: Tracked<WorkRequests>,
+    pub claim_requests: Tracked<ClaimRequests>,
+    pub attack_requests: Tracked<AttackRequests>,
+    pub defense_requests: Tracked<DefenseRequests>,
+    pub combat_stats: Tracked<HashMap<String, u32>>,
+    /// Stored in its own segments, see memory_segments
+    #[serde(skip)]
+    pub construction_sites: Tracked<HashMap<ObjectId<ConstructionSite>, u32>>,
+    pub allies: Tracked<HashMap<String, AllyMemory>>,
+    pub enemies: Tracked<HashMap<String, EnemyMemory>>,
+    /// Cold sections a migration moved out of the main blob, waiting to be written to their segments
+    #[serde(skip)]
+    pub migrated_cold_sections: ColdSections,
 }
 
 impl GameMemory {
     pub fn new(settings: &Settings) -> Self {
         info!("constructing new GameMemory");
 
+        GameMemory::empty(
+            utils::general::me().unwrap(),
+            settings.breaking_version,
+            settings.compressed_memory,
+        )
+    }
+
+    /// Memory with every section empty, without asking the game who we are
+    pub fn empty(me: String, breaking_version: u32, compressed_memory: bool) -> Self {
         GameMemory {
-            breaking_version: settings.breaking_version,
-            compressed_memory: settings.compressed_memory,
-            me: utils::general::me().unwrap(),
-            rooms: HashMap::new(),
-            remotes: HashMap::new(),
-            communes: HashMap::new(),
-            claimable_rooms: HashMap::new(),
-            highway: HashMap::new(),
-            portal_rooms: HashMap::new(),
-            harvestable_rooms: HashMap::new(),
-            keeper: HashMap::new(),
-            ally: HashMap::new(),
-            enemy: HashMap::new(),
-            creeps: HashMap::new(),
-            power_creeps: HashMap::new(),
-            work_requests: WorkRequests::new(),
-            claim_requests: ClaimRequests::new(),
-            attack_requests: AttackRequests::new(),
-            defense_requests: DefenseRequests::new(),
-            combat_stats: HashMap::new(),
-            construction_sites: HashMap::new(),
-            allies: HashMap::new(),
-            enemies: HashMap::new(),
+            breaking_version,
+            compressed_memory,
+            me,
+            rooms: Tracked::default(),
+            remotes: Tracked::default(),
+            communes: Tracked::default(),
+            claimable_rooms: Tracked::default(),
+            highway: Tracked::default(),
+            portal_rooms: Tracked::default(),
+            harvestable_rooms: Tracked::default(),
+            keeper: Tracked::default(),
+            ally: Tracked::default(),
+            enemy: Tracked::default(),
+            creeps: Tracked::default(),
+            power_creeps: Tracked::default(),
+            work_requests: Tracked::default(),
+            claim_requests: Tracked::default(),
+            attack_requests: Tracked::default(),
+            defense_requests: Tracked::default(),
+            combat_stats: Tracked::default(),
+            construction_sites: Tracked::default(),
+            allies: Tracked::default(),
+            enemies: Tracked::default(),
+            migrated_cold_sections: ColdSections::new(),
         }
     }
 
     #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
     pub fn load_from_memory_or_default() -> GameMemory {
         SETTINGS.with_borrow(|settings| {
-            let memory: Result<GameMemory, GeneralError> = match settings.compressed_memory {
-                true => GameMemory::read_base32768_bitcode(),
-                false => GameMemory::read_json(),
+            let memory: Result<GameMemory, MigrationError> = match settings.compressed_memory {
+                true => GameMemory::read_base32768_bitcode(settings.breaking_version),
+                false => GameMemory::read_json(settings.breaking_version),
             };
             
-            if let Ok(memory) = memory {
-                return memory;
+            match memory {
+                Ok(memory) => memory,
+                // We decoded memory but have no way to bring it up to date.
+                // Keep the old version so try_migrate can reset the colony on the init tick
+                Err(MigrationError::MissingStep(version)) | Err(MigrationError::ColdSectionTooLarge(version)) => {
+                    let mut memory = GameMemory::new(settings);
+                    memory.breaking_version = version;
+
+                    memory
+                }
+                // Memory is corrupt, so rebuild what we can from its sections and the snapshots
+                Err(MigrationError::Undecodable) => {
+                    memory_recovery::recover(settings).unwrap_or_else(|| GameMemory::new(settings))
+                }
             }
-            
-            // We were not able to create memory from the game's cache
-            
-            GameMemory::new(settings)
         })
     }
     
     #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-    fn read_json() -> Result<GameMemory, GeneralError> {
-        let stringified_memory = raw_memory::get().as_string().unwrap();
+    fn read_json(breaking_version: u32) -> Result<GameMemory, MigrationError> {
+        let stringified_memory = world::raw_memory();
         
         info!("Read JSON memory {}", stringified_memory);
         
-        match serde_json::from_str::<GameMemory>(&stringified_memory) {
-            Ok(memory) => Ok(memory),
-            Err(err) => {
-                error!("memory parse error on initial read {:?}", err);
-                
-                Err(GeneralError::Fail)
-            }
-        }
+        let (mut memory, cold_sections) = migrations::migrate_to_with_cold_sections::<GameMemory>(
+            stringified_memory.as_bytes(),
+            MemoryEncoding::Json,
+            breaking_version,
+            MIGRATIONS,
+        )?;
+        memory.migrated_cold_sections = cold_sections;
+
+        Ok(memory)
     }
     
     #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-    fn read_base32768_bitcode() -> Result<GameMemory, GeneralError> {
-        let stringified_memory = raw_memory::get().as_string().unwrap();
+    fn read_base32768_bitcode(breaking_version: u32) -> Result<GameMemory, MigrationError> {
+        let stringified_memory = world::raw_memory();
         
         info!("READ base32768 MEMORY {}", stringified_memory);
         
@@ -126,36 +159,44 @@ impl GameMemory {
         let Ok(res) = base32768::decode(&stringified_memory, &mut bits) else {
             error!("Failed to decode base32768 memory");
             
-            return Err(GeneralError::Fail)
-        };
-        
-        // Try to decode bitcode to memory
-        let Ok(memory) = bitcode::deserialize::<GameMemory>(&bits) else {
-            error!("Failed to decode bitcode memory");
-            
-            return Err(GeneralError::Fail)
+            return Err(MigrationError::Undecodable)
         };
         
+        // Try to decode bitcode to memory, migrating it forward if it was written by an older version
+        let (mut memory, cold_sections) = migrations::migrate_to_with_cold_sections::<GameMemory>(
+            &bits,
+            MemoryEncoding::Bitcode,
+            breaking_version,
+            MIGRATIONS,
+        )?;
+        memory.migrated_cold_sections = cold_sections;
+
         Ok(memory)
     }
 
     #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-    pub fn write(&self, game_state: &GameState) {
+    pub fn write(&self, game_state: &mut GameState) {
         if !is_tick_interval(game_state.tick, game_state.intervals.write_memory) {
             return;
         };
         
         match self.compressed_memory {
-            true => self.write_bitcode_base32768(),
+            true => self.write_bitcode_base32768(&mut game_state.memory_cache, &mut game_state.segments.stats),
             false => self.write_json(),
         }
+
+        if is_tick_interval(game_state.tick, game_state.intervals.memory_snapshot) {
+            memory_recovery::write_snapshot(self, game_state);
+        }
+
+        memory_segments::write_cold_sections(game_state, self);
     }
 
     #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
     /// Write to memory using JSON (ew!)
     pub fn write_json(&self) {
         match serde_json::to_string(self) {
-            Ok(v) => raw_memory::set(&JsString::from(v)),
+            Ok(v) => world::set_raw_memory(v),
             Err(e) => {
                 warn!("Memory write error {:?}", e)
             }
@@ -163,9 +204,35 @@ impl GameMemory {
     }
 
     #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-    /// Write to memory using bitcode encoding + base32768
-    pub fn write_bitcode_base32768(&self) {
-        let x = bitcode::serialize(self);
+    /// Write to memory using bitcode encoding + base32768.
+    /// Only sections that changed since the last write are encoded again, and nothing is written if none did
+    pub fn write_bitcode_base32768(&self, cache: &mut SectionCache, stats: &mut StatsSegment) {
+        let start_cpu = world::cpu_used();
+        let current = cache.is_current(self);
+
+        // Encode changed sections one at a time so we can see what each costs
+        for section in MEMORY_SECTIONS {
+            let section_start_cpu = world::cpu_used();
+            let encoded = cache.refresh(self, section);
+
+            let section_stats = stats
+                .memory_sections
+                .entry(section.name().to_string())
+                .or_insert_with(MemorySectionStats::new);
+
+            section_stats.bytes = cache.encoded_len(section) as u32;
+            section_stats.encode_cpu = match encoded {
+                Ok(true) => (world::cpu_used() - section_start_cpu) as f32,
+                _ => 0.,
+            };
+        }
+
+        if current {
+            stats.memory_encode_cpu = 0.;
+            return;
+        }
+
+        let x = memory_sections::encode_sectioned(self, cache);
         let Ok(bits) = x else {
             warn!("Bitcode serialization error {:?}", x);
             return;
@@ -175,20 +242,28 @@ impl GameMemory {
             warn!("Base32768 encoding error");
             return;
         };
+
+        stats.memory_encode_cpu = (world::cpu_used() - start_cpu) as f32;
+        // Base32768 packs 15 bits into each character
+        stats.memory_length = (bits.len() * 8).div_ceil(15) as u32;
         
         info!("Base32768 encoded memory written successfully {}", base);
 
-        raw_memory::set(&JsString::from(base));
+        world::set_raw_memory(base);
     }
 
     #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
     pub fn tick_update(&mut self, game_state: &mut GameState, settings: &Settings) {
         self.try_migrate(game_state, settings);
+        memory_segments::load_cold_sections(game_state, self);
         self.scout_visible_rooms(game_state);
         self.tick_update_commune_memory(game_state);
     }
 
-    pub fn try_migrate(&mut self, game_state: &GameState, settings: &Settings) -> GeneralResult {
+    pub fn try_migrate(&mut self, game_state: &mut GameState, settings: &Settings) -> GeneralResult {
+        // Sections moved out of the main blob by a migration are written over the ticks after it
+        memory_segments::write_migrated_sections(game_state, self);
+
         if game_state.init_tick != game_state.tick {
             return GeneralResult::Fail;
         }
@@ -197,23 +272,30 @@ impl GameMemory {
             return GeneralResult::Fail;
         }
 
-        // migrate
+        // Versions with a registered migration were already upgraded when memory was loaded
 
         self.migrate(game_state, settings)
     }
 
-    fn migrate(&mut self, game_state: &GameState, settings: &Settings) -> GeneralResult {
-        info!("Migrating game memory");
+    /// Last resort for memory that no registered migration could bring up to date
+    fn migrate(&mut self, game_state: &mut GameState, settings: &Settings) -> GeneralResult {
+        info!("Resetting game memory from breaking version {}", self.breaking_version);
         
         collective_ops::kill_all_creeps(game_state);
         mem::swap(self, &mut GameMemory::new(settings));
 
+        // Generations start over with the new memory, so nothing written before can be trusted as current
+        game_state.memory_cache.clear();
+        for section_state in game_state.segments.memory.values_mut() {
+            section_state.written_generation = None;
+        }
+
         GeneralResult::Success
     }
 
     /// Set raw memory to equal an empty string
     pub fn clear_memory(memory: &mut GameMemory) {
-        raw_memory::set(&JsString::from(""));
+        world::set_raw_memory(String::new());
     }
 
     pub fn tick_update_commune_memory(&mut self, game_state: &mut GameState) {
@@ -247,6 +329,7 @@ impl GameMemory {
 
         for room_name in room_names {
             try_scout_room(&room_name, game_state, self);
+            update_room_intel(&room_name, game_state, self);
         }
     }
 }
@@ -255,28 +338,30 @@ impl GameMemory {
 mod tests {
     use screeps::RoomName;
     use serde::{Deserialize, Serialize};
-    use wasm_bindgen_test::*;
 
-    use crate::{constants::general::GeneralResult, memory::{game_memory::GameMemory, room_memory::{self, RoomMemory}}, settings::Settings, state::game::GameState};
+    use crate::{constants::general::GeneralResult, memory::{game_memory::GameMemory, room_memory::{self, RoomMemory}}, settings::Settings, state::game::GameState, world::mock_world::{self, MockWorld}};
 
-    #[wasm_bindgen_test]
+    #[test]
     fn test_memory_compressed() {
+        let room_name = RoomName::new("W1N1").unwrap();
+        mock_world::install(MockWorld::with_commune(room_name));
+
         let mut memory = GameMemory::new(&Settings::new());
         let mut game_state = GameState::new();
+        game_state.tick_update(&mut memory);
         
-        let room_name = RoomName::new("W1N1").unwrap();
         let room_memory = RoomMemory::new(&room_name, &mut game_state, &mut memory).ok().unwrap();
         memory.rooms.insert(room_name, room_memory);
 
-        memory.write(&game_state);
-        let read_memory = GameMemory::read_base32768_bitcode();
+        memory.write(&mut game_state);
+        let read_memory = GameMemory::read_base32768_bitcode(memory.breaking_version);
 
         // eprintln!("read memory {:?}", read_memory);
         
         assert!(read_memory.is_ok());
     }
     
-    #[wasm_bindgen_test]
+    #[test]
     fn pass() {
         assert_eq!(1, 1);
     }
@@ -0,0 +1,230 @@
+//! Decoding, querying and re-encoding memory blobs outside of the game, for the memory inspector binary
+
+use std::collections::HashMap;
+
+use screeps::{ConstructionSite, ObjectId, RoomName};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::settings::Settings;
+
+use super::{
+    game_memory::GameMemory,
+    memory_sections::{self, SectionCache},
+    memory_segments::{decode_section, ColdSection, COLD_SECTIONS},
+    migrations::{self, MemoryEncoding, MigrationError, MIGRATIONS},
+    room_memory::{HarvestableRoomMemory, PortalRoomMemory, RemoteRoomMemory},
+    static_room_memory::KeeperRoomMemory,
+};
+
+#[derive(Debug)]
+pub enum InspectorError {
+    /// The blob is not valid base32768
+    NotBase32768,
+    Memory(MigrationError),
+    UnknownSection(String),
+    Json(String),
+}
+
+/// Decode a dumped RawMemory string, or a snapshot segment, migrating it to the bot's current version
+pub fn decode_blob(text: &str) -> Result<GameMemory, InspectorError> {
+    let text = strip_snapshot_header(text.trim());
+    let settings = Settings::new();
+
+    // JSON memory is written when compression is disabled
+    if text.starts_with('{') {
+        return migrations::migrate_to::<GameMemory>(
+            text.as_bytes(),
+            MemoryEncoding::Json,
+            settings.breaking_version,
+            MIGRATIONS,
+        )
+        .map_err(InspectorError::Memory);
+    }
+
+    let mut bits = Vec::new();
+    base32768::decode(text, &mut bits).map_err(|_| InspectorError::NotBase32768)?;
+
+    migrations::migrate_to::<GameMemory>(
+        &bits,
+        MemoryEncoding::Bitcode,
+        settings.breaking_version,
+        MIGRATIONS,
+    )
+    .map_err(InspectorError::Memory)
+}
+
+/// Snapshots are prefixed with the tick they were taken at
+fn strip_snapshot_header(text: &str) -> &str {
+    match text.split_once('|') {
+        Some((tick, blob)) if tick.parse::<u32>().is_ok() => blob,
+        _ => text,
+    }
+}
+
+/// Encode memory into a compressed blob that can be given to `RawMemory.set`
+pub fn encode_blob(memory: &GameMemory) -> Result<String, InspectorError> {
+    let bits = memory_sections::encode_sectioned(memory, &mut SectionCache::new())
+        .map_err(InspectorError::Memory)?;
+
+    base32768::encode(&bits).map_err(|_| InspectorError::NotBase32768)
+}
+
+/// Read memory from JSON exported by the inspector, such as after editing it by hand
+pub fn memory_from_json(json: &str) -> Result<GameMemory, InspectorError> {
+    serde_json::from_str(json).map_err(|e| InspectorError::Json(e.to_string()))
+}
+
+pub fn memory_to_json(memory: &GameMemory) -> Result<String, InspectorError> {
+    serde_json::to_string_pretty(memory).map_err(|e| InspectorError::Json(e.to_string()))
+}
+
+/// Decode the contents of a cold section's segments, concatenated in order
+pub fn decode_cold_segment(text: &str, section_name: &str, compressed: bool) -> Result<Value, InspectorError> {
+    let Some(section) = COLD_SECTIONS.iter().find(|section| section.name() == section_name) else {
+        return Err(InspectorError::UnknownSection(section_name.to_string()));
+    };
+
+    let text = text.trim();
+
+    match section {
+        ColdSection::HarvestableRooms => {
+            to_value(decode_section::<HashMap<RoomName, HarvestableRoomMemory>>(text, compressed))
+        }
+        ColdSection::KeeperRooms => {
+            to_value(decode_section::<HashMap<RoomName, KeeperRoomMemory>>(text, compressed))
+        }
+        ColdSection::PortalRooms => {
+            to_value(decode_section::<HashMap<RoomName, PortalRoomMemory>>(text, compressed))
+        }
+        ColdSection::ConstructionSites => to_value(decode_section::<
+            HashMap<ObjectId<ConstructionSite>, u32>,
+        >(text, compressed)),
+    }
+}
+
+fn to_value<T: Serialize, E>(decoded: Result<T, E>) -> Result<Value, InspectorError> {
+    let decoded = decoded.map_err(|_| InspectorError::Memory(MigrationError::Undecodable))?;
+
+    serde_json::to_value(decoded).map_err(|e| InspectorError::Json(e.to_string()))
+}
+
+/// The remotes a commune is responsible for
+pub fn remotes_for<'a>(memory: &'a GameMemory, commune: &RoomName) -> Vec<(&'a RoomName, &'a RemoteRoomMemory)> {
+    let mut remotes: Vec<(&RoomName, &RemoteRoomMemory)> = memory
+        .remotes
+        .iter()
+        .filter(|(_, remote)| &remote.commune == commune)
+        .collect();
+
+    remotes.sort_by_key(|(room_name, _)| room_name.to_string());
+    remotes
+}
+
+/// Look up a value by its path through the JSON form of memory, such as `["rooms", "W1N1"]`
+pub fn query(memory: &GameMemory, path: &[&str]) -> Result<Option<Value>, InspectorError> {
+    let mut value = serde_json::to_value(memory).map_err(|e| InspectorError::Json(e.to_string()))?;
+
+    for key in path {
+        let next = match &mut value {
+            Value::Object(fields) => fields.remove(*key),
+            Value::Array(items) => key
+                .parse::<usize>()
+                .ok()
+                .filter(|index| *index < items.len())
+                .map(|index| items.swap_remove(index)),
+            _ => None,
+        };
+
+        let Some(next) = next else {
+            return Ok(None);
+        };
+        value = next;
+    }
+
+    Ok(Some(value))
+}
+
+#[cfg(test)]
+mod tests {
+    use screeps::RoomName;
+
+    use crate::{
+        memory::{game_memory::GameMemory, room_memory::RemoteRoomMemory},
+        settings::Settings,
+    };
+
+    use super::{decode_blob, encode_blob, memory_from_json, memory_to_json, query, remotes_for};
+
+    fn memory_fixture() -> GameMemory {
+        let settings = Settings::new();
+        let mut memory = GameMemory::empty("MarvinTMB".to_string(), settings.breaking_version, true);
+
+        for (remote, commune) in [("W2N1", "W1N1"), ("W1N2", "W1N1"), ("W5N5", "W6N5")] {
+            memory.remotes.insert(
+                remote.parse().unwrap(),
+                RemoteRoomMemory {
+                    commune: commune.parse().unwrap(),
+                    source_paths: Vec::new(),
+                    cost: 10,
+                    abandon: None,
+                },
+            );
+        }
+
+        memory
+    }
+
+    #[test]
+    fn blobs_round_trip() {
+        let blob = encode_blob(&memory_fixture()).unwrap();
+
+        let memory = decode_blob(&blob).unwrap();
+
+        assert_eq!(memory.me, "MarvinTMB");
+        assert_eq!(memory.remotes.len(), 3);
+    }
+
+    #[test]
+    fn snapshots_decode_like_raw_memory() {
+        let blob = encode_blob(&memory_fixture()).unwrap();
+
+        let memory = decode_blob(&format!("1234|{}", blob)).unwrap();
+
+        assert_eq!(memory.remotes.len(), 3);
+    }
+
+    #[test]
+    fn edited_json_re_encodes() {
+        let json = memory_to_json(&memory_fixture()).unwrap();
+        let edited = json.replace("\"cost\": 10", "\"cost\": 99");
+
+        let blob = encode_blob(&memory_from_json(&edited).unwrap()).unwrap();
+        let memory = decode_blob(&blob).unwrap();
+
+        assert!(memory.remotes.values().all(|remote| remote.cost == 99));
+    }
+
+    #[test]
+    fn remotes_are_found_by_commune() {
+        let memory = memory_fixture();
+        let commune: RoomName = "W1N1".parse().unwrap();
+
+        let remotes: Vec<String> = remotes_for(&memory, &commune)
+            .iter()
+            .map(|(room_name, _)| room_name.to_string())
+            .collect();
+
+        assert_eq!(remotes, vec!["W1N2", "W2N1"]);
+    }
+
+    #[test]
+    fn queries_follow_paths() {
+        let memory = memory_fixture();
+
+        let cost = query(&memory, &["remotes", "W5N5", "cost"]).unwrap();
+
+        assert_eq!(cost, Some(serde_json::json!(10)));
+        assert_eq!(query(&memory, &["remotes", "W9N9"]).unwrap(), None);
+    }
+}
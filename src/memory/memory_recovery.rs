@@ -0,0 +1,227 @@
+use log::{error, info, warn};
+
+use crate::{
+    constants::{general::GeneralResult, segments::SNAPSHOT_SEGMENTS},
+    settings::Settings,
+    state::game::GameState,
+    world,
+};
+
+use super::{
+    game_memory::GameMemory,
+    memory_sections::{self, PartialMemory, MEMORY_SECTIONS},
+    memory_segments,
+    migrations::MemoryEncoding,
+};
+
+/// Write the main memory into one of the snapshot slots, alternating between them so a bad write never replaces the only good copy
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+pub fn write_snapshot(memory: &GameMemory, game_state: &mut GameState) -> GeneralResult {
+    let encoded = match memory.compressed_memory {
+        true => {
+            let Ok(bits) = memory_sections::encode_sectioned(memory, &mut game_state.memory_cache) else {
+                return GeneralResult::Fail;
+            };
+            let Ok(base) = base32768::encode(&bits) else {
+                return GeneralResult::Fail;
+            };
+
+            base
+        }
+        false => {
+            let Ok(json) = serde_json::to_string(memory) else {
+                return GeneralResult::Fail;
+            };
+
+            json
+        }
+    };
+
+    let slot = (game_state.tick / game_state.intervals.memory_snapshot) as usize % SNAPSHOT_SEGMENTS.len();
+    let snapshot = format!("{}|{}", game_state.tick, encoded);
+
+    let result = memory_segments::write_chunks(&SNAPSHOT_SEGMENTS[slot], &snapshot);
+    if result == GeneralResult::Fail {
+        warn!("Memory is too large to snapshot");
+    }
+
+    result
+}
+
+/// Read every snapshot we can, newest first
+fn read_snapshots(encoding: MemoryEncoding) -> Vec<(u32, PartialMemory)> {
+    let mut snapshots = Vec::new();
+
+    for slot in SNAPSHOT_SEGMENTS {
+        let Some(snapshot) = memory_segments::read_chunks(&slot) else {
+            continue;
+        };
+        let Some((tick, encoded)) = snapshot.split_once('|') else {
+            continue;
+        };
+        let Ok(tick) = tick.parse::<u32>() else {
+            continue;
+        };
+
+        let bytes = match encoding {
+            MemoryEncoding::Bitcode => {
+                let mut bits = Vec::new();
+                if base32768::decode(encoded, &mut bits).is_err() {
+                    continue;
+                }
+
+                bits
+            }
+            MemoryEncoding::Json => encoded.as_bytes().to_vec(),
+        };
+
+        match memory_sections::decode_partial(&bytes, encoding) {
+            Ok(partial) => snapshots.push((tick, partial)),
+            Err(_) => warn!("Memory snapshot from tick {} is unreadable", tick),
+        }
+    }
+
+    snapshots.sort_by(|(a, _), (b, _)| b.cmp(a));
+    snapshots
+}
+
+/// Rebuild memory that failed to decode from whatever sections of it, or of the snapshots, are still readable
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+pub fn recover(settings: &Settings) -> Option<GameMemory> {
+    let encoding = match settings.compressed_memory {
+        true => MemoryEncoding::Bitcode,
+        false => MemoryEncoding::Json,
+    };
+
+    let stringified_memory = world::raw_memory();
+    let bytes = match encoding {
+        MemoryEncoding::Bitcode => {
+            let mut bits = Vec::new();
+            base32768::decode(&stringified_memory, &mut bits).ok().map(|_| bits)
+        }
+        MemoryEncoding::Json => Some(stringified_memory.into_bytes()),
+    };
+
+    let primary = bytes.and_then(|bytes| memory_sections::decode_partial(&bytes, encoding).ok());
+
+    merge_recovered(primary, read_snapshots(encoding), settings.breaking_version)
+}
+
+/// Prefer sections from the main memory, fill the rest from the newest snapshot that has them
+pub fn merge_recovered(
+    primary: Option<PartialMemory>,
+    snapshots: Vec<(u32, PartialMemory)>,
+    breaking_version: u32,
+) -> Option<GameMemory> {
+    // Sections of another version can not be trusted to mean the same thing
+    let mut recovered = primary.filter(|partial| partial.memory.breaking_version == breaking_version);
+
+    if let Some(partial) = &recovered {
+        let salvaged: Vec<&str> = MEMORY_SECTIONS
+            .iter()
+            .filter(|section| !partial.failed.contains(section))
+            .map(|section| section.name())
+            .collect();
+
+        info!("Salvaged memory sections {:?}", salvaged);
+    }
+
+    for (tick, mut snapshot) in snapshots {
+        if snapshot.memory.breaking_version != breaking_version {
+            continue;
+        }
+
+        let Some(partial) = &mut recovered else {
+            info!("Restored memory from the snapshot taken at tick {}", tick);
+            recovered = Some(snapshot);
+            continue;
+        };
+
+        partial.failed.retain(|section| {
+            if snapshot.failed.contains(section) {
+                return true;
+            }
+
+            section.swap(&mut partial.memory, &mut snapshot.memory);
+            info!("Restored memory section {} from the snapshot taken at tick {}", section.name(), tick);
+            false
+        });
+
+        if partial.failed.is_empty() {
+            break;
+        }
+    }
+
+    let partial = recovered?;
+    if !partial.failed.is_empty() {
+        let lost: Vec<&str> = partial.failed.iter().map(|section| section.name()).collect();
+        error!("Lost memory sections {:?}", lost);
+    }
+
+    Some(partial.memory)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::memory::{
+        game_memory::GameMemory,
+        memory_sections::{MemorySection, PartialMemory},
+        room_memory::HighwayRoomMemory,
+    };
+
+    use super::merge_recovered;
+
+    fn partial(kills: u32, failed: Vec<MemorySection>) -> PartialMemory {
+        let mut memory = GameMemory::empty("MarvinTMB".to_string(), 6, true);
+        memory.combat_stats.insert("kills".to_string(), kills);
+
+        PartialMemory { memory, failed }
+    }
+
+    #[test]
+    fn failed_sections_come_from_the_newest_snapshot() {
+        let mut primary = partial(0, vec![MemorySection::CombatStats]);
+        primary.memory.combat_stats.clear();
+        primary
+            .memory
+            .highway
+            .insert("W10N0".parse().unwrap(), HighwayRoomMemory::new());
+
+        let snapshots = vec![(200, partial(7, vec![])), (100, partial(3, vec![]))];
+
+        let memory = merge_recovered(Some(primary), snapshots, 6).unwrap();
+
+        assert_eq!(memory.combat_stats.get("kills"), Some(&7));
+        // Sections that decoded from main memory are kept over the snapshot's
+        assert_eq!(memory.highway.len(), 1);
+    }
+
+    #[test]
+    fn snapshots_missing_the_section_are_skipped() {
+        let primary = partial(0, vec![MemorySection::CombatStats]);
+        let snapshots = vec![
+            (200, partial(7, vec![MemorySection::CombatStats])),
+            (100, partial(3, vec![])),
+        ];
+
+        let memory = merge_recovered(Some(primary), snapshots, 6).unwrap();
+
+        assert_eq!(memory.combat_stats.get("kills"), Some(&3));
+    }
+
+    #[test]
+    fn unreadable_memory_falls_back_to_a_snapshot() {
+        let snapshots = vec![(100, partial(3, vec![])), (50, partial(1, vec![]))];
+
+        let memory = merge_recovered(None, snapshots, 6).unwrap();
+
+        assert_eq!(memory.combat_stats.get("kills"), Some(&3));
+    }
+
+    #[test]
+    fn other_versions_are_not_recovered() {
+        let snapshots = vec![(100, partial(3, vec![]))];
+
+        assert!(merge_recovered(None, snapshots, 7).is_none());
+    }
+}
@@ -0,0 +1,477 @@
+use std::mem;
+
+use enum_map::{Enum, EnumMap};
+use log::error;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{
+    game_memory::GameMemory,
+    migrations::{self, MemoryEncoding, MigrationError},
+    tracked::Tracked,
+};
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+/// Top level sections of the main memory blob, each encoded on its own so unchanged sections can reuse their last encoding.
+/// Tampering with the order will break decoding of existing memory
+pub enum MemorySection {
+    Rooms,
+    Remotes,
+    Communes,
+    ClaimableRooms,
+    Highway,
+    Ally,
+    Enemy,
+    Creeps,
+    PowerCreeps,
+    WorkRequests,
+    ClaimRequests,
+    AttackRequests,
+    DefenseRequests,
+    CombatStats,
+    Allies,
+    Enemies,
+}
+
+pub const MEMORY_SECTIONS: [MemorySection; 16] = [
+    MemorySection::Rooms,
+    MemorySection::Remotes,
+    MemorySection::Communes,
+    MemorySection::ClaimableRooms,
+    MemorySection::Highway,
+    MemorySection::Ally,
+    MemorySection::Enemy,
+    MemorySection::Creeps,
+    MemorySection::PowerCreeps,
+    MemorySection::WorkRequests,
+    MemorySection::ClaimRequests,
+    MemorySection::AttackRequests,
+    MemorySection::DefenseRequests,
+    MemorySection::CombatStats,
+    MemorySection::Allies,
+    MemorySection::Enemies,
+];
+
+impl MemorySection {
+    pub fn generation(&self, memory: &GameMemory) -> u32 {
+        match self {
+            MemorySection::Rooms => memory.rooms.generation(),
+            MemorySection::Remotes => memory.remotes.generation(),
+            MemorySection::Communes => memory.communes.generation(),
+            MemorySection::ClaimableRooms => memory.claimable_rooms.generation(),
+            MemorySection::Highway => memory.highway.generation(),
+            MemorySection::Ally => memory.ally.generation(),
+            MemorySection::Enemy => memory.enemy.generation(),
+            MemorySection::Creeps => memory.creeps.generation(),
+            MemorySection::PowerCreeps => memory.power_creeps.generation(),
+            MemorySection::WorkRequests => memory.work_requests.generation(),
+            MemorySection::ClaimRequests => memory.claim_requests.generation(),
+            MemorySection::AttackRequests => memory.attack_requests.generation(),
+            MemorySection::DefenseRequests => memory.defense_requests.generation(),
+            MemorySection::CombatStats => memory.combat_stats.generation(),
+            MemorySection::Allies => memory.allies.generation(),
+            MemorySection::Enemies => memory.enemies.generation(),
+        }
+    }
+
+    pub fn encode(&self, memory: &GameMemory) -> Result<Vec<u8>, MigrationError> {
+        match self {
+            MemorySection::Rooms => encode_bitcode(&memory.rooms),
+            MemorySection::Remotes => encode_bitcode(&memory.remotes),
+            MemorySection::Communes => encode_bitcode(&memory.communes),
+            MemorySection::ClaimableRooms => encode_bitcode(&memory.claimable_rooms),
+            MemorySection::Highway => encode_bitcode(&memory.highway),
+            MemorySection::Ally => encode_bitcode(&memory.ally),
+            MemorySection::Enemy => encode_bitcode(&memory.enemy),
+            MemorySection::Creeps => encode_bitcode(&memory.creeps),
+            MemorySection::PowerCreeps => encode_bitcode(&memory.power_creeps),
+            MemorySection::WorkRequests => encode_bitcode(&memory.work_requests),
+            MemorySection::ClaimRequests => encode_bitcode(&memory.claim_requests),
+            MemorySection::AttackRequests => encode_bitcode(&memory.attack_requests),
+            MemorySection::DefenseRequests => encode_bitcode(&memory.defense_requests),
+            MemorySection::CombatStats => encode_bitcode(&memory.combat_stats),
+            MemorySection::Allies => encode_bitcode(&memory.allies),
+            MemorySection::Enemies => encode_bitcode(&memory.enemies),
+        }
+    }
+
+    /// Decode the section's bytes into memory, replacing whatever it held
+    pub fn decode_into(
+        &self,
+        memory: &mut GameMemory,
+        bytes: &[u8],
+        encoding: MemoryEncoding,
+    ) -> Result<(), MigrationError> {
+        match self {
+            MemorySection::Rooms => memory.rooms = decode_tracked(bytes, encoding)?,
+            MemorySection::Remotes => memory.remotes = decode_tracked(bytes, encoding)?,
+            MemorySection::Communes => memory.communes = decode_tracked(bytes, encoding)?,
+            MemorySection::ClaimableRooms => memory.claimable_rooms = decode_tracked(bytes, encoding)?,
+            MemorySection::Highway => memory.highway = decode_tracked(bytes, encoding)?,
+            MemorySection::Ally => memory.ally = decode_tracked(bytes, encoding)?,
+            MemorySection::Enemy => memory.enemy = decode_tracked(bytes, encoding)?,
+            MemorySection::Creeps => memory.creeps = decode_tracked(bytes, encoding)?,
+            MemorySection::PowerCreeps => memory.power_creeps = decode_tracked(bytes, encoding)?,
+            MemorySection::WorkRequests => memory.work_requests = decode_tracked(bytes, encoding)?,
+            MemorySection::ClaimRequests => memory.claim_requests = decode_tracked(bytes, encoding)?,
+            MemorySection::AttackRequests => memory.attack_requests = decode_tracked(bytes, encoding)?,
+            MemorySection::DefenseRequests => memory.defense_requests = decode_tracked(bytes, encoding)?,
+            MemorySection::CombatStats => memory.combat_stats = decode_tracked(bytes, encoding)?,
+            MemorySection::Allies => memory.allies = decode_tracked(bytes, encoding)?,
+            MemorySection::Enemies => memory.enemies = decode_tracked(bytes, encoding)?,
+        }
+
+        Ok(())
+    }
+
+    /// Exchange this section between two memories
+    pub fn swap(&self, a: &mut GameMemory, b: &mut GameMemory) {
+        match self {
+            MemorySection::Rooms => mem::swap(&mut a.rooms, &mut b.rooms),
+            MemorySection::Remotes => mem::swap(&mut a.remotes, &mut b.remotes),
+            MemorySection::Communes => mem::swap(&mut a.communes, &mut b.communes),
+            MemorySection::ClaimableRooms => mem::swap(&mut a.claimable_rooms, &mut b.claimable_rooms),
+            MemorySection::Highway => mem::swap(&mut a.highway, &mut b.highway),
+            MemorySection::Ally => mem::swap(&mut a.ally, &mut b.ally),
+            MemorySection::Enemy => mem::swap(&mut a.enemy, &mut b.enemy),
+            MemorySection::Creeps => mem::swap(&mut a.creeps, &mut b.creeps),
+            MemorySection::PowerCreeps => mem::swap(&mut a.power_creeps, &mut b.power_creeps),
+            MemorySection::WorkRequests => mem::swap(&mut a.work_requests, &mut b.work_requests),
+            MemorySection::ClaimRequests => mem::swap(&mut a.claim_requests, &mut b.claim_requests),
+            MemorySection::AttackRequests => mem::swap(&mut a.attack_requests, &mut b.attack_requests),
+            MemorySection::DefenseRequests => mem::swap(&mut a.defense_requests, &mut b.defense_requests),
+            MemorySection::CombatStats => mem::swap(&mut a.combat_stats, &mut b.combat_stats),
+            MemorySection::Allies => mem::swap(&mut a.allies, &mut b.allies),
+            MemorySection::Enemies => mem::swap(&mut a.enemies, &mut b.enemies),
+        }
+    }
+
+    /// The name of the field the section is stored in
+    pub fn name(&self) -> &'static str {
+        match self {
+            MemorySection::Rooms => "rooms",
+            MemorySection::Remotes => "remotes",
+            MemorySection::Communes => "communes",
+            MemorySection::ClaimableRooms => "claimable_rooms",
+            MemorySection::Highway => "highway",
+            MemorySection::Ally => "ally",
+            MemorySection::Enemy => "enemy",
+            MemorySection::Creeps => "creeps",
+            MemorySection::PowerCreeps => "power_creeps",
+            MemorySection::WorkRequests => "work_requests",
+            MemorySection::ClaimRequests => "claim_requests",
+            MemorySection::AttackRequests => "attack_requests",
+            MemorySection::DefenseRequests => "defense_requests",
+            MemorySection::CombatStats => "combat_stats",
+            MemorySection::Allies => "allies",
+            MemorySection::Enemies => "enemies",
+        }
+    }
+}
+
+fn encode_bitcode<T: Serialize>(section: &T) -> Result<Vec<u8>, MigrationError> {
+    bitcode::serialize(section).map_err(|_| MigrationError::Undecodable)
+}
+
+fn decode_tracked<T: DeserializeOwned>(bytes: &[u8], encoding: MemoryEncoding) -> Result<Tracked<T>, MigrationError> {
+    migrations::decode::<T>(bytes, encoding).map(Tracked::new)
+}
+
+#[derive(Debug)]
+struct EncodedSection {
+    generation: u32,
+    bytes: Vec<u8>,
+}
+
+#[derive(Debug, Default)]
+/// The last encoding of each section, so only sections that changed are encoded again
+pub struct SectionCache {
+    sections: EnumMap<MemorySection, Option<EncodedSection>>,
+}
+
+impl SectionCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether every section was encoded at its current generation
+    pub fn is_current(&self, memory: &GameMemory) -> bool {
+        MEMORY_SECTIONS.iter().all(|section| {
+            self.sections[*section]
+                .as_ref()
+                .is_some_and(|encoded| encoded.generation == section.generation(memory))
+        })
+    }
+
+    /// Forget every encoding, for when memory is replaced wholesale and generations start over
+    pub fn clear(&mut self) {
+        self.sections = EnumMap::default();
+    }
+
+    /// Encode the section again if it changed since it was cached. Returns whether it was encoded
+    pub fn refresh(&mut self, memory: &GameMemory, section: MemorySection) -> Result<bool, MigrationError> {
+        let generation = section.generation(memory);
+
+        if self.sections[section]
+            .as_ref()
+            .is_some_and(|encoded| encoded.generation == generation)
+        {
+            return Ok(false);
+        }
+
+        self.sections[section] = Some(EncodedSection {
+            generation,
+            bytes: section.encode(memory)?,
+        });
+
+        Ok(true)
+    }
+
+    /// How many bytes the section's cached encoding takes up
+    pub fn encoded_len(&self, section: MemorySection) -> usize {
+        self.sections[section]
+            .as_ref()
+            .map_or(0, |encoded| encoded.bytes.len())
+    }
+}
+
+#[derive(Serialize)]
+struct SectionedMemoryRef<'a> {
+    breaking_version: u32,
+    me: &'a str,
+    compressed_memory: bool,
+    sections: Vec<&'a [u8]>,
+}
+
+#[derive(Serialize, Deserialize)]
+/// The bitcode layout of the main memory blob. Sections are in the order of `MEMORY_SECTIONS`
+pub struct SectionedMemory {
+    pub breaking_version: u32,
+    pub me: String,
+    pub compressed_memory: bool,
+    pub sections: Vec<Vec<u8>>,
+}
+
+/// Encode memory as a header followed by each section's own bitcode, reusing cached encodings for unchanged sections
+pub fn encode_sectioned(memory: &GameMemory, cache: &mut SectionCache) -> Result<Vec<u8>, MigrationError> {
+    for section in MEMORY_SECTIONS {
+        cache.refresh(memory, section)?;
+    }
+
+    let sections = MEMORY_SECTIONS
+        .iter()
+        .map(|section| match &cache.sections[*section] {
+            Some(encoded) => encoded.bytes.as_slice(),
+            None => &[],
+        })
+        .collect();
+
+    bitcode::serialize(&SectionedMemoryRef {
+        breaking_version: memory.breaking_version,
+        me: &memory.me,
+        compressed_memory: memory.compressed_memory,
+        sections,
+    })
+    .map_err(|_| MigrationError::Undecodable)
+}
+
+/// Memory decoded section by section, along with the sections that could not be read and were left empty
+pub struct PartialMemory {
+    pub memory: GameMemory,
+    pub failed: Vec<MemorySection>,
+}
+
+pub fn decode_sectioned_partial(bytes: &[u8]) -> Result<PartialMemory, MigrationError> {
+    let sectioned: SectionedMemory =
+        bitcode::deserialize(bytes).map_err(|_| MigrationError::Undecodable)?;
+
+    if sectioned.sections.len() != MEMORY_SECTIONS.len() {
+        return Err(MigrationError::Undecodable);
+    }
+
+    let mut memory = GameMemory::empty(sectioned.me, sectioned.breaking_version, sectioned.compressed_memory);
+    let mut failed = Vec::new();
+
+    for (section, bytes) in MEMORY_SECTIONS.iter().zip(sectioned.sections.iter()) {
+        if section.decode_into(&mut memory, bytes, MemoryEncoding::Bitcode).is_err() {
+            failed.push(*section);
+        }
+    }
+
+    Ok(PartialMemory { memory, failed })
+}
+
+/// JSON is self describing, so each top level field can be read on its own
+pub fn decode_json_partial(bytes: &[u8]) -> Result<PartialMemory, MigrationError> {
+    let Ok(serde_json::Value::Object(mut fields)) = serde_json::from_slice(bytes) else {
+        return Err(MigrationError::Undecodable);
+    };
+
+    let (Some(breaking_version), Some(me), Some(compressed_memory)) = (
+        fields.get("breaking_version").and_then(|value| value.as_u64()),
+        fields.get("me").and_then(|value| value.as_str()),
+        fields.get("compressed_memory").and_then(|value| value.as_bool()),
+    ) else {
+        return Err(MigrationError::Undecodable);
+    };
+
+    let mut memory = GameMemory::empty(me.to_string(), breaking_version as u32, compressed_memory);
+    let mut failed = Vec::new();
+
+    for section in MEMORY_SECTIONS {
+        let decoded = fields
+            .remove(section.name())
+            .and_then(|value| serde_json::to_vec(&value).ok())
+            .map(|bytes| section.decode_into(&mut memory, &bytes, MemoryEncoding::Json));
+
+        if !matches!(decoded, Some(Ok(()))) {
+            failed.push(section);
+        }
+    }
+
+    Ok(PartialMemory { memory, failed })
+}
+
+pub fn decode_partial(bytes: &[u8], encoding: MemoryEncoding) -> Result<PartialMemory, MigrationError> {
+    match encoding {
+        MemoryEncoding::Bitcode => decode_sectioned_partial(bytes),
+        MemoryEncoding::Json => decode_json_partial(bytes),
+    }
+}
+
+pub fn decode_sectioned(bytes: &[u8]) -> Result<GameMemory, MigrationError> {
+    let partial = decode_sectioned_partial(bytes)?;
+
+    if !partial.failed.is_empty() {
+        error!("Failed to decode memory sections {:?}", partial.failed);
+        return Err(MigrationError::Undecodable);
+    }
+
+    Ok(partial.memory)
+}
+
+/// Bitcode memory is sectioned, JSON memory is left as one human readable document
+pub fn encode(memory: &GameMemory, encoding: MemoryEncoding, cache: &mut SectionCache) -> Result<Vec<u8>, MigrationError> {
+    match encoding {
+        MemoryEncoding::Bitcode => encode_sectioned(memory, cache),
+        MemoryEncoding::Json => serde_json::to_vec(memory).map_err(|_| MigrationError::Undecodable),
+    }
+}
+
+pub fn decode(bytes: &[u8], encoding: MemoryEncoding) -> Result<GameMemory, MigrationError> {
+    match encoding {
+        MemoryEncoding::Bitcode => decode_sectioned(bytes),
+        MemoryEncoding::Json => serde_json::from_slice(bytes).map_err(|_| MigrationError::Undecodable),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::memory::{game_memory::GameMemory, room_memory::HighwayRoomMemory};
+
+    use super::{
+        decode_json_partial, decode_sectioned, decode_sectioned_partial, encode_sectioned,
+        MemorySection, SectionCache, SectionedMemoryRef, MEMORY_SECTIONS,
+    };
+
+    fn memory_fixture() -> GameMemory {
+        let mut memory = GameMemory::empty("MarvinTMB".to_string(), 6, true);
+        memory.combat_stats.insert("kills".to_string(), 3);
+        memory
+            .highway
+            .insert("W10N0".parse().unwrap(), HighwayRoomMemory::new());
+
+        memory
+    }
+
+    #[test]
+    fn sectioned_memory_round_trips() {
+        let memory = memory_fixture();
+
+        let bytes = encode_sectioned(&memory, &mut SectionCache::new()).unwrap();
+        let read = decode_sectioned(&bytes).unwrap();
+
+        assert_eq!(read.me, "MarvinTMB");
+        assert_eq!(read.breaking_version, 6);
+        assert_eq!(read.combat_stats.get("kills"), Some(&3));
+        assert_eq!(read.highway.len(), 1);
+    }
+
+    #[test]
+    fn only_changed_sections_are_encoded_again() {
+        let mut memory = memory_fixture();
+        let mut cache = SectionCache::new();
+
+        encode_sectioned(&memory, &mut cache).unwrap();
+        assert!(cache.is_current(&memory));
+
+        memory.combat_stats.insert("deaths".to_string(), 1);
+        assert!(!cache.is_current(&memory));
+
+        let cached_rooms = cache.sections[MemorySection::Rooms].as_ref().unwrap().bytes.as_ptr();
+        let bytes = encode_sectioned(&memory, &mut cache).unwrap();
+
+        // Untouched sections keep their previous encoding
+        assert_eq!(cache.sections[MemorySection::Rooms].as_ref().unwrap().bytes.as_ptr(), cached_rooms);
+        assert!(cache.is_current(&memory));
+        assert_eq!(decode_sectioned(&bytes).unwrap().combat_stats.len(), 2);
+    }
+
+    #[test]
+    fn corrupt_sections_do_not_discard_the_rest() {
+        let memory = memory_fixture();
+        let mut cache = SectionCache::new();
+        encode_sectioned(&memory, &mut cache).unwrap();
+
+        let garbage: &[u8] = &[255, 255, 255];
+        let sections = MEMORY_SECTIONS
+            .iter()
+            .map(|section| match section {
+                MemorySection::CombatStats => garbage,
+                _ => cache.sections[*section].as_ref().unwrap().bytes.as_slice(),
+            })
+            .collect();
+        let bytes = bitcode::serialize(&SectionedMemoryRef {
+            breaking_version: 6,
+            me: "MarvinTMB",
+            compressed_memory: true,
+            sections,
+        })
+        .unwrap();
+
+        let partial = decode_sectioned_partial(&bytes).unwrap();
+
+        assert_eq!(partial.failed, vec![MemorySection::CombatStats]);
+        assert_eq!(partial.memory.highway.len(), 1);
+        assert!(decode_sectioned(&bytes).is_err());
+    }
+
+    #[test]
+    fn corrupt_json_fields_do_not_discard_the_rest() {
+        let memory = memory_fixture();
+
+        let mut json = serde_json::to_value(&memory).unwrap();
+        json["combat_stats"] = serde_json::Value::String("garbage".to_string());
+        let bytes = serde_json::to_vec(&json).unwrap();
+
+        let partial = decode_json_partial(&bytes).unwrap();
+
+        assert_eq!(partial.failed, vec![MemorySection::CombatStats]);
+        assert_eq!(partial.memory.highway.len(), 1);
+    }
+
+    #[test]
+    fn refresh_reports_whether_it_encoded() {
+        let mut memory = memory_fixture();
+        let mut cache = SectionCache::new();
+
+        assert_eq!(cache.refresh(&memory, MemorySection::CombatStats), Ok(true));
+        assert_eq!(cache.refresh(&memory, MemorySection::CombatStats), Ok(false));
+        let len = cache.encoded_len(MemorySection::CombatStats);
+        assert!(len > 0);
+
+        memory.combat_stats.insert("deaths".to_string(), 4);
+
+        assert_eq!(cache.refresh(&memory, MemorySection::CombatStats), Ok(true));
+        assert!(cache.encoded_len(MemorySection::CombatStats) > len);
+    }
+}
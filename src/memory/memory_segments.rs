@@ -0,0 +1,398 @@
+use std::{collections::HashMap, hash::Hash, mem};
+
+use enum_map::Enum;
+use log::{error, info, warn};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{
+    constants::{
+        general::{GeneralError, GeneralResult},
+        segments::{
+            CONSTRUCTION_SITES_SEGMENTS, HARVESTABLE_ROOMS_SEGMENTS, KEEPER_ROOMS_SEGMENTS,
+            MAX_MEMORY_SEGMENT_WRITES, MAX_SEGMENT_LENGTH, PORTAL_ROOMS_SEGMENTS,
+            SNAPSHOT_SEGMENTS,
+        },
+    },
+    state::{game::GameState, stats_segment::MemorySectionStats},
+    world,
+};
+
+use super::{game_memory::GameMemory, tracked::Tracked};
+
+#[derive(Enum, Debug, Clone, Copy, PartialEq, Eq)]
+/// Sections of game memory that change rarely, so they live in their own segments instead of the main blob
+pub enum ColdSection {
+    HarvestableRooms,
+    KeeperRooms,
+    PortalRooms,
+    ConstructionSites,
+}
+
+pub const COLD_SECTIONS: [ColdSection; 4] = [
+    ColdSection::HarvestableRooms,
+    ColdSection::KeeperRooms,
+    ColdSection::PortalRooms,
+    ColdSection::ConstructionSites,
+];
+
+impl ColdSection {
+    /// The segments the section is chunked across, in order
+    pub fn segments(&self) -> &'static [u8] {
+        match self {
+            ColdSection::HarvestableRooms => &HARVESTABLE_ROOMS_SEGMENTS,
+            ColdSection::KeeperRooms => &KEEPER_ROOMS_SEGMENTS,
+            ColdSection::PortalRooms => &PORTAL_ROOMS_SEGMENTS,
+            ColdSection::ConstructionSites => &CONSTRUCTION_SITES_SEGMENTS,
+        }
+    }
+
+    /// The name of the field the section is stored in
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColdSection::HarvestableRooms => "harvestable_rooms",
+            ColdSection::KeeperRooms => "keeper",
+            ColdSection::PortalRooms => "portal_rooms",
+            ColdSection::ConstructionSites => "construction_sites",
+        }
+    }
+
+    pub fn generation(&self, memory: &GameMemory) -> u32 {
+        match self {
+            ColdSection::HarvestableRooms => memory.harvestable_rooms.generation(),
+            ColdSection::KeeperRooms => memory.keeper.generation(),
+            ColdSection::PortalRooms => memory.portal_rooms.generation(),
+            ColdSection::ConstructionSites => memory.construction_sites.generation(),
+        }
+    }
+}
+
+/// Read any cold sections whose segments are active, and request the segments of those that are not.
+/// Until a section is loaded, its map only holds what was recorded this global
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+pub fn load_cold_sections(game_state: &mut GameState, memory: &mut GameMemory) {
+    // Snapshots are kept active so they can be read on the tick memory fails to decode
+    let mut requested_segments: Vec<u8> = SNAPSHOT_SEGMENTS.concat();
+
+    for section in COLD_SECTIONS {
+        if game_state.segments.memory[section].loaded {
+            continue;
+        }
+
+        // Loading before a migrated section is written would read the empty segments it hasn't reached yet
+        if memory
+            .migrated_cold_sections
+            .iter()
+            .any(|(migrated_section, _)| *migrated_section == section)
+        {
+            continue;
+        }
+
+        let Some(encoded) = read_chunks(section.segments()) else {
+            requested_segments.extend_from_slice(section.segments());
+            continue;
+        };
+
+        let clean = match decode_cold_section(memory, section, &encoded) {
+            Ok(clean) => clean,
+            Err(_) => {
+                // Treat the section as empty so it is rebuilt by scouting rather than blocking forever
+                error!("Failed to decode cold memory section {:?}", section);
+                false
+            }
+        };
+
+        info!("Loaded cold memory section {:?}", section);
+        section_stats(game_state, section).bytes = encoded_bytes(&encoded, memory.compressed_memory) as u32;
+
+        let section_state = &mut game_state.segments.memory[section];
+        section_state.loaded = true;
+        section_state.written_generation = match clean {
+            true => Some(section.generation(memory)),
+            false => None,
+        };
+    }
+
+    if game_state.segments.active_memory_segments != requested_segments {
+        world::set_active_segments(&requested_segments);
+        game_state.segments.active_memory_segments = requested_segments;
+    }
+}
+
+/// Decode a section read from its segments and merge it into memory.
+/// Returns whether memory now matches what is stored, or if the section needs to be written again
+fn decode_cold_section(
+    memory: &mut GameMemory,
+    section: ColdSection,
+    encoded: &str,
+) -> Result<bool, GeneralError> {
+    let compressed = memory.compressed_memory;
+
+    match section {
+        ColdSection::HarvestableRooms => Ok(merge_loaded(
+            &mut memory.harvestable_rooms,
+            decode_section(encoded, compressed)?,
+        )),
+        ColdSection::KeeperRooms => Ok(merge_loaded(
+            &mut memory.keeper,
+            decode_section(encoded, compressed)?,
+        )),
+        ColdSection::PortalRooms => Ok(merge_loaded(
+            &mut memory.portal_rooms,
+            decode_section(encoded, compressed)?,
+        )),
+        ColdSection::ConstructionSites => Ok(merge_loaded(
+            &mut memory.construction_sites,
+            decode_section(encoded, compressed)?,
+        )),
+    }
+}
+
+/// Entries recorded before the section finished loading are newer than the stored ones, so they win
+fn merge_loaded<K: Eq + Hash, V>(
+    section: &mut Tracked<HashMap<K, V>>,
+    mut loaded: HashMap<K, V>,
+) -> bool {
+    let fresh = mem::take(&mut **section);
+    let clean = fresh.is_empty();
+
+    loaded.extend(fresh);
+    **section = loaded;
+
+    clean
+}
+
+/// Write cold sections that changed since they were last written, as long as the segment write budget allows
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+pub fn write_cold_sections(game_state: &mut GameState, memory: &GameMemory) {
+    let mut segment_writes = game_state.segments.memory_segment_writes;
+
+    for section in COLD_SECTIONS {
+        if game_state.segments.memory[section].loaded {
+            section_stats(game_state, section).encode_cpu = 0.;
+        }
+    }
+
+    for section in COLD_SECTIONS {
+        let section_state = &game_state.segments.memory[section];
+
+        // Writing a section we have not read yet would erase what is stored
+        if !section_state.loaded {
+            continue;
+        }
+
+        let generation = section.generation(memory);
+        if section_state.written_generation == Some(generation) {
+            continue;
+        }
+
+        // Wait for a later write rather than exceed what the game will save this tick
+        if segment_writes + section.segments().len() > MAX_MEMORY_SEGMENT_WRITES {
+            continue;
+        }
+
+        let start_cpu = world::cpu_used();
+        let Some(encoded) = encode_cold_section(memory, section) else {
+            continue;
+        };
+
+        let stats = section_stats(game_state, section);
+        stats.encode_cpu = (world::cpu_used() - start_cpu) as f32;
+        stats.bytes = encoded_bytes(&encoded, memory.compressed_memory) as u32;
+
+        if write_section_segments(section, &encoded) == GeneralResult::Fail {
+            continue;
+        }
+
+        segment_writes += section.segments().len();
+        game_state.segments.memory[section].written_generation = Some(generation);
+    }
+}
+
+fn section_stats(game_state: &mut GameState, section: ColdSection) -> &mut MemorySectionStats {
+    game_state
+        .segments
+        .stats
+        .memory_sections
+        .entry(section.name().to_string())
+        .or_insert_with(MemorySectionStats::new)
+}
+
+/// Size of the encoding before it was turned into a string. Base32768 packs 15 bits into each character
+fn encoded_bytes(encoded: &str, compressed: bool) -> usize {
+    match compressed {
+        true => encoded.chars().count() * 15 / 8,
+        false => encoded.len(),
+    }
+}
+
+pub fn encode_cold_section(memory: &GameMemory, section: ColdSection) -> Option<String> {
+    let compressed = memory.compressed_memory;
+
+    match section {
+        ColdSection::HarvestableRooms => encode_section(&*memory.harvestable_rooms, compressed),
+        ColdSection::KeeperRooms => encode_section(&*memory.keeper, compressed),
+        ColdSection::PortalRooms => encode_section(&*memory.portal_rooms, compressed),
+        ColdSection::ConstructionSites => encode_section(&*memory.construction_sites, compressed),
+    }
+}
+
+/// Whether an encoded section fits in the segments it is chunked across
+pub fn fits_segments(section: ColdSection, encoded: &str) -> bool {
+    encoded.chars().count().div_ceil(MAX_SEGMENT_LENGTH) <= section.segments().len()
+}
+
+/// Write the cold sections a migration moved out of the main blob, as the segment write budget allows.
+/// Each written section is read back from its segments next tick, the rest wait for a later tick
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+pub fn write_migrated_sections(game_state: &mut GameState, memory: &mut GameMemory) {
+    game_state.segments.memory_segment_writes = 0;
+
+    for (section, encoded) in mem::take(&mut memory.migrated_cold_sections) {
+        if game_state.segments.memory_segment_writes + section.segments().len() > MAX_MEMORY_SEGMENT_WRITES {
+            memory.migrated_cold_sections.push((section, encoded));
+            continue;
+        }
+
+        // Migrations only hand back sections that fit, so this never drops one
+        if write_section_segments(section, &encoded) == GeneralResult::Fail {
+            continue;
+        }
+
+        game_state.segments.memory_segment_writes += section.segments().len();
+        game_state.segments.memory[section].loaded = false;
+    }
+}
+
+pub fn write_section_segments(section: ColdSection, encoded: &str) -> GeneralResult {
+    let result = write_chunks(section.segments(), encoded);
+    if result == GeneralResult::Fail {
+        error!("Cold memory section {:?} does not fit in its segments", section);
+    }
+
+    result
+}
+
+/// Concatenate the chunks stored across segments. None if any of them are not active this tick
+pub fn read_chunks(segments: &[u8]) -> Option<String> {
+    let mut encoded = String::new();
+
+    for segment in segments {
+        encoded.push_str(&world::segment(*segment)?);
+    }
+
+    Some(encoded)
+}
+
+/// Split an encoded string into chunks and write them across segments, clearing any that are left over
+pub fn write_chunks(segments: &[u8], encoded: &str) -> GeneralResult {
+    let chunks = chunk_encoded(encoded);
+
+    if chunks.len() > segments.len() {
+        return GeneralResult::Fail;
+    }
+
+    for (index, segment) in segments.iter().enumerate() {
+        let chunk = chunks.get(index).cloned().unwrap_or_default();
+        world::set_segment(*segment, chunk);
+    }
+
+    GeneralResult::Success
+}
+
+/// Base32768 characters are all in the basic multilingual plane, so splitting on characters keeps every chunk in bounds
+fn chunk_encoded(encoded: &str) -> Vec<String> {
+    let chars: Vec<char> = encoded.chars().collect();
+
+    chars
+        .chunks(MAX_SEGMENT_LENGTH)
+        .map(|chunk| chunk.iter().collect())
+        .collect()
+}
+
+pub fn encode_section<T: Serialize>(value: &T, compressed: bool) -> Option<String> {
+    if !compressed {
+        return match serde_json::to_string(value) {
+            Ok(json) => Some(json),
+            Err(e) => {
+                warn!("Cold memory section JSON error {:?}", e);
+                None
+            }
+        };
+    }
+
+    let Ok(bits) = bitcode::serialize(value) else {
+        warn!("Cold memory section bitcode serialization error");
+        return None;
+    };
+
+    let Ok(base) = base32768::encode(&bits) else {
+        warn!("Cold memory section base32768 encoding error");
+        return None;
+    };
+
+    Some(base)
+}
+
+/// An empty string means the section has never been written
+pub fn decode_section<T: DeserializeOwned + Default>(
+    encoded: &str,
+    compressed: bool,
+) -> Result<T, GeneralError> {
+    if encoded.is_empty() {
+        return Ok(T::default());
+    }
+
+    if !compressed {
+        return serde_json::from_str(encoded).map_err(|_| GeneralError::Fail);
+    }
+
+    let mut bits = Vec::new();
+    if base32768::decode(encoded, &mut bits).is_err() {
+        return Err(GeneralError::Fail);
+    }
+
+    bitcode::deserialize(&bits).map_err(|_| GeneralError::Fail)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{chunk_encoded, decode_section, encode_section};
+    use crate::constants::segments::MAX_SEGMENT_LENGTH;
+
+    #[test]
+    fn sections_round_trip() {
+        let mut section: HashMap<u32, Vec<u32>> = HashMap::new();
+        section.insert(7, vec![1, 2, 3]);
+
+        for compressed in [true, false] {
+            let encoded = encode_section(&section, compressed).unwrap();
+            let decoded: HashMap<u32, Vec<u32>> = decode_section(&encoded, compressed).unwrap();
+
+            assert_eq!(decoded, section);
+        }
+    }
+
+    #[test]
+    fn unwritten_sections_are_empty() {
+        let decoded: HashMap<u32, u32> = decode_section("", true).unwrap();
+
+        assert!(decoded.is_empty());
+    }
+
+    #[test]
+    fn chunks_rejoin_to_the_original() {
+        let section: Vec<u64> = (0..40_000u64)
+            .map(|n| n.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+            .collect();
+        let encoded = encode_section(&section, true).unwrap();
+
+        let chunks = chunk_encoded(&encoded);
+        assert!(chunks.len() > 1);
+        assert!(chunks.iter().all(|chunk| chunk.chars().count() <= MAX_SEGMENT_LENGTH));
+
+        let decoded: Vec<u64> = decode_section(&chunks.concat(), true).unwrap();
+        assert_eq!(decoded, section);
+    }
+}
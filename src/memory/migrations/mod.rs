@@ -0,0 +1,458 @@
+use enum_map::Enum;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use super::{
+    game_memory::GameMemory,
+    memory_sections::{self, MemorySection, SectionedMemory, MEMORY_SECTIONS},
+    memory_segments::ColdSection,
+};
+
+pub mod v4;
+pub mod v5;
+pub mod v6;
+pub mod v7;
+pub mod v8;
+
+/// How a memory blob is encoded before it is handed to raw memory
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemoryEncoding {
+    Bitcode,
+    Json,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MigrationError {
+    /// The blob could not be decoded by any known schema
+    Undecodable,
+    /// The blob was decoded at the contained version, but there is no registered step to move it forward
+    MissingStep(u32),
+    /// The step from the contained version moves a cold section out of the blob that won't fit in its segments
+    ColdSectionTooLarge(u32),
+}
+
+/// Cold sections a step moved out of the blob, encoded for their segments
+pub type ColdSections = Vec<(ColdSection, String)>;
+
+/// Memory that records the breaking version it was written with
+pub trait Versioned: Sized {
+    fn breaking_version(&self) -> u32;
+
+    /// Decode a blob written with the live schema
+    fn decode(bytes: &[u8], encoding: MemoryEncoding) -> Result<Self, MigrationError>;
+}
+
+impl Versioned for GameMemory {
+    fn breaking_version(&self) -> u32 {
+        self.breaking_version
+    }
+
+    fn decode(bytes: &[u8], encoding: MemoryEncoding) -> Result<Self, MigrationError> {
+        memory_sections::decode(bytes, encoding)
+    }
+}
+
+/// Upgrades an encoded blob written at `from_version` into an encoded blob of `from_version + 1`.
+///
+/// Types whose shape changed are frozen in a module named after the version they were last valid for,
+/// so `detect` and `migrate` can decode the old schema without keeping the live types backwards compatible.
+pub struct Migration {
+    pub from_version: u32,
+    /// Tries to decode the blob with the frozen schema of `from_version`, returning the version stored in it
+    pub detect: fn(&[u8], MemoryEncoding) -> Option<u32>,
+    pub migrate: fn(&[u8], MemoryEncoding) -> Result<Vec<u8>, MigrationError>,
+    /// For steps that move sections out of the blob, encodes them from the `from_version` blob.
+    /// Steps never write segments themselves, the caller writes these when it has the budget
+    pub cold_sections: Option<fn(&[u8], MemoryEncoding) -> Result<ColdSections, MigrationError>>,
+}
+
+/// Every registered migration, in ascending order of `from_version`.
+/// Add a step here whenever `Settings::breaking_version` is increased
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from_version: 4,
+        detect: v4::detect,
+        migrate: v4::migrate,
+        cold_sections: Some(v4::cold_sections),
+    },
+    Migration {
+        from_version: 5,
+        detect: v5::detect,
+        migrate: v5::migrate,
+        cold_sections: None,
+    },
+    Migration {
+        from_version: 6,
+        detect: v6::detect,
+        migrate: v6::migrate,
+        cold_sections: None,
+    },
+    Migration {
+        from_version: 7,
+        detect: v7::detect,
+        migrate: v7::migrate,
+        cold_sections: None,
+    },
+    Migration {
+        from_version: 8,
+        detect: v8::detect,
+        migrate: v8::migrate,
+        cold_sections: None,
+    },
+];
+
+pub fn decode<T: DeserializeOwned>(bytes: &[u8], encoding: MemoryEncoding) -> Result<T, MigrationError> {
+    match encoding {
+        MemoryEncoding::Bitcode => bitcode::deserialize::<T>(bytes).map_err(|_| MigrationError::Undecodable),
+        MemoryEncoding::Json => serde_json::from_slice::<T>(bytes).map_err(|_| MigrationError::Undecodable),
+    }
+}
+
+pub fn encode<T: Serialize>(value: &T, encoding: MemoryEncoding) -> Result<Vec<u8>, MigrationError> {
+    match encoding {
+        MemoryEncoding::Bitcode => bitcode::serialize(value).map_err(|_| MigrationError::Undecodable),
+        MemoryEncoding::Json => serde_json::to_vec(value).map_err(|_| MigrationError::Undecodable),
+    }
+}
+
+/// Upgrades the encoding of a single section, leaving it in the same encoding
+pub type SectionMigration = fn(&[u8], MemoryEncoding) -> Result<Vec<u8>, MigrationError>;
+
+/// Rewrite only the sections whose shape changed, carrying every other section over untouched
+pub fn migrate_sections(
+    bytes: &[u8],
+    encoding: MemoryEncoding,
+    to_version: u32,
+    section_migrations: &[(MemorySection, SectionMigration)],
+) -> Result<Vec<u8>, MigrationError> {
+    match encoding {
+        MemoryEncoding::Bitcode => {
+            let mut sectioned: SectionedMemory =
+                bitcode::deserialize(bytes).map_err(|_| MigrationError::Undecodable)?;
+            if sectioned.sections.len() != MEMORY_SECTIONS.len() {
+                return Err(MigrationError::Undecodable);
+            }
+
+            for (section, migrate) in section_migrations {
+                let index = section.into_usize();
+                sectioned.sections[index] = migrate(&sectioned.sections[index], encoding)?;
+            }
+
+            sectioned.breaking_version = to_version;
+            bitcode::serialize(&sectioned).map_err(|_| MigrationError::Undecodable)
+        }
+        MemoryEncoding::Json => {
+            let Ok(serde_json::Value::Object(mut fields)) = serde_json::from_slice(bytes) else {
+                return Err(MigrationError::Undecodable);
+            };
+
+            for (section, migrate) in section_migrations {
+                let Some(value) = fields.get(section.name()) else {
+                    return Err(MigrationError::Undecodable);
+                };
+
+                let section_bytes = serde_json::to_vec(value).map_err(|_| MigrationError::Undecodable)?;
+                let migrated = migrate(&section_bytes, encoding)?;
+                let migrated = serde_json::from_slice(&migrated).map_err(|_| MigrationError::Undecodable)?;
+
+                fields.insert(section.name().to_string(), migrated);
+            }
+
+            fields.insert("breaking_version".to_string(), to_version.into());
+            serde_json::to_vec(&fields).map_err(|_| MigrationError::Undecodable)
+        }
+    }
+}
+
+/// The breaking version recorded in a blob's header, without decoding any of its sections
+pub fn header_version(bytes: &[u8], encoding: MemoryEncoding) -> Option<u32> {
+    match encoding {
+        MemoryEncoding::Bitcode => bitcode::deserialize::<SectionedMemory>(bytes)
+            .ok()
+            .map(|sectioned| sectioned.breaking_version),
+        MemoryEncoding::Json => serde_json::from_slice::<VersionProbe>(bytes)
+            .ok()
+            .map(|probe| probe.breaking_version),
+    }
+}
+
+#[derive(Deserialize)]
+/// JSON is self describing, so we can read the version without knowing the rest of the schema
+struct VersionProbe {
+    breaking_version: u32,
+}
+
+/// Find the breaking version a blob was written with.
+/// Bitcode is not self describing, so we try the live schema first and then each frozen schema, newest to oldest
+pub fn detect_version<T: Versioned>(
+    bytes: &[u8],
+    encoding: MemoryEncoding,
+    migrations: &[Migration],
+) -> Option<u32> {
+    if encoding == MemoryEncoding::Json {
+        return serde_json::from_slice::<VersionProbe>(bytes)
+            .ok()
+            .map(|probe| probe.breaking_version);
+    }
+
+    if let Ok(memory) = T::decode(bytes, encoding) {
+        return Some(memory.breaking_version());
+    }
+
+    for migration in migrations.iter().rev() {
+        // Only trust a frozen schema if the version stored inside agrees with it
+        if (migration.detect)(bytes, encoding) == Some(migration.from_version) {
+            return Some(migration.from_version);
+        }
+    }
+
+    None
+}
+
+/// Decode a blob of any registered breaking version and run each migration step until it matches `target_version`.
+/// Cold sections moved out of the blob along the way are dropped, see migrate_to_with_cold_sections
+pub fn migrate_to<T: Versioned>(
+    bytes: &[u8],
+    encoding: MemoryEncoding,
+    target_version: u32,
+    migrations: &[Migration],
+) -> Result<T, MigrationError> {
+    migrate_to_with_cold_sections(bytes, encoding, target_version, migrations).map(|(memory, _)| memory)
+}
+
+/// Like migrate_to, also handing back the cold sections steps moved out of the blob so they can be written to segments
+pub fn migrate_to_with_cold_sections<T: Versioned>(
+    bytes: &[u8],
+    encoding: MemoryEncoding,
+    target_version: u32,
+    migrations: &[Migration],
+) -> Result<(T, ColdSections), MigrationError> {
+    let Some(mut version) = detect_version::<T>(bytes, encoding, migrations) else {
+        error!("Failed to detect the breaking version of memory");
+        return Err(MigrationError::Undecodable);
+    };
+
+    if version > target_version {
+        warn!(
+            "Memory breaking version {} is newer than the bot's {}",
+            version, target_version
+        );
+        return Err(MigrationError::MissingStep(version));
+    }
+
+    let mut bytes = bytes.to_vec();
+    let mut cold_sections = ColdSections::new();
+
+    while version < target_version {
+        let Some(migration) = migrations
+            .iter()
+            .find(|migration| migration.from_version == version)
+        else {
+            warn!("No memory migration registered from breaking version {}", version);
+            return Err(MigrationError::MissingStep(version));
+        };
+
+        info!("Migrating memory from breaking version {} to {}", version, version + 1);
+
+        if let Some(migration_cold_sections) = migration.cold_sections {
+            cold_sections.extend(migration_cold_sections(&bytes, encoding)?);
+        }
+
+        bytes = (migration.migrate)(&bytes, encoding)?;
+        version += 1;
+    }
+
+    let memory = T::decode(&bytes, encoding)?;
+    if memory.breaking_version() != target_version {
+        error!(
+            "Migrated memory reports breaking version {} instead of {}",
+            memory.breaking_version(),
+            target_version
+        );
+        return Err(MigrationError::MissingStep(memory.breaking_version()));
+    }
+
+    Ok((memory, cold_sections))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    // Frozen fixtures of a small schema that changed twice
+
+    #[derive(Serialize, Deserialize)]
+    struct RemoteV1 {
+        cost: u32,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct MemoryV1 {
+        breaking_version: u32,
+        remotes: HashMap<String, RemoteV1>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct RemoteV2 {
+        cost: u32,
+        abandon: Option<u32>,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    struct MemoryV2 {
+        breaking_version: u32,
+        remotes: HashMap<String, RemoteV2>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct RemoteV3 {
+        cost: u32,
+        abandon: Option<u32>,
+        reserved: bool,
+    }
+
+    #[derive(Serialize, Deserialize, Debug)]
+    struct MemoryV3 {
+        breaking_version: u32,
+        remotes: HashMap<String, RemoteV3>,
+    }
+
+    impl Versioned for MemoryV3 {
+        fn breaking_version(&self) -> u32 {
+            self.breaking_version
+        }
+
+        fn decode(bytes: &[u8], encoding: MemoryEncoding) -> Result<Self, MigrationError> {
+            decode::<MemoryV3>(bytes, encoding)
+        }
+    }
+
+    fn detect_v1(bytes: &[u8], encoding: MemoryEncoding) -> Option<u32> {
+        decode::<MemoryV1>(bytes, encoding).ok().map(|memory| memory.breaking_version)
+    }
+
+    fn migrate_v1(bytes: &[u8], encoding: MemoryEncoding) -> Result<Vec<u8>, MigrationError> {
+        let old = decode::<MemoryV1>(bytes, encoding)?;
+
+        let remotes = old
+            .remotes
+            .into_iter()
+            .map(|(name, remote)| (name, RemoteV2 { cost: remote.cost, abandon: None }))
+            .collect();
+
+        encode(&MemoryV2 { breaking_version: 2, remotes }, encoding)
+    }
+
+    fn detect_v2(bytes: &[u8], encoding: MemoryEncoding) -> Option<u32> {
+        decode::<MemoryV2>(bytes, encoding).ok().map(|memory| memory.breaking_version)
+    }
+
+    fn migrate_v2(bytes: &[u8], encoding: MemoryEncoding) -> Result<Vec<u8>, MigrationError> {
+        let old = decode::<MemoryV2>(bytes, encoding)?;
+
+        let remotes = old
+            .remotes
+            .into_iter()
+            .map(|(name, remote)| {
+                (
+                    name,
+                    RemoteV3 {
+                        cost: remote.cost,
+                        abandon: remote.abandon,
+                        reserved: false,
+                    },
+                )
+            })
+            .collect();
+
+        encode(&MemoryV3 { breaking_version: 3, remotes }, encoding)
+    }
+
+    const TEST_MIGRATIONS: &[Migration] = &[
+        Migration {
+            from_version: 1,
+            detect: detect_v1,
+            migrate: migrate_v1,
+            cold_sections: None,
+        },
+        Migration {
+            from_version: 2,
+            detect: detect_v2,
+            migrate: migrate_v2,
+            cold_sections: None,
+        },
+    ];
+
+    fn v1_fixture() -> MemoryV1 {
+        let mut remotes = HashMap::new();
+        remotes.insert("W1N1".to_string(), RemoteV1 { cost: 12 });
+        remotes.insert("W2N1".to_string(), RemoteV1 { cost: 30 });
+
+        MemoryV1 {
+            breaking_version: 1,
+            remotes,
+        }
+    }
+
+    #[test]
+    fn migrates_bitcode_through_every_step() {
+        let bits = bitcode::serialize(&v1_fixture()).unwrap();
+
+        let memory =
+            migrate_to::<MemoryV3>(&bits, MemoryEncoding::Bitcode, 3, TEST_MIGRATIONS).unwrap();
+
+        assert_eq!(memory.breaking_version, 3);
+        assert_eq!(
+            memory.remotes.get("W1N1"),
+            Some(&RemoteV3 { cost: 12, abandon: None, reserved: false })
+        );
+        assert_eq!(memory.remotes.get("W2N1").unwrap().cost, 30);
+    }
+
+    #[test]
+    fn migrates_json_from_an_intermediate_version() {
+        let mut remotes = HashMap::new();
+        remotes.insert("W1N1".to_string(), RemoteV2 { cost: 5, abandon: Some(100) });
+        let json = serde_json::to_vec(&MemoryV2 { breaking_version: 2, remotes }).unwrap();
+
+        let memory =
+            migrate_to::<MemoryV3>(&json, MemoryEncoding::Json, 3, TEST_MIGRATIONS).unwrap();
+
+        assert_eq!(memory.remotes.get("W1N1").unwrap().abandon, Some(100));
+    }
+
+    #[test]
+    fn current_version_is_decoded_directly() {
+        let memory = MemoryV3 {
+            breaking_version: 3,
+            remotes: HashMap::new(),
+        };
+        let bits = bitcode::serialize(&memory).unwrap();
+
+        assert_eq!(
+            detect_version::<MemoryV3>(&bits, MemoryEncoding::Bitcode, TEST_MIGRATIONS),
+            Some(3)
+        );
+        assert!(migrate_to::<MemoryV3>(&bits, MemoryEncoding::Bitcode, 3, TEST_MIGRATIONS).is_ok());
+    }
+
+    #[test]
+    fn missing_step_reports_the_stuck_version() {
+        let json = serde_json::to_vec(&v1_fixture()).unwrap();
+
+        let result = migrate_to::<MemoryV3>(&json, MemoryEncoding::Json, 3, &TEST_MIGRATIONS[1..]);
+
+        assert_eq!(result.unwrap_err(), MigrationError::MissingStep(1));
+    }
+
+    #[test]
+    fn garbage_is_undecodable() {
+        let result = migrate_to::<MemoryV3>(&[1, 2, 3], MemoryEncoding::Bitcode, 3, TEST_MIGRATIONS);
+
+        assert_eq!(result.unwrap_err(), MigrationError::Undecodable);
+    }
+}
@@ -0,0 +1,268 @@
+//! Game memory as of breaking version 4, when every section still lived in the main blob
+
+use std::collections::HashMap;
+
+use log::error;
+use screeps::{ConstructionSite, ObjectId, RoomName};
+use serde::{Deserialize, Serialize};
+
+use crate::memory::{
+    ally::AllyMemory,
+    creep_memory::PowerCreepMemory,
+    enemy::EnemyMemory,
+    global_requests::{AttackRequests, ClaimRequests, DefenseRequests, WorkRequests},
+    memory_segments::{self, ColdSection},
+    room_memory::{
+        AllyRoomMemory, EnemyRoomMemory, HarvestableRoomMemory, HighwayRoomMemory,
+        PortalRoomMemory,
+    },
+    static_room_memory::{ClaimableRoomMemory, KeeperRoomMemory},
+};
+
+use super::{
+    decode, encode, v5,
+    v6::{CommuneRoomMemory, CreepMemory, RemoteRoomMemory},
+    v8::RoomMemory,
+    ColdSections, MemoryEncoding, MigrationError,
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct GameMemory {
+    pub breaking_version: u32,
+    pub me: String,
+    pub compressed_memory: bool,
+    pub rooms: HashMap<RoomName, RoomMemory>,
+    pub remotes: HashMap<RoomName, RemoteRoomMemory>,
+    pub communes: HashMap<RoomName, CommuneRoomMemory>,
+    pub claimable_rooms: HashMap<RoomName, ClaimableRoomMemory>,
+    pub highway: HashMap<RoomName, HighwayRoomMemory>,
+    pub portal_rooms: HashMap<RoomName, PortalRoomMemory>,
+    pub harvestable_rooms: HashMap<RoomName, HarvestableRoomMemory>,
+    pub keeper: HashMap<RoomName, KeeperRoomMemory>,
+    pub ally: HashMap<RoomName, AllyRoomMemory>,
+    pub enemy: HashMap<RoomName, EnemyRoomMemory>,
+    pub creeps: HashMap<String, CreepMemory>,
+    pub power_creeps: HashMap<String, PowerCreepMemory>,
+    pub work_requests: WorkRequests,
+    pub claim_requests: ClaimRequests,
+    pub attack_requests: AttackRequests,
+    pub defense_requests: DefenseRequests,
+    pub combat_stats: HashMap<String, u32>,
+    pub construction_sites: HashMap<ObjectId<ConstructionSite>, u32>,
+    pub allies: HashMap<String, AllyMemory>,
+    pub enemies: HashMap<String, EnemyMemory>,
+}
+
+pub fn detect(bytes: &[u8], encoding: MemoryEncoding) -> Option<u32> {
+    decode::<GameMemory>(bytes, encoding)
+        .ok()
+        .map(|memory| memory.breaking_version)
+}
+
+/// Version 5 moved the cold sections into their own segments
+pub fn migrate(bytes: &[u8], encoding: MemoryEncoding) -> Result<Vec<u8>, MigrationError> {
+    let memory = upgrade(decode::<GameMemory>(bytes, encoding)?);
+
+    encode(&memory, encoding)
+}
+
+/// The cold sections the new main blob skips, for the caller to write to their segments before they are dropped.
+/// Sections that would not fit in their segments abort the migration rather than be lost
+pub fn cold_sections(bytes: &[u8], encoding: MemoryEncoding) -> Result<ColdSections, MigrationError> {
+    let memory = decode::<GameMemory>(bytes, encoding)?;
+    let compressed = encoding == MemoryEncoding::Bitcode;
+
+    let mut cold_sections = ColdSections::new();
+    push_cold_section(&mut cold_sections, ColdSection::HarvestableRooms, &memory.harvestable_rooms, compressed)?;
+    push_cold_section(&mut cold_sections, ColdSection::KeeperRooms, &memory.keeper, compressed)?;
+    push_cold_section(&mut cold_sections, ColdSection::PortalRooms, &memory.portal_rooms, compressed)?;
+    push_cold_section(&mut cold_sections, ColdSection::ConstructionSites, &memory.construction_sites, compressed)?;
+
+    Ok(cold_sections)
+}
+
+fn push_cold_section<K: Serialize, V: Serialize>(
+    cold_sections: &mut ColdSections,
+    section: ColdSection,
+    value: &HashMap<K, V>,
+    compressed: bool,
+) -> Result<(), MigrationError> {
+    // Unwritten segments already read as an empty section
+    if value.is_empty() {
+        return Ok(());
+    }
+
+    let encoded = memory_segments::encode_section(value, compressed).ok_or(MigrationError::Undecodable)?;
+    if !memory_segments::fits_segments(section, &encoded) {
+        error!("Cold memory section {:?} does not fit in its segments", section);
+        return Err(MigrationError::ColdSectionTooLarge(4));
+    }
+
+    cold_sections.push((section, encoded));
+    Ok(())
+}
+
+pub fn upgrade(old: GameMemory) -> v5::GameMemory {
+    v5::GameMemory {
+        breaking_version: 5,
+        me: old.me,
+        compressed_memory: old.compressed_memory,
+        rooms: old.rooms,
+        remotes: old.remotes,
+        communes: old.communes,
+        claimable_rooms: old.claimable_rooms,
+        highway: old.highway,
+        portal_rooms: old.portal_rooms,
+        harvestable_rooms: old.harvestable_rooms,
+        keeper: old.keeper,
+        ally: old.ally,
+        enemy: old.enemy,
+        creeps: old.creeps,
+        power_creeps: old.power_creeps,
+        work_requests: old.work_requests,
+        claim_requests: old.claim_requests,
+        attack_requests: old.attack_requests,
+        defense_requests: old.defense_requests,
+        combat_stats: old.combat_stats,
+        construction_sites: old.construction_sites,
+        allies: old.allies,
+        enemies: old.enemies,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use screeps::{ConstructionSite, ObjectId, RoomName};
+
+    use crate::{
+        constants::creep::CreepRole,
+        memory::{
+            game_memory,
+            global_requests::{AttackRequests, ClaimRequests, DefenseRequests, WorkRequests},
+            memory_segments::{self, ColdSection},
+            migrations::{decode, encode, migrate_to_with_cold_sections, v5, v6, v8, MemoryEncoding, MIGRATIONS},
+            room_memory::{RoomDanger, StaticRoomType},
+        },
+    };
+
+    use super::{cold_sections, upgrade, GameMemory};
+
+    fn v4_fixture() -> GameMemory {
+        let mut construction_sites = HashMap::new();
+        let id: ObjectId<ConstructionSite> = "5bbcac9a9099fc012e6363c1".parse().unwrap();
+        construction_sites.insert(id, 40);
+
+        let mut rooms = HashMap::new();
+        rooms.insert(
+            RoomName::new("W1N1").unwrap(),
+            v8::RoomMemory {
+                room_type: StaticRoomType::Claimable,
+                danger: Some(6),
+                last_scout: 2000,
+            },
+        );
+
+        let mut creeps = HashMap::new();
+        creeps.insert(
+            "hauler".to_string(),
+            v6::CreepMemory {
+                role: CreepRole::Hauler,
+                room_from: RoomName::new("W1N1").unwrap(),
+                source_index: None,
+                scout_target: None,
+                rampart_only_shoving: None,
+                move_goal_pos: None,
+                move_path: None,
+            },
+        );
+
+        GameMemory {
+            breaking_version: 4,
+            me: "MarvinTMB".to_string(),
+            compressed_memory: true,
+            rooms,
+            remotes: HashMap::new(),
+            communes: HashMap::new(),
+            claimable_rooms: HashMap::new(),
+            highway: HashMap::new(),
+            portal_rooms: HashMap::new(),
+            harvestable_rooms: HashMap::new(),
+            keeper: HashMap::new(),
+            ally: HashMap::new(),
+            enemy: HashMap::new(),
+            creeps,
+            power_creeps: HashMap::new(),
+            work_requests: WorkRequests::new(),
+            claim_requests: ClaimRequests::new(),
+            attack_requests: AttackRequests::new(),
+            defense_requests: DefenseRequests::new(),
+            combat_stats: HashMap::new(),
+            construction_sites,
+            allies: HashMap::new(),
+            enemies: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn cold_sections_leave_the_main_blob() {
+        let memory = upgrade(v4_fixture());
+
+        assert_eq!(memory.breaking_version, 5);
+        assert_eq!(memory.me, "MarvinTMB");
+        assert_eq!(memory.construction_sites.len(), 1);
+
+        for encoding in [MemoryEncoding::Bitcode, MemoryEncoding::Json] {
+            let bytes = encode(&memory, encoding).unwrap();
+            let read = decode::<v5::GameMemory>(&bytes, encoding).unwrap();
+
+            assert_eq!(read.breaking_version, 5);
+            assert!(read.construction_sites.is_empty());
+        }
+    }
+
+    #[test]
+    fn non_empty_cold_sections_are_handed_back_for_their_segments() {
+        for encoding in [MemoryEncoding::Bitcode, MemoryEncoding::Json] {
+            let bytes = encode(&v4_fixture(), encoding).unwrap();
+
+            let sections = cold_sections(&bytes, encoding).unwrap();
+
+            assert_eq!(sections.len(), 1);
+            let (section, encoded) = &sections[0];
+            assert_eq!(*section, ColdSection::ConstructionSites);
+
+            let sites: HashMap<ObjectId<ConstructionSite>, u32> =
+                memory_segments::decode_section(encoded, encoding == MemoryEncoding::Bitcode).unwrap();
+            assert_eq!(sites.len(), 1);
+        }
+    }
+
+    #[test]
+    fn v4_memory_migrates_to_the_live_schema() {
+        for encoding in [MemoryEncoding::Bitcode, MemoryEncoding::Json] {
+            let bytes = encode(&v4_fixture(), encoding).unwrap();
+
+            let (memory, sections) =
+                migrate_to_with_cold_sections::<game_memory::GameMemory>(&bytes, encoding, 9, MIGRATIONS).unwrap();
+
+            assert_eq!(memory.breaking_version, 9);
+            assert_eq!(memory.me, "MarvinTMB");
+
+            let room = memory.rooms.get(&RoomName::new("W1N1").unwrap()).unwrap();
+            assert_eq!(room.room_type, StaticRoomType::Claimable);
+            assert_eq!(room.danger, Some(RoomDanger { combat_parts: 6, tick: 2000 }));
+            assert_eq!(room.last_scout, 2000);
+
+            let creep = memory.creeps.get("hauler").unwrap();
+            assert!(matches!(creep.role, CreepRole::Hauler));
+            assert_eq!(creep.room_from, RoomName::new("W1N1").unwrap());
+
+            // Construction sites left the blob at version 5, so they only survive in the handed back sections
+            assert!(memory.construction_sites.is_empty());
+            assert_eq!(sections.len(), 1);
+            assert_eq!(sections[0].0, ColdSection::ConstructionSites);
+        }
+    }
+}
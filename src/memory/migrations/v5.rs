@@ -0,0 +1,165 @@
+//! Game memory as of breaking version 5, when the main blob was encoded as a single struct
+
+use std::collections::HashMap;
+
+use screeps::{ConstructionSite, ObjectId, RoomName};
+use serde::{Deserialize, Serialize};
+
+use crate::memory::{
+    ally::AllyMemory,
+    creep_memory::PowerCreepMemory,
+    enemy::EnemyMemory,
+    global_requests::{AttackRequests, ClaimRequests, DefenseRequests, WorkRequests},
+    memory_sections::SectionedMemory,
+    room_memory::{
+        AllyRoomMemory, EnemyRoomMemory, HarvestableRoomMemory, HighwayRoomMemory,
+        PortalRoomMemory,
+    },
+    static_room_memory::{ClaimableRoomMemory, KeeperRoomMemory},
+};
+
+use super::{
+    decode,
+    v6::{CommuneRoomMemory, CreepMemory, RemoteRoomMemory},
+    v8::RoomMemory,
+    MemoryEncoding, MigrationError,
+};
+
+#[derive(Serialize, Deserialize)]
+pub struct GameMemory {
+    pub breaking_version: u32,
+    pub me: String,
+    pub compressed_memory: bool,
+    pub rooms: HashMap<RoomName, RoomMemory>,
+    pub remotes: HashMap<RoomName, RemoteRoomMemory>,
+    pub communes: HashMap<RoomName, CommuneRoomMemory>,
+    pub claimable_rooms: HashMap<RoomName, ClaimableRoomMemory>,
+    pub highway: HashMap<RoomName, HighwayRoomMemory>,
+    #[serde(skip)]
+    pub portal_rooms: HashMap<RoomName, PortalRoomMemory>,
+    #[serde(skip)]
+    pub harvestable_rooms: HashMap<RoomName, HarvestableRoomMemory>,
+    #[serde(skip)]
+    pub keeper: HashMap<RoomName, KeeperRoomMemory>,
+    pub ally: HashMap<RoomName, AllyRoomMemory>,
+    pub enemy: HashMap<RoomName, EnemyRoomMemory>,
+    pub creeps: HashMap<String, CreepMemory>,
+    pub power_creeps: HashMap<String, PowerCreepMemory>,
+    pub work_requests: WorkRequests,
+    pub claim_requests: ClaimRequests,
+    pub attack_requests: AttackRequests,
+    pub defense_requests: DefenseRequests,
+    pub combat_stats: HashMap<String, u32>,
+    #[serde(skip)]
+    pub construction_sites: HashMap<ObjectId<ConstructionSite>, u32>,
+    pub allies: HashMap<String, AllyMemory>,
+    pub enemies: HashMap<String, EnemyMemory>,
+}
+
+pub fn detect(bytes: &[u8], encoding: MemoryEncoding) -> Option<u32> {
+    decode::<GameMemory>(bytes, encoding)
+        .ok()
+        .map(|memory| memory.breaking_version)
+}
+
+/// Version 6 encodes each section of the main blob on its own
+pub fn migrate(bytes: &[u8], encoding: MemoryEncoding) -> Result<Vec<u8>, MigrationError> {
+    let mut old = decode::<GameMemory>(bytes, encoding)?;
+
+    match encoding {
+        MemoryEncoding::Bitcode => {
+            // In the order of the version 6 sections
+            let sections = [
+                bitcode::serialize(&old.rooms),
+                bitcode::serialize(&old.remotes),
+                bitcode::serialize(&old.communes),
+                bitcode::serialize(&old.claimable_rooms),
+                bitcode::serialize(&old.highway),
+                bitcode::serialize(&old.ally),
+                bitcode::serialize(&old.enemy),
+                bitcode::serialize(&old.creeps),
+                bitcode::serialize(&old.power_creeps),
+                bitcode::serialize(&old.work_requests),
+                bitcode::serialize(&old.claim_requests),
+                bitcode::serialize(&old.attack_requests),
+                bitcode::serialize(&old.defense_requests),
+                bitcode::serialize(&old.combat_stats),
+                bitcode::serialize(&old.allies),
+                bitcode::serialize(&old.enemies),
+            ]
+            .into_iter()
+            .collect::<Result<Vec<Vec<u8>>, _>>()
+            .map_err(|_| MigrationError::Undecodable)?;
+
+            bitcode::serialize(&SectionedMemory {
+                breaking_version: 6,
+                me: old.me,
+                compressed_memory: old.compressed_memory,
+                sections,
+            })
+            .map_err(|_| MigrationError::Undecodable)
+        }
+        // JSON memory kept its layout
+        MemoryEncoding::Json => {
+            old.breaking_version = 6;
+            serde_json::to_vec(&old).map_err(|_| MigrationError::Undecodable)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::memory::{
+        global_requests::{AttackRequests, ClaimRequests, DefenseRequests, WorkRequests},
+        migrations::{encode, migrate_to, MemoryEncoding, MIGRATIONS},
+        game_memory,
+    };
+
+    use super::GameMemory;
+
+    fn v5_fixture() -> GameMemory {
+        let mut combat_stats = HashMap::new();
+        combat_stats.insert("kills".to_string(), 9);
+
+        GameMemory {
+            breaking_version: 5,
+            me: "MarvinTMB".to_string(),
+            compressed_memory: true,
+            rooms: HashMap::new(),
+            remotes: HashMap::new(),
+            communes: HashMap::new(),
+            claimable_rooms: HashMap::new(),
+            highway: HashMap::new(),
+            portal_rooms: HashMap::new(),
+            harvestable_rooms: HashMap::new(),
+            keeper: HashMap::new(),
+            ally: HashMap::new(),
+            enemy: HashMap::new(),
+            creeps: HashMap::new(),
+            power_creeps: HashMap::new(),
+            work_requests: WorkRequests::new(),
+            claim_requests: ClaimRequests::new(),
+            attack_requests: AttackRequests::new(),
+            defense_requests: DefenseRequests::new(),
+            combat_stats,
+            construction_sites: HashMap::new(),
+            allies: HashMap::new(),
+            enemies: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn single_struct_memory_becomes_sectioned() {
+        for encoding in [MemoryEncoding::Bitcode, MemoryEncoding::Json] {
+            let bytes = encode(&v5_fixture(), encoding).unwrap();
+
+            let memory =
+                migrate_to::<game_memory::GameMemory>(&bytes, encoding, 9, MIGRATIONS).unwrap();
+
+            assert_eq!(memory.breaking_version, 9);
+            assert_eq!(memory.combat_stats.get("kills"), Some(&9));
+        }
+    }
+}
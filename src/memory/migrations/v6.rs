@@ -0,0 +1,212 @@
+//! Memory as of breaking version 6, when paths and harvest positions were stored as full positions
+
+use std::collections::HashMap;
+
+use screeps::{Position, RoomName};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::creep::CreepRole,
+    memory::{memory_sections::MemorySection, room_memory},
+};
+
+use super::{decode, encode, header_version, migrate_sections, v7, MemoryEncoding, MigrationError};
+
+#[derive(Serialize, Deserialize)]
+pub struct CreepMemory {
+    pub role: CreepRole,
+    pub room_from: RoomName,
+    pub source_index: Option<usize>,
+    pub scout_target: Option<RoomName>,
+    pub rampart_only_shoving: Option<bool>,
+    pub move_goal_pos: Option<Position>,
+    pub move_path: Option<Vec<Position>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CommuneRoomMemory {
+    pub highest_rcl: u8,
+    pub source_harvest_positions: Vec<Vec<Position>>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RemoteRoomMemory {
+    pub commune: RoomName,
+    pub source_paths: Vec<Vec<Position>>,
+    pub cost: u32,
+    pub abandon: Option<u32>,
+}
+
+pub fn detect(bytes: &[u8], encoding: MemoryEncoding) -> Option<u32> {
+    header_version(bytes, encoding)
+}
+
+/// Version 7 packs paths and harvest positions
+pub fn migrate(bytes: &[u8], encoding: MemoryEncoding) -> Result<Vec<u8>, MigrationError> {
+    migrate_sections(
+        bytes,
+        encoding,
+        7,
+        &[
+            (MemorySection::Creeps, migrate_creeps),
+            (MemorySection::Communes, migrate_communes),
+            (MemorySection::Remotes, migrate_remotes),
+        ],
+    )
+}
+
+fn migrate_creeps(bytes: &[u8], encoding: MemoryEncoding) -> Result<Vec<u8>, MigrationError> {
+    let old = decode::<HashMap<String, CreepMemory>>(bytes, encoding)?;
+
+    let creeps: HashMap<String, v7::CreepMemory> = old
+        .into_iter()
+        .map(|(name, creep)| {
+            (
+                name,
+                v7::CreepMemory {
+                    role: creep.role,
+                    room_from: creep.room_from,
+                    source_index: creep.source_index,
+                    scout_target: creep.scout_target,
+                    rampart_only_shoving: creep.rampart_only_shoving,
+                    move_goal_pos: creep.move_goal_pos,
+                    move_path: creep.move_path,
+                },
+            )
+        })
+        .collect();
+
+    encode(&creeps, encoding)
+}
+
+fn migrate_communes(bytes: &[u8], encoding: MemoryEncoding) -> Result<Vec<u8>, MigrationError> {
+    let old = decode::<HashMap<RoomName, CommuneRoomMemory>>(bytes, encoding)?;
+
+    let communes: HashMap<RoomName, room_memory::CommuneRoomMemory> = old
+        .into_iter()
+        .map(|(room_name, commune)| {
+            (
+                room_name,
+                room_memory::CommuneRoomMemory {
+                    highest_rcl: commune.highest_rcl,
+                    source_harvest_positions: commune.source_harvest_positions,
+                },
+            )
+        })
+        .collect();
+
+    encode(&communes, encoding)
+}
+
+fn migrate_remotes(bytes: &[u8], encoding: MemoryEncoding) -> Result<Vec<u8>, MigrationError> {
+    let old = decode::<HashMap<RoomName, RemoteRoomMemory>>(bytes, encoding)?;
+
+    let remotes: HashMap<RoomName, room_memory::RemoteRoomMemory> = old
+        .into_iter()
+        .map(|(room_name, remote)| {
+            (
+                room_name,
+                room_memory::RemoteRoomMemory {
+                    commune: remote.commune,
+                    source_paths: remote.source_paths,
+                    cost: remote.cost,
+                    abandon: remote.abandon,
+                },
+            )
+        })
+        .collect();
+
+    encode(&remotes, encoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use screeps::{Position, RoomCoordinate, RoomName};
+
+    use crate::{
+        constants::creep::CreepRole,
+        memory::{
+            game_memory::GameMemory,
+            memory_sections::{self, MemorySection, SectionCache},
+            migrations::{encode, migrate_to, MemoryEncoding, MIGRATIONS},
+        },
+    };
+
+    use super::{CreepMemory, RemoteRoomMemory};
+
+    fn pos(x: u8, y: u8) -> Position {
+        Position::new(
+            RoomCoordinate::new(x).unwrap(),
+            RoomCoordinate::new(y).unwrap(),
+            RoomName::new("W1N1").unwrap(),
+        )
+    }
+
+    /// A version 6 blob, built by swapping the frozen sections into live memory's encoding
+    fn v6_blob(encoding: MemoryEncoding) -> Vec<u8> {
+        let mut creeps = HashMap::new();
+        creeps.insert(
+            "harvester".to_string(),
+            CreepMemory {
+                role: CreepRole::SourceHarvester,
+                room_from: RoomName::new("W1N1").unwrap(),
+                source_index: Some(0),
+                scout_target: None,
+                rampart_only_shoving: None,
+                move_goal_pos: Some(pos(12, 10)),
+                move_path: Some(vec![pos(10, 10), pos(11, 10), pos(12, 10)]),
+            },
+        );
+
+        let mut remotes = HashMap::new();
+        remotes.insert(
+            RoomName::new("W2N1").unwrap(),
+            RemoteRoomMemory {
+                commune: RoomName::new("W1N1").unwrap(),
+                source_paths: vec![vec![pos(5, 5), pos(6, 6)]],
+                cost: 4,
+                abandon: None,
+            },
+        );
+
+        let memory = GameMemory::empty("MarvinTMB".to_string(), 6, true);
+
+        match encoding {
+            MemoryEncoding::Bitcode => {
+                let bytes = memory_sections::encode_sectioned(&memory, &mut SectionCache::new()).unwrap();
+                let mut sectioned: memory_sections::SectionedMemory = bitcode::deserialize(&bytes).unwrap();
+
+                sectioned.sections[MemorySection::Creeps as usize] = encode(&creeps, encoding).unwrap();
+                sectioned.sections[MemorySection::Remotes as usize] = encode(&remotes, encoding).unwrap();
+
+                bitcode::serialize(&sectioned).unwrap()
+            }
+            MemoryEncoding::Json => {
+                let mut json = serde_json::to_value(&memory).unwrap();
+                json["creeps"] = serde_json::to_value(&creeps).unwrap();
+                json["remotes"] = serde_json::to_value(&remotes).unwrap();
+
+                serde_json::to_vec(&json).unwrap()
+            }
+        }
+    }
+
+    #[test]
+    fn paths_are_packed() {
+        for encoding in [MemoryEncoding::Bitcode, MemoryEncoding::Json] {
+            let memory = migrate_to::<GameMemory>(&v6_blob(encoding), encoding, 9, MIGRATIONS).unwrap();
+
+            assert_eq!(memory.breaking_version, 9);
+            assert_eq!(
+                memory.creeps.get("harvester").unwrap().move_path,
+                Some(vec![pos(10, 10), pos(11, 10), pos(12, 10)])
+            );
+            assert_eq!(
+                memory.remotes.get(&RoomName::new("W2N1").unwrap()).unwrap().source_paths,
+                vec![vec![pos(5, 5), pos(6, 6)]]
+            );
+        }
+    }
+}
@@ -0,0 +1,136 @@
+//! Memory as of breaking version 7, before creeps counted the ticks they were stuck
+
+use std::collections::HashMap;
+
+use screeps::{Position, RoomName};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::creep::CreepRole,
+    memory::{creep_memory, memory_sections::MemorySection},
+    utils::serialize,
+};
+
+use super::{decode, encode, header_version, migrate_sections, MemoryEncoding, MigrationError};
+
+#[derive(Serialize, Deserialize)]
+pub struct CreepMemory {
+    pub role: CreepRole,
+    pub room_from: RoomName,
+    pub source_index: Option<usize>,
+    pub scout_target: Option<RoomName>,
+    pub rampart_only_shoving: Option<bool>,
+    pub move_goal_pos: Option<Position>,
+    #[serde(with = "serialize::option_path_packed")]
+    pub move_path: Option<Vec<Position>>,
+}
+
+pub fn detect(bytes: &[u8], encoding: MemoryEncoding) -> Option<u32> {
+    header_version(bytes, encoding)
+}
+
+/// Version 8 adds stuck counters to creeps
+pub fn migrate(bytes: &[u8], encoding: MemoryEncoding) -> Result<Vec<u8>, MigrationError> {
+    migrate_sections(bytes, encoding, 8, &[(MemorySection::Creeps, migrate_creeps)])
+}
+
+fn migrate_creeps(bytes: &[u8], encoding: MemoryEncoding) -> Result<Vec<u8>, MigrationError> {
+    let old = decode::<HashMap<String, CreepMemory>>(bytes, encoding)?;
+
+    let creeps: HashMap<String, creep_memory::CreepMemory> = old
+        .into_iter()
+        .map(|(name, creep)| {
+            (
+                name,
+                creep_memory::CreepMemory {
+                    role: creep.role,
+                    room_from: creep.room_from,
+                    source_index: creep.source_index,
+                    scout_target: creep.scout_target,
+                    rampart_only_shoving: creep.rampart_only_shoving,
+                    move_goal_pos: creep.move_goal_pos,
+                    move_path: creep.move_path,
+                    stuck_ticks: 0,
+                },
+            )
+        })
+        .collect();
+
+    encode(&creeps, encoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use screeps::{Position, RoomCoordinate, RoomName};
+
+    use crate::{
+        constants::creep::CreepRole,
+        memory::{
+            game_memory::GameMemory,
+            memory_sections::{self, MemorySection, SectionCache},
+            migrations::{encode, migrate_to, MemoryEncoding, MIGRATIONS},
+        },
+    };
+
+    use super::CreepMemory;
+
+    fn pos(x: u8, y: u8) -> Position {
+        Position::new(
+            RoomCoordinate::new(x).unwrap(),
+            RoomCoordinate::new(y).unwrap(),
+            RoomName::new("W1N1").unwrap(),
+        )
+    }
+
+    /// A version 7 blob, built by swapping the frozen creeps into live memory's encoding
+    fn v7_blob(encoding: MemoryEncoding) -> Vec<u8> {
+        let mut creeps = HashMap::new();
+        creeps.insert(
+            "hauler".to_string(),
+            CreepMemory {
+                role: CreepRole::Hauler,
+                room_from: RoomName::new("W1N1").unwrap(),
+                source_index: None,
+                scout_target: None,
+                rampart_only_shoving: Some(true),
+                move_goal_pos: Some(pos(12, 10)),
+                move_path: Some(vec![pos(10, 10), pos(11, 10), pos(12, 10)]),
+            },
+        );
+
+        let memory = GameMemory::empty("MarvinTMB".to_string(), 7, true);
+
+        match encoding {
+            MemoryEncoding::Bitcode => {
+                let bytes = memory_sections::encode_sectioned(&memory, &mut SectionCache::new()).unwrap();
+                let mut sectioned: memory_sections::SectionedMemory = bitcode::deserialize(&bytes).unwrap();
+
+                sectioned.sections[MemorySection::Creeps as usize] = encode(&creeps, encoding).unwrap();
+
+                bitcode::serialize(&sectioned).unwrap()
+            }
+            MemoryEncoding::Json => {
+                let mut json = serde_json::to_value(&memory).unwrap();
+                json["creeps"] = serde_json::to_value(&creeps).unwrap();
+
+                serde_json::to_vec(&json).unwrap()
+            }
+        }
+    }
+
+    #[test]
+    fn creeps_start_unstuck() {
+        for encoding in [MemoryEncoding::Bitcode, MemoryEncoding::Json] {
+            let memory = migrate_to::<GameMemory>(&v7_blob(encoding), encoding, 9, MIGRATIONS).unwrap();
+
+            assert_eq!(memory.breaking_version, 9);
+
+            let hauler = memory.creeps.get("hauler").unwrap();
+            assert_eq!(hauler.stuck_ticks, 0);
+            assert_eq!(hauler.rampart_only_shoving, Some(true));
+            assert_eq!(hauler.move_path, Some(vec![pos(10, 10), pos(11, 10), pos(12, 10)]));
+        }
+    }
+}
@@ -0,0 +1,133 @@
+//! Memory as of breaking version 8, before room danger carried the tick it was seen
+
+use std::collections::HashMap;
+
+use screeps::RoomName;
+use serde::{Deserialize, Serialize};
+
+use crate::memory::{
+    memory_sections::MemorySection,
+    room_memory::{self, RoomDanger, StaticRoomType},
+};
+
+use super::{decode, encode, header_version, migrate_sections, MemoryEncoding, MigrationError};
+
+#[derive(Serialize, Deserialize)]
+pub struct RoomMemory {
+    pub room_type: StaticRoomType,
+    pub danger: Option<u32>,
+    pub last_scout: u32,
+}
+
+pub fn detect(bytes: &[u8], encoding: MemoryEncoding) -> Option<u32> {
+    header_version(bytes, encoding)
+}
+
+/// Version 9 timestamps room danger and records enemy towers
+pub fn migrate(bytes: &[u8], encoding: MemoryEncoding) -> Result<Vec<u8>, MigrationError> {
+    migrate_sections(bytes, encoding, 9, &[(MemorySection::Rooms, migrate_rooms)])
+}
+
+fn migrate_rooms(bytes: &[u8], encoding: MemoryEncoding) -> Result<Vec<u8>, MigrationError> {
+    let old = decode::<HashMap<RoomName, RoomMemory>>(bytes, encoding)?;
+
+    let rooms: HashMap<RoomName, room_memory::RoomMemory> = old
+        .into_iter()
+        .map(|(room_name, room)| {
+            (
+                room_name,
+                room_memory::RoomMemory {
+                    room_type: room.room_type,
+                    // The old danger had no timestamp, so treat it as seen on the last scout
+                    danger: room.danger.filter(|danger| *danger > 0).map(|danger| RoomDanger {
+                        combat_parts: danger,
+                        tick: room.last_scout,
+                    }),
+                    enemy_towers: 0,
+                    last_scout: room.last_scout,
+                },
+            )
+        })
+        .collect();
+
+    encode(&rooms, encoding)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use screeps::RoomName;
+
+    use crate::memory::{
+        game_memory::GameMemory,
+        memory_sections::{self, MemorySection, SectionCache},
+        migrations::{encode, migrate_to, MemoryEncoding, MIGRATIONS},
+        room_memory::{RoomDanger, StaticRoomType},
+    };
+
+    use super::RoomMemory;
+
+    /// A version 8 blob, built by swapping the frozen rooms into live memory's encoding
+    fn v8_blob(encoding: MemoryEncoding) -> Vec<u8> {
+        let mut rooms = HashMap::new();
+        rooms.insert(
+            RoomName::new("W1N1").unwrap(),
+            RoomMemory {
+                room_type: StaticRoomType::Claimable,
+                danger: Some(12),
+                last_scout: 4000,
+            },
+        );
+        rooms.insert(
+            RoomName::new("W2N1").unwrap(),
+            RoomMemory {
+                room_type: StaticRoomType::CardinalHighway,
+                danger: Some(0),
+                last_scout: 3000,
+            },
+        );
+
+        let memory = GameMemory::empty("MarvinTMB".to_string(), 8, true);
+
+        match encoding {
+            MemoryEncoding::Bitcode => {
+                let bytes = memory_sections::encode_sectioned(&memory, &mut SectionCache::new()).unwrap();
+                let mut sectioned: memory_sections::SectionedMemory = bitcode::deserialize(&bytes).unwrap();
+
+                sectioned.sections[MemorySection::Rooms as usize] = encode(&rooms, encoding).unwrap();
+
+                bitcode::serialize(&sectioned).unwrap()
+            }
+            MemoryEncoding::Json => {
+                let mut json = serde_json::to_value(&memory).unwrap();
+                json["rooms"] = serde_json::to_value(&rooms).unwrap();
+
+                serde_json::to_vec(&json).unwrap()
+            }
+        }
+    }
+
+    #[test]
+    fn danger_is_timestamped_at_the_last_scout() {
+        for encoding in [MemoryEncoding::Bitcode, MemoryEncoding::Json] {
+            let memory = migrate_to::<GameMemory>(&v8_blob(encoding), encoding, 9, MIGRATIONS).unwrap();
+
+            assert_eq!(memory.breaking_version, 9);
+
+            let dangerous = memory.rooms.get(&RoomName::new("W1N1").unwrap()).unwrap();
+            assert_eq!(
+                dangerous.danger,
+                Some(RoomDanger {
+                    combat_parts: 12,
+                    tick: 4000
+                })
+            );
+            assert_eq!(dangerous.enemy_towers, 0);
+
+            let safe = memory.rooms.get(&RoomName::new("W2N1").unwrap()).unwrap();
+            assert_eq!(safe.danger, None);
+            assert_eq!(safe.last_scout, 3000);
+        }
+    }
+}
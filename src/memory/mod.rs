@@ -3,5 +3,11 @@ pub mod creep_memory;
 pub mod enemy;
 pub mod game_memory;
 pub mod global_requests;
+pub mod inspector;
+pub mod memory_recovery;
+pub mod memory_sections;
+pub mod memory_segments;
+pub mod migrations;
 pub mod room_memory;
 pub mod static_room_memory;
+pub mod tracked;
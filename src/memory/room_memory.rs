@@ -2,16 +2,14 @@ use std::{collections::HashSet, default, u8};
 
 use enum_map::Enum;
 use screeps::{
-    FindConstant, HasPosition, InterShardPortalDestination, ObjectId, PortalDestination, Position,
-    Room, RoomCoordinate, RoomName, Source, constants, find,
-    game::{self, map::RoomStatus},
+    ObjectId, Position, RoomCoordinate, RoomName, Source, constants, game::map::RoomStatus,
 };
 use serde::{Deserialize, Serialize};
 
 use crate::{
     constants::{general::{GeneralError, GeneralResult}, spawning::spawn_priority_bounds::SOURCE_HARVESTER},
     room::room_ops::{self, find_room_type},
-    state::game::GameState, utils::pos::get_adjacent_positions,
+    state::game::GameState, utils::{pos::get_adjacent_positions, serialize}, world,
 };
 
 use super::game_memory::GameMemory;
@@ -19,10 +17,19 @@ use super::game_memory::GameMemory;
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RoomMemory {
     pub room_type: StaticRoomType,
-    pub danger: Option<u32>,
+    pub danger: Option<RoomDanger>,
+    /// How many enemy towers were seen the last time the room was scouted
+    pub enemy_towers: u8,
     pub last_scout: u32,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+/// Hostile combat presence seen in a room, decayed by route costs based on its age
+pub struct RoomDanger {
+    pub combat_parts: u32,
+    pub tick: u32,
+}
+
 impl RoomMemory {
     pub fn new(
         room_name: &RoomName,
@@ -35,6 +42,7 @@ impl RoomMemory {
             room_type,
             last_scout: game_state.tick,
             danger: None,
+            enemy_towers: 0,
         })
     }
 }
@@ -74,6 +82,7 @@ pub struct CommuneRoomMemory {
     /// The highest controller level the room has had without loosing ownership (implied by commune memory existing)
     pub highest_rcl: u8,
     // Derived from completed commune base plans
+    #[serde(with = "serialize::nested_room_positions_packed")]
     pub source_harvest_positions: Vec<Vec<Position>>,
 }
 
@@ -84,12 +93,15 @@ impl CommuneRoomMemory {
             return Err(GeneralError::Fail);
         };
 
-        let controller = room.controller().unwrap();
-        let rcl = controller.level();
+        let controller = room.controller.as_ref().unwrap();
+        let rcl = controller.level;
         
         // Temporary construction of source harvest positions
         let sparse_terrain = room_ops::sparse_terrain(room_name, game_state);
-        let harvestable_room_memory = memory.harvestable_rooms.get(room_name).unwrap();
+        // The harvestable section may not have loaded from its segments yet
+        let Some(harvestable_room_memory) = memory.harvestable_rooms.get(room_name) else {
+            return Err(GeneralError::Fail);
+        };
         
         let source_harvest_positions = harvestable_room_memory.source_positions.iter().map(|pos| {
             let mut positions = Vec::new();
@@ -117,7 +129,7 @@ impl CommuneRoomMemory {
 pub struct RemoteRoomMemory {
     pub commune: RoomName,
     /// The paths from the controller to the sources
-    /* #[serde(with = "screeps::local::serde_position_packed")] */
+    #[serde(with = "serialize::paths_packed")]
     pub source_paths: Vec<Vec<Position>>,
     /// Not really sure what this is for
     pub cost: u32,
@@ -191,7 +203,7 @@ pub struct HarvestableRoomMemory {
 impl HarvestableRoomMemory {
     pub fn new(room_name: &RoomName, game_state: &mut GameState) -> Result<Self, GeneralError> {
         let sources = room_ops::get_sources(room_name, game_state);
-        let source_positions: Vec<Position> = sources.iter().map(|source| source.pos()).collect();
+        let source_positions: Vec<Position> = sources.iter().map(|source| source.pos).collect();
 
         let Some(room) = game_state.rooms.get(room_name) else {
             return Err(GeneralError::Fail);
@@ -199,12 +211,12 @@ impl HarvestableRoomMemory {
 
         // Mineral type
 
-        let minerals = room.find(find::MINERALS, None);
+        let minerals = world::minerals(*room_name);
         let Some(mineral) = minerals.first() else {
             return Err(GeneralError::Fail);
         };
-        let mineral_type = mineral.mineral_type();
-        let mineral_pos = mineral.pos();
+        let mineral_type = mineral.mineral_type;
+        let mineral_pos = mineral.pos;
 
         Ok(Self {
             source_positions,
@@ -234,11 +246,8 @@ impl PortalRoomMemory {
             .iter()
             .map(|portal| {
                 (
-                    match portal.destination() {
-                        PortalDestination::InterRoom(pos) => pos.room_name(),
-                        PortalDestination::InterShard(destination) => destination.room(),
-                    },
-                    portal.pos(),
+                    portal.portal_destination.unwrap(),
+                    portal.pos,
                 )
             })
             .collect();
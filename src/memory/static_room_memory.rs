@@ -1,8 +1,6 @@
 use std::collections::HashSet;
 
-use screeps::{
-    HasPosition, Mineral, OwnedStructureProperties, Position, RoomName, constants, find,
-};
+use screeps::{Position, RoomName, constants};
 use serde::{Deserialize, Serialize};
 
 use crate::{constants::general::GeneralError, room::room_ops, state::game::GameState};
@@ -32,18 +30,17 @@ impl ClaimableRoomMemory {
 
         // Controller pos
 
-        let Some(controller) = room.controller() else {
+        let Some(controller) = &room.controller else {
             return Err(GeneralError::Fail);
         };
-        let controller_pos = controller.pos();
+        let controller_pos = controller.pos;
 
         // Controller owner
 
         let mut my_claim = None;
         let mut non_me_owner = None;
 
-        if let Some(owner) = controller.owner() {
-            let username = owner.username();
+        if let Some(username) = controller.owner.clone() {
             if username == memory.me {
                 my_claim = Some(true);
             } else {
@@ -53,8 +50,8 @@ impl ClaimableRoomMemory {
 
         Ok(Self {
             controller_pos,
-            my_claim: None,
-            non_me_owner: None,
+            my_claim,
+            non_me_owner,
         })
     }
 }
@@ -75,7 +72,7 @@ impl KeeperRoomMemory {
         let keeper_lairs = &room_ops::structures_by_type(room_name, game_state).keeper_lair;
         let keeper_lair_positions = keeper_lairs
             .iter()
-            .map(|keeper_lair| keeper_lair.pos())
+            .map(|keeper_lair| keeper_lair.pos)
             .collect();
 
         Self {
@@ -0,0 +1,78 @@
+use std::ops::{Deref, DerefMut};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+#[serde(transparent)]
+/// A section of memory that counts how often it has been mutably borrowed, so writers can tell if it may have changed.
+/// Serializes exactly like the value it wraps
+pub struct Tracked<T> {
+    value: T,
+    #[serde(skip)]
+    generation: u32,
+}
+
+impl<T> Tracked<T> {
+    pub fn new(value: T) -> Self {
+        Self {
+            value,
+            generation: 0,
+        }
+    }
+
+    /// Changes every time the value is mutably borrowed
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Tracked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.generation = self.generation.wrapping_add(1);
+        &mut self.value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::Tracked;
+
+    #[test]
+    fn mutable_borrows_advance_the_generation() {
+        let mut tracked: Tracked<HashMap<u32, u32>> = Tracked::default();
+        let generation = tracked.generation();
+
+        tracked.get(&1);
+        assert_eq!(tracked.generation(), generation);
+
+        tracked.insert(1, 2);
+        assert_ne!(tracked.generation(), generation);
+    }
+
+    #[test]
+    fn serializes_like_the_inner_value() {
+        let mut map = HashMap::new();
+        map.insert(1u32, 2u32);
+
+        let tracked = Tracked::new(map.clone());
+
+        assert_eq!(
+            serde_json::to_string(&tracked).unwrap(),
+            serde_json::to_string(&map).unwrap()
+        );
+        assert_eq!(
+            bitcode::serialize(&tracked).unwrap(),
+            bitcode::serialize(&map).unwrap()
+        );
+    }
+}
@@ -1,9 +1,9 @@
-use room_costs::economy_room_costs;
+use room_costs::{avoid_creeps_room_costs, block_creeps_room_costs, economy_room_costs};
 use route_costs::economy_creep_costs;
 use screeps::RoomName;
 use screeps_utils::sparse_cost_matrix::SparseCostMatrix;
 
-use crate::{memory::game_memory::GameMemory, state::game::GameState};
+use crate::{constants::move_costs::DEFAULT_LAND_COST, memory::game_memory::GameMemory, state::game::GameState};
 
 pub mod portal_router_multi;
 pub mod portal_router_single;
@@ -11,12 +11,17 @@ pub mod pathfinding_services_multi;
 pub mod pathfinding_services_single;
 pub mod room_pather_multi;
 pub mod room_pather_single;
+pub mod room_search;
 pub mod route_costs;
 pub mod room_costs;
+pub mod path_cache_ops;
+#[cfg(test)]
+mod room_pather_benches;
 
 pub struct PathfindingOpts {
     pub room_pathfinder_opts: RoomPathfinderOpts,
-    pub route_callback: RouteCallback,
+    pub route_profile: RouteProfile,
+    /// Overrides room_pathfinder_opts.avoid_enemy_creeps when set
     pub avoid_enemy_creeps: Option<bool>,
 }
 
@@ -24,27 +29,74 @@ impl PathfindingOpts {
     pub fn new() -> Self {
         Self {
             room_pathfinder_opts: RoomPathfinderOpts::new(),
-            route_callback: economy_creep_costs,
+            route_profile: RouteProfile::Economy,
             avoid_enemy_creeps: None,
         }
     }
 }
 
 pub type RoomCallback = fn(&RoomName) -> u8;
+pub type RoomCostCallback = fn(&RoomName, &mut GameState, &GameMemory) -> SparseCostMatrix;
 pub type RouteCallback = fn(&RoomName, &GameMemory) -> u8;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The room costs a search uses. Named rather than passed as a callback so paths found with them can be shared
+pub enum CostProfile {
+    Economy,
+    /// Economy costs that add the creeps in the way
+    AvoidCreeps,
+    /// Economy costs that block the creeps in the way
+    BlockCreeps,
+}
+
+impl CostProfile {
+    pub fn callback(self) -> RoomCostCallback {
+        match self {
+            CostProfile::Economy => economy_room_costs,
+            CostProfile::AvoidCreeps => avoid_creeps_room_costs,
+            CostProfile::BlockCreeps => block_creeps_room_costs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// The room traversal costs a route uses
+pub enum RouteProfile {
+    Economy,
+}
+
+impl RouteProfile {
+    pub fn callback(self) -> RouteCallback {
+        match self {
+            RouteProfile::Economy => economy_creep_costs,
+        }
+    }
+}
+
 pub struct RoomPathfinderOpts {
-    pub cost_callback: fn(&RoomName, &mut GameState, &GameMemory) -> SparseCostMatrix,
+    pub cost_profile: CostProfile,
     pub allow_outside_origin_room: bool,
+    /// Block tiles enemy creeps can attack and add the damage of enemy towers to tile costs.
+    /// Fleeing never ends on a blocked tile but may pass through them
     pub avoid_enemy_attackers: bool,
+    /// Block tiles enemy creeps stand on
+    pub avoid_enemy_creeps: bool,
+    /// The cheapest tile cost_profile can produce. The search's heuristic is scaled by it,
+    /// so it must not be above any cost the callback sets
+    pub min_traverse_cost: u8,
+    /// Treat goals as threats, finding the cheapest path to a tile out of range of all of them
+    pub flee: bool,
 }
 
 impl RoomPathfinderOpts {
     pub fn new() -> Self {
         Self {
-            cost_callback: economy_room_costs,
+            cost_profile: CostProfile::Economy,
             allow_outside_origin_room: true,
             avoid_enemy_attackers: false,
+            avoid_enemy_creeps: false,
+            min_traverse_cost: DEFAULT_LAND_COST,
+            flee: false,
         }
     }
 }
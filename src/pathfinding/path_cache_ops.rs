@@ -0,0 +1,265 @@
+use std::{collections::HashMap, rc::Rc};
+
+use screeps::{Position, RoomName};
+
+use crate::{
+    constants::move_costs::{MAX_CACHED_PATHS_PER_GOAL, PATH_CACHE_LIFETIME},
+    memory::game_memory::GameMemory,
+    room::room_ops,
+    state::{
+        game::GameState,
+        path_cache::{CachedPath, PathCacheKey},
+    },
+    world,
+};
+
+/// A valid shared path for the key that passes through the position, and the position's index in it.
+/// Paths found to be invalid are dropped from the cache
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+pub fn find_cached_path(
+    pos: Position,
+    key: &PathCacheKey,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+) -> Option<(Rc<CachedPath>, usize)> {
+    let cached_paths = game_state.path_cache.paths.get(key)?.clone();
+
+    for cached_path in cached_paths {
+        let Some(index) = cached_path.indexes.get(&pos).copied() else {
+            continue;
+        };
+
+        // Standing on the last position leaves nothing to follow
+        if index + 1 >= cached_path.path.len() {
+            continue;
+        }
+
+        if !is_valid(&cached_path, game_state, memory) {
+            remove_path(&cached_path, game_state);
+            continue;
+        }
+
+        return Some((cached_path, index));
+    }
+
+    None
+}
+
+/// Whether the path is young enough and nothing it crosses has changed since it was found
+pub fn is_valid(cached_path: &CachedPath, game_state: &mut GameState, memory: &GameMemory) -> bool {
+    if world::time() - cached_path.created >= PATH_CACHE_LIFETIME {
+        return false;
+    }
+
+    cached_path.signatures.iter().all(|(room_name, signature)| {
+        room_ops::move_costs_signature(room_name, game_state, memory) == *signature
+            && !(cached_path.key.avoids_enemies() && room_ops::has_enemy_threats(room_name, game_state, memory))
+    })
+}
+
+/// Share a path found for the key. Paths that avoid enemies through rooms with enemy threats
+/// depend on where the enemies are this tick, so they are not shared
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+pub fn cache_path(
+    key: PathCacheKey,
+    path: Vec<Position>,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+) -> Option<Rc<CachedPath>> {
+    let mut room_names: Vec<RoomName> = Vec::new();
+    for pos in &path {
+        if !room_names.contains(&pos.room_name()) {
+            room_names.push(pos.room_name());
+        }
+    }
+
+    let mut signatures = Vec::new();
+    for room_name in room_names {
+        if key.avoids_enemies() && room_ops::has_enemy_threats(&room_name, game_state, memory) {
+            return None;
+        }
+
+        signatures.push((room_name, room_ops::move_costs_signature(&room_name, game_state, memory)));
+    }
+
+    let indexes: HashMap<Position, usize> = path
+        .iter()
+        .enumerate()
+        .map(|(index, pos)| (*pos, index))
+        .collect();
+
+    let cached_path = Rc::new(CachedPath {
+        key,
+        path,
+        indexes,
+        signatures,
+        created: world::time(),
+    });
+
+    let cached_paths = game_state.path_cache.paths.entry(key).or_default();
+    if cached_paths.len() >= MAX_CACHED_PATHS_PER_GOAL {
+        cached_paths.remove(0);
+    }
+    cached_paths.push(cached_path.clone());
+
+    Some(cached_path)
+}
+
+fn remove_path(cached_path: &Rc<CachedPath>, game_state: &mut GameState) {
+    let Some(cached_paths) = game_state.path_cache.paths.get_mut(&cached_path.key) else {
+        return;
+    };
+
+    cached_paths.retain(|other| !Rc::ptr_eq(other, cached_path));
+    if cached_paths.is_empty() {
+        game_state.path_cache.paths.remove(&cached_path.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use screeps::{Part, RoomName, StructureType};
+
+    use super::{cache_path, find_cached_path};
+    use crate::{
+        memory::game_memory::GameMemory,
+        pathfinding::{
+            pathfinding_services_single::try_find_path, room_pather_single::PathGoal,
+            PathfindingOpts,
+        },
+        state::{game::GameState, path_cache::PathCacheKey},
+        world::mock_world::{self, MockWorld},
+    };
+
+    fn room_name() -> RoomName {
+        RoomName::new("W1N1").unwrap()
+    }
+
+    fn start(world: MockWorld) -> (GameState, GameMemory) {
+        mock_world::install(world);
+
+        let mut game_state = GameState::new();
+        let mut memory = mock_world::new_memory();
+        game_state.tick_update(&mut memory);
+
+        (game_state, memory)
+    }
+
+    fn next_tick(game_state: &mut GameState, memory: &mut GameMemory) {
+        mock_world::with(|mock| mock.next_tick());
+        game_state.tick_update(memory);
+    }
+
+    /// Find and share a path along row 25 from x 5 to within 1 of x 40
+    fn cache_row_path(game_state: &mut GameState, memory: &GameMemory) -> PathCacheKey {
+        let goal = PathGoal::new(mock_world::pos(room_name(), 40, 25), 1);
+        let opts = PathfindingOpts::new();
+        let key = PathCacheKey::new(&goal, &opts);
+
+        let path = try_find_path(mock_world::pos(room_name(), 5, 25), &goal, opts, game_state, memory).unwrap();
+        assert!(cache_path(key, path, game_state, memory).is_some());
+
+        key
+    }
+
+    #[test]
+    fn creeps_along_a_path_share_it() {
+        let mut world = MockWorld::new();
+        world.add_room(room_name());
+        let (mut game_state, mut memory) = start(world);
+
+        let key = cache_row_path(&mut game_state, &memory);
+        next_tick(&mut game_state, &mut memory);
+
+        let (first, first_index) =
+            find_cached_path(mock_world::pos(room_name(), 5, 25), &key, &mut game_state, &memory).unwrap();
+        let along = first.path[10];
+        let (second, second_index) = find_cached_path(along, &key, &mut game_state, &memory).unwrap();
+
+        assert!(Rc::ptr_eq(&first, &second));
+        assert_eq!(first_index, 0);
+        assert_eq!(second_index, 10);
+
+        // Off the path there is nothing to follow
+        assert!(find_cached_path(mock_world::pos(room_name(), 5, 40), &key, &mut game_state, &memory).is_none());
+    }
+
+    #[test]
+    fn new_impassible_structures_invalidate_paths() {
+        let mut world = MockWorld::new();
+        world.add_room(room_name());
+        let (mut game_state, mut memory) = start(world);
+
+        let key = cache_row_path(&mut game_state, &memory);
+
+        mock_world::with(|mock| {
+            mock.add_structure(StructureType::Extension, mock_world::pos(room_name(), 45, 5));
+        });
+        next_tick(&mut game_state, &mut memory);
+
+        assert!(find_cached_path(mock_world::pos(room_name(), 5, 25), &key, &mut game_state, &memory).is_none());
+        assert!(game_state.path_cache.paths.is_empty());
+    }
+
+    #[test]
+    fn paths_avoiding_enemies_are_shared_once_they_leave() {
+        let mut world = MockWorld::new();
+        world.add_room(room_name());
+        let mut enemy = mock_world::new_creep(
+            "enemy",
+            None,
+            "Enemy",
+            mock_world::pos(room_name(), 25, 40),
+            &[Part::Attack, Part::Move],
+        );
+        enemy.my = false;
+        world.room_mut(room_name()).hostile_creeps.push(enemy);
+        let (mut game_state, mut memory) = start(world);
+
+        let goal = PathGoal::new(mock_world::pos(room_name(), 40, 25), 1);
+        let origin = mock_world::pos(room_name(), 5, 25);
+        let avoiding_opts = || {
+            let mut opts = PathfindingOpts::new();
+            opts.room_pathfinder_opts.avoid_enemy_attackers = true;
+            opts.room_pathfinder_opts.avoid_enemy_creeps = true;
+            opts
+        };
+
+        let key = PathCacheKey::new(&goal, &avoiding_opts());
+        let path = try_find_path(origin, &goal, avoiding_opts(), &mut game_state, &memory).unwrap();
+        assert!(cache_path(key, path, &mut game_state, &memory).is_none());
+
+        // Paths that ignore enemies don't depend on them
+        let opts = PathfindingOpts::new();
+        let ignoring_key = PathCacheKey::new(&goal, &opts);
+        let path = try_find_path(origin, &goal, opts, &mut game_state, &memory).unwrap();
+        assert!(cache_path(ignoring_key, path, &mut game_state, &memory).is_some());
+
+        mock_world::with(|mock| mock.room_mut(room_name()).hostile_creeps.clear());
+        next_tick(&mut game_state, &mut memory);
+
+        let path = try_find_path(origin, &goal, avoiding_opts(), &mut game_state, &memory).unwrap();
+        assert!(cache_path(key, path, &mut game_state, &memory).is_some());
+        assert!(find_cached_path(origin, &key, &mut game_state, &memory).is_some());
+    }
+
+    #[test]
+    fn paths_found_with_other_opts_are_kept_apart() {
+        let mut world = MockWorld::new();
+        world.add_room(room_name());
+        let (mut game_state, mut memory) = start(world);
+
+        let key = cache_row_path(&mut game_state, &memory);
+        next_tick(&mut game_state, &mut memory);
+
+        let goal = PathGoal::new(mock_world::pos(room_name(), 40, 25), 1);
+        let mut opts = PathfindingOpts::new();
+        opts.room_pathfinder_opts.flee = true;
+        let flee_key = PathCacheKey::new(&goal, &opts);
+
+        assert_ne!(key, flee_key);
+        assert!(find_cached_path(mock_world::pos(room_name(), 5, 25), &flee_key, &mut game_state, &memory).is_none());
+    }
+}
@@ -9,8 +9,12 @@ use crate::{constants::general::GeneralResult, memory::game_memory::GameMemory,
 use super::{portal_router_multi, portal_router_single, room_pather_multi::{self, PathGoals}, route_costs::{self, economy_creep_costs}, PathfindingOpts};
 
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-pub fn try_find_path(origin: &Position, goals: &PathGoals, opts: PathfindingOpts, game_state: &mut GameState, memory: &GameMemory) -> Result<Vec<Position>, GeneralResult> {
+pub fn try_find_path(origin: &Position, goals: &PathGoals, mut opts: PathfindingOpts, game_state: &mut GameState, memory: &GameMemory) -> Result<Vec<Position>, GeneralResult> {
     let mut allowed_rooms: HashSet<RoomName> = find_allowed_rooms(origin, goals, &opts, memory);
+    if let Some(avoid_enemy_creeps) = opts.avoid_enemy_creeps {
+        opts.room_pathfinder_opts.avoid_enemy_creeps = avoid_enemy_creeps;
+    }
+
     let path = room_pather_multi::find_path(*origin, goals, allowed_rooms, &opts.room_pathfinder_opts, game_state, memory);
 
     path
@@ -20,6 +24,11 @@ pub fn try_find_path(origin: &Position, goals: &PathGoals, opts: PathfindingOpts
 fn find_allowed_rooms(origin: &Position, goals: &PathGoals, opts: &PathfindingOpts, memory: &GameMemory) -> HashSet<RoomName> {
     let origin_room_name = origin.room_name();
     
+    // Fleeing has no destination to route to, so no rooms are ruled out
+    if opts.room_pathfinder_opts.flee {
+        return HashSet::new();
+    }
+
     let mut allowed_rooms: HashSet<RoomName> = HashSet::new();
     allowed_rooms.insert(origin_room_name);
 
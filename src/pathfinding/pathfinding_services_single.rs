@@ -9,8 +9,12 @@ use crate::{constants::general::GeneralResult, memory::game_memory::GameMemory,
 use super::{portal_router_single, room_pather_single::{self, PathGoal}, route_costs::{self, economy_creep_costs}, PathfindingOpts};
 
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-pub fn try_find_path(origin: Position, goal: &PathGoal, opts: PathfindingOpts, game_state: &mut GameState, memory: &GameMemory) -> Result<Vec<Position>, GeneralResult> {
+pub fn try_find_path(origin: Position, goal: &PathGoal, mut opts: PathfindingOpts, game_state: &mut GameState, memory: &GameMemory) -> Result<Vec<Position>, GeneralResult> {
     let mut allowed_rooms: HashSet<RoomName> = find_allowed_rooms(origin, goal, &opts, memory);
+    if let Some(avoid_enemy_creeps) = opts.avoid_enemy_creeps {
+        opts.room_pathfinder_opts.avoid_enemy_creeps = avoid_enemy_creeps;
+    }
+
     let path = room_pather_single::find_path(origin, goal, allowed_rooms, &opts.room_pathfinder_opts, game_state, memory);
 
     path
@@ -1,124 +1,212 @@
-use std::collections::{HashMap, HashSet};
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
 
-use screeps::{game, RoomName};
+use screeps::RoomName;
 
-use crate::{constants::general::GeneralResult, memory::game_memory::GameMemory};
+use crate::{constants::general::GeneralResult, memory::game_memory::GameMemory, world};
 
 use super::PathfindingOpts;
 
+/// Stop searching once this many rooms have been expanded without reaching a goal
+const MAX_EXPLORED_ROOMS: usize = 200;
+
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+/// Find the cheapest sequence of rooms from the origin to any goal, weighted by the route callback.
+/// The route excludes the origin and ends with the goal reached
 pub fn find_route(
     origin: RoomName,
     goals: HashSet<RoomName>,
     opts: &PathfindingOpts,
     memory: &GameMemory,
 ) -> Result<Vec<RoomName>, GeneralResult> {
-    let mut current_generation = HashSet::new();
-    current_generation.insert(origin);
-
-    let mut visited = HashSet::new();
-    let mut path_from = HashMap::new();
-    let mut lowest_next_gen_cost: u32 = u32::MAX;
-
-    while !current_generation.is_empty() {
-        let mut next_generation = HashSet::new();
-        let lowest_gen_cost = lowest_next_gen_cost;
-        lowest_next_gen_cost = u32::MAX;
-
-        for room_name in &current_generation {
-            let range_cost = find_range_cost(room_name, &goals, &path_from);
-            // If the tile's cost is too high for this generation's threshold, add it to the next generation
-            if range_cost > lowest_gen_cost {
-                next_generation.insert(*room_name);
-                continue;
-            }
+    if goals.is_empty() {
+        return Err(GeneralResult::Fail);
+    }
 
-            let exits = game::map::describe_exits(*room_name).values();
-            for adj_room_name in exits {
-                // No point in revisiting already explored tiles
-                if visited.contains(&adj_room_name) {
-                    continue;
-                }
-                visited.insert(adj_room_name);
+    let mut costs: HashMap<RoomName, u32> = HashMap::new();
+    costs.insert(origin, 0);
 
-                let adj_traverse_cost = (opts.route_callback)(&adj_room_name, memory);
-                // If the tile is marked as impassible, skip it
-                if adj_traverse_cost == u8::MAX {
-                    continue;
-                }
+    let mut path_from: HashMap<RoomName, RoomName> = HashMap::new();
+    let mut explored: HashSet<RoomName> = HashSet::new();
 
-                // If we reached a goal, terminate and return the found path
-                if goals.contains(&adj_room_name) {
-                    let path = find_path(&adj_room_name, &path_from);
-                    return Ok(path);
-                }
+    let mut open = BinaryHeap::new();
+    open.push(Reverse((find_lowest_cost_goal(&origin, &goals), origin.x_coord(), origin.y_coord())));
 
-                // We found a valid room to propagate the path to
+    while let Some(Reverse((_, x, y))) = open.pop() {
+        let Ok(room_name) = RoomName::from_coords(x, y) else {
+            continue;
+        };
 
-                next_generation.insert(adj_room_name);
-                path_from.insert(adj_room_name, *room_name);
+        if goals.contains(&room_name) {
+            return Ok(find_path(&room_name, &origin, &path_from));
+        }
 
-                // Find the lowest cost for the next generation's considerations
+        // Rooms can be queued more than once when a cheaper way to them is found
+        if !explored.insert(room_name) {
+            continue;
+        }
 
-                let adj_range_cost =
-                    find_range_cost(&adj_room_name, &goals, &path_from);
-                let adj_cost = adj_range_cost + adj_traverse_cost as u32;
+        if explored.len() > MAX_EXPLORED_ROOMS {
+            break;
+        }
 
-                if adj_cost < lowest_next_gen_cost {
-                    lowest_next_gen_cost = adj_cost
-                }
+        let cost = costs[&room_name];
+
+        for (_, adj_room_name) in world::exits(room_name) {
+            if explored.contains(&adj_room_name) {
+                continue;
+            }
+
+            let adj_traverse_cost = (opts.route_profile.callback())(&adj_room_name, memory);
+            // Goals are always allowed so we can route to rooms we otherwise avoid
+            if adj_traverse_cost == u8::MAX && !goals.contains(&adj_room_name) {
+                continue;
+            }
+
+            // Every room takes at least one room's worth of travel, keeping the range heuristic admissible
+            let adj_cost = cost + adj_traverse_cost.max(1) as u32;
+            if costs.get(&adj_room_name).is_some_and(|known| *known <= adj_cost) {
+                continue;
             }
-        }
 
-        current_generation = next_generation;
+            costs.insert(adj_room_name, adj_cost);
+            path_from.insert(adj_room_name, room_name);
+            open.push(Reverse((
+                adj_cost + find_lowest_cost_goal(&adj_room_name, &goals),
+                adj_room_name.x_coord(),
+                adj_room_name.y_coord(),
+            )));
+        }
     }
 
     Err(GeneralResult::Fail)
 }
 
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-/// navigate backwards accross our map of where tiles came from to construct a path
-fn find_path(room_name: &RoomName, path_from: &HashMap<RoomName, RoomName>) -> Vec<RoomName> {
-    let mut path = Vec::new();
-    path.push(*room_name);
+/// navigate backwards accross our map of where rooms came from to construct a route in travel order
+fn find_path(room_name: &RoomName, origin: &RoomName, path_from: &HashMap<RoomName, RoomName>) -> Vec<RoomName> {
+    let mut path = vec![*room_name];
 
     let mut next_room_name = path_from.get(room_name);
 
-    while next_room_name.is_some() {
-        path.push(*next_room_name.unwrap());
-        next_room_name = path_from.get(next_room_name.unwrap());
+    while let Some(room_name) = next_room_name {
+        if room_name == origin {
+            break;
+        }
+
+        path.push(*room_name);
+        next_room_name = path_from.get(room_name);
     }
 
+    path.reverse();
     path
 }
 
-#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-/// Provides a semi-accurate range cost that serves to limit the search area of the algorithm
-fn find_range_cost(
-    room_name: &RoomName,
-    goals: &HashSet<RoomName>,
-    path_from: &HashMap<RoomName, RoomName>,
-) -> u32 {
-    // A rough range
-    let goal_cost = find_lowest_cost_goal(room_name, goals);
-    // An exact path distance back to the origin
-    let origin_cost = find_path(room_name, path_from).len() as u32;
-
-    origin_cost + goal_cost
-}
-
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
 /// Find cost as the lowest linear distance to any goal
 fn find_lowest_cost_goal(room_name: &RoomName, goals: &HashSet<RoomName>) -> u32 {
-    let mut lowest_cost = f32::INFINITY as u32;
-    for goal in goals {
-        let cost = game::map::get_room_linear_distance(*room_name, *goal, false);
-        if cost >= lowest_cost {
-            continue;
+    goals
+        .iter()
+        .map(|goal| world::room_linear_distance(*room_name, *goal))
+        .min()
+        .unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use screeps::RoomName;
+
+    use crate::{
+        memory::{
+            game_memory::GameMemory,
+            room_memory::{RoomDanger, RoomMemory, StaticRoomType},
+        },
+        pathfinding::{pathfinding_services_multi, room_pather_multi::PathGoals, PathfindingOpts},
+        settings::Settings,
+        state::game::GameState,
+        world::mock_world::{self, MockWorld},
+    };
+
+    use super::find_route;
+
+    fn room(name: &str) -> RoomName {
+        RoomName::new(name).unwrap()
+    }
+
+    /// A 3x2 grid of scouted rooms, W1N1 to W3N2
+    fn grid() -> GameMemory {
+        let names = ["W1N1", "W2N1", "W3N1", "W1N2", "W2N2", "W3N2"];
+
+        let mut world = MockWorld::new();
+        for name in names {
+            world.add_room(room(name));
+        }
+        mock_world::install(world);
+
+        let mut memory = mock_world::new_memory();
+        for name in names {
+            memory.rooms.insert(
+                room(name),
+                RoomMemory {
+                    room_type: StaticRoomType::Claimable,
+                    danger: None,
+                    enemy_towers: 0,
+                    last_scout: 1,
+                },
+            );
         }
 
-        lowest_cost = cost;
+        memory
+    }
+
+    #[test]
+    fn routes_around_danger_until_it_decays() {
+        let mut memory = grid();
+        memory.rooms.get_mut(&room("W2N1")).unwrap().danger = Some(RoomDanger {
+            combat_parts: 30,
+            tick: 1,
+        });
+
+        let opts = PathfindingOpts::new();
+        let goals = HashSet::from([room("W3N1")]);
+
+        let route = find_route(room("W1N1"), goals.clone(), &opts, &memory).unwrap();
+        assert_eq!(route, vec![room("W1N2"), room("W2N2"), room("W3N2"), room("W3N1")]);
+
+        let decay_ticks = Settings::new().route_weights.danger_decay_ticks;
+        mock_world::with(|mock| mock.time = 1 + decay_ticks);
+
+        let route = find_route(room("W1N1"), goals, &opts, &memory).unwrap();
+        assert_eq!(route, vec![room("W2N1"), room("W3N1")]);
     }
 
-    lowest_cost
+    #[test]
+    fn position_paths_keep_to_the_route_around_danger() {
+        let mut memory = grid();
+        memory.rooms.get_mut(&room("W2N1")).unwrap().danger = Some(RoomDanger {
+            combat_parts: 30,
+            tick: 1,
+        });
+        let mut game_state = GameState::new();
+
+        let origin = mock_world::pos(room("W1N1"), 25, 25);
+        let goal = mock_world::pos(room("W3N1"), 25, 25);
+        let path = pathfinding_services_multi::try_find_path(
+            &origin,
+            &PathGoals::new_from_pos(goal, 0),
+            PathfindingOpts::new(),
+            &mut game_state,
+            &memory,
+        )
+        .unwrap();
+
+        // Straight through the dangerous room would be far shorter
+        assert_eq!(*path.last().unwrap(), goal);
+        assert!(path.iter().all(|pos| pos.room_name() != room("W2N1")));
+        assert!(path.iter().any(|pos| pos.room_name() == room("W2N2")));
+    }
 }
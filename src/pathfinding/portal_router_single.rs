@@ -1,115 +1,18 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::HashSet;
 
-use screeps::{RoomName, game};
+use screeps::RoomName;
 
 use crate::{constants::general::GeneralResult, memory::game_memory::GameMemory};
 
-use super::PathfindingOpts;
+use super::{portal_router_multi, PathfindingOpts};
 
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+/// Find the cheapest sequence of rooms from the origin to the goal, excluding the origin
 pub fn find_route(
     origin: RoomName,
     goal: &RoomName,
     opts: &PathfindingOpts,
     memory: &GameMemory,
 ) -> Result<Vec<RoomName>, GeneralResult> {
-    let mut current_generation = HashSet::new();
-    current_generation.insert(origin);
-
-    let mut visited = HashSet::new();
-    let mut path_from = HashMap::new();
-    let mut lowest_next_gen_cost: u32 = u32::MAX;
-
-    while !current_generation.is_empty() {
-        let mut next_generation = HashSet::new();
-        let lowest_gen_cost = lowest_next_gen_cost;
-        lowest_next_gen_cost = u32::MAX;
-
-        for room_name in &current_generation {
-            let range_cost = find_range_cost(room_name, goal, &path_from);
-            // If the tile's cost is too high for this generation's threshold, add it to the next generation
-            if range_cost > lowest_gen_cost {
-                next_generation.insert(*room_name);
-                continue;
-            }
-
-            let exits = game::map::describe_exits(*room_name).values();
-            for adj_room_name in exits {
-                // No point in revisiting already explored tiles
-                if visited.contains(&adj_room_name) {
-                    continue;
-                }
-                visited.insert(adj_room_name);
-
-                let adj_traverse_cost = (opts.route_callback)(&adj_room_name, memory);
-                // If the tile is marked as impassible, skip it
-                if adj_traverse_cost == u8::MAX {
-                    continue;
-                }
-
-                // If we reached a goal, terminate and return the found path
-                if goal == &adj_room_name {
-                    let path = find_path(&adj_room_name, &path_from);
-                    return Ok(path);
-                }
-
-                // We found a valid room to propagate the path to
-
-                next_generation.insert(adj_room_name);
-                path_from.insert(adj_room_name, *room_name);
-
-                // Find the lowest cost for the next generation's considerations
-
-                let adj_range_cost = find_range_cost(&adj_room_name, goal, &path_from);
-                let adj_cost = adj_range_cost + adj_traverse_cost as u32;
-
-                if adj_cost < lowest_next_gen_cost {
-                    lowest_next_gen_cost = adj_cost
-                }
-            }
-        }
-
-        current_generation = next_generation;
-    }
-
-    Err(GeneralResult::Fail)
-}
-
-#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-/// navigate backwards accross our map of where tiles came from to construct a path
-fn find_path(room_name: &RoomName, path_from: &HashMap<RoomName, RoomName>) -> Vec<RoomName> {
-    let mut path = Vec::new();
-    path.push(*room_name);
-
-    let mut next_room_name = path_from.get(room_name);
-
-    while next_room_name.is_some() {
-        path.push(*next_room_name.unwrap());
-        next_room_name = path_from.get(next_room_name.unwrap());
-    }
-
-    path
-}
-
-#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-/// Provides a semi-accurate range cost that serves to limit the search area of the algorithm
-fn find_range_cost(
-    room_name: &RoomName,
-    goal: &RoomName,
-    path_from: &HashMap<RoomName, RoomName>,
-) -> u32 {
-    // A rough range
-    let goal_cost = find_lowest_cost_goal(room_name, goal);
-    // An exact path distance back to the origin
-    let origin_cost = find_path(room_name, path_from).len() as u32;
-
-    origin_cost + goal_cost
-}
-
-#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-/// Find cost as the lowest linear distance to any goal
-fn find_lowest_cost_goal(room_name: &RoomName, goal: &RoomName) -> u32 {
-
-    let cost = game::map::get_room_linear_distance(*room_name, *goal, false);
-    cost
+    portal_router_multi::find_route(origin, HashSet::from([*goal]), opts, memory)
 }
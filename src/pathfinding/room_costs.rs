@@ -1,7 +1,12 @@
 use screeps::{Position, RoomName};
 use screeps_utils::sparse_cost_matrix::SparseCostMatrix;
 
-use crate::{memory::game_memory::GameMemory, room::room_ops, state::game::GameState};
+use crate::{
+    constants::move_costs::{DEFAULT_LAND_COST, MAX_COST, MY_CREEP_COST},
+    memory::game_memory::GameMemory,
+    room::room_ops,
+    state::game::GameState,
+};
 
 pub fn economy_room_costs(room_name: &RoomName, game_state: &mut GameState, memory: &GameMemory) -> SparseCostMatrix {
     // // Temporary solution for when we don't have vision
@@ -11,4 +16,48 @@ pub fn economy_room_costs(room_name: &RoomName, game_state: &mut GameState, memo
 
     let mut costs = room_ops::default_move_costs(room_name, game_state, memory);
     costs
-}
\ No newline at end of file
+}
+
+/// Economy costs that prefer tiles without our creeps and avoid everyone else's, for creeps that are stuck
+pub fn avoid_creeps_room_costs(room_name: &RoomName, game_state: &mut GameState, memory: &GameMemory) -> SparseCostMatrix {
+    let mut costs = economy_room_costs(room_name, game_state, memory);
+    add_creep_costs(room_name, &mut costs, MY_CREEP_COST, game_state, memory);
+    costs
+}
+
+/// Economy costs where every creep is impassable, for creeps that stay stuck after going around creeps
+pub fn block_creeps_room_costs(room_name: &RoomName, game_state: &mut GameState, memory: &GameMemory) -> SparseCostMatrix {
+    let mut costs = economy_room_costs(room_name, game_state, memory);
+    add_creep_costs(room_name, &mut costs, MAX_COST, game_state, memory);
+    costs
+}
+
+fn add_creep_costs(
+    room_name: &RoomName,
+    costs: &mut SparseCostMatrix,
+    my_creep_cost: u8,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+) {
+    let my_creep_positions: Vec<Position> = game_state
+        .creeps
+        .values()
+        .map(|creep| creep.inner().pos)
+        .chain(game_state.power_creeps.values().map(|power_creep| power_creep.pos))
+        .filter(|pos| pos.room_name() == *room_name)
+        .collect();
+
+    for pos in my_creep_positions {
+        let cost = costs.get(pos.xy()).max(DEFAULT_LAND_COST);
+        if cost == MAX_COST {
+            continue;
+        }
+
+        costs.set(pos.xy(), cost.saturating_add(my_creep_cost));
+    }
+
+    let not_my_creeps = room_ops::not_my_creeps(room_name, game_state, memory);
+    for creep in not_my_creeps.ally.iter().chain(not_my_creeps.enemy.iter()) {
+        costs.set(creep.pos.xy(), MAX_COST);
+    }
+}
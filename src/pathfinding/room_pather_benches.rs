@@ -0,0 +1,300 @@
+//! Compares room_search against the pather it replaced, a BinaryHeap and HashMap search that built
+//! each room's costs eagerly. Run with `cargo bench`; under `cargo test` each bench runs once.
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet},
+};
+
+use screeps::{Direction, Position, RoomName, Terrain};
+use screeps_utils::sparse_cost_matrix::SparseCostMatrix;
+use test::Bencher;
+
+use crate::{
+    constants::general::DIAGONAL_CARDINAL_DIRECTIONS,
+    memory::game_memory::GameMemory,
+    state::game::GameState,
+    world::mock_world::{self, MockWorld},
+};
+
+use super::{
+    room_costs::economy_room_costs,
+    room_pather_multi::{self, PathGoals},
+    RoomPathfinderOpts,
+};
+
+#[derive(Clone, Copy)]
+enum StandardTerrain {
+    /// Plains inside a wall border
+    Open,
+    /// Scattered walls over heavy swamp
+    Swampy,
+    /// Rows of walls with gaps at alternating ends
+    Maze,
+}
+
+fn room_name() -> RoomName {
+    RoomName::new("W1N1").unwrap()
+}
+
+fn origin() -> Position {
+    mock_world::pos(room_name(), 2, 2)
+}
+
+fn goal() -> Position {
+    mock_world::pos(room_name(), 47, 47)
+}
+
+fn install_terrain(terrain: StandardTerrain) -> (GameState, GameMemory) {
+    let mut world = MockWorld::new();
+    let room = world.add_room(room_name());
+    let mut rng = fastrand::Rng::with_seed(2500);
+
+    for y in 0..50u8 {
+        for x in 0..50u8 {
+            let border = x == 0 || y == 0 || x == 49 || y == 49;
+            let kept_clear = (x.abs_diff(2) <= 1 && y.abs_diff(2) <= 1)
+                || (x.abs_diff(47) <= 1 && y.abs_diff(47) <= 1);
+
+            let tile = match terrain {
+                _ if border => Terrain::Wall,
+                _ if kept_clear => Terrain::Plain,
+                StandardTerrain::Open => Terrain::Plain,
+                StandardTerrain::Swampy => match rng.u8(..100) {
+                    0..=9 => Terrain::Wall,
+                    10..=54 => Terrain::Swamp,
+                    _ => Terrain::Plain,
+                },
+                StandardTerrain::Maze => {
+                    let gap = match (y / 8) % 2 {
+                        0 => 47,
+                        _ => 2,
+                    };
+
+                    match y % 8 == 4 && x.abs_diff(gap) > 1 {
+                        true => Terrain::Wall,
+                        false => Terrain::Plain,
+                    }
+                }
+            };
+
+            room.set_terrain(x, y, tile);
+        }
+    }
+
+    mock_world::install(world);
+
+    (GameState::new(), mock_world::new_memory())
+}
+
+#[test]
+fn paths_are_walkable_on_standard_terrains() {
+    for terrain in [StandardTerrain::Open, StandardTerrain::Swampy, StandardTerrain::Maze] {
+        let (mut game_state, memory) = install_terrain(terrain);
+        let goals = PathGoals::new_from_pos(goal(), 1);
+        let opts = RoomPathfinderOpts::new();
+
+        let path = room_pather_multi::find_path(
+            origin(),
+            &goals,
+            HashSet::new(),
+            &opts,
+            &mut game_state,
+            &memory,
+        )
+        .unwrap();
+
+        assert_eq!(path.first(), Some(&origin()));
+        assert!(path.last().unwrap().get_range_to(goal()) <= 1);
+        for step in path.windows(2) {
+            assert_eq!(step[0].get_range_to(step[1]), 1);
+        }
+
+        let costs = economy_room_costs(&room_name(), &mut game_state, &memory);
+        assert!(path.iter().all(|pos| costs.get(pos.xy()) != u8::MAX));
+    }
+}
+
+fn bench_room_search(bencher: &mut Bencher, terrain: StandardTerrain) {
+    let (mut game_state, memory) = install_terrain(terrain);
+    let goals = PathGoals::new_from_pos(goal(), 1);
+    let opts = RoomPathfinderOpts::new();
+
+    bencher.iter(|| {
+        room_pather_multi::find_path(
+            origin(),
+            &goals,
+            HashSet::new(),
+            &opts,
+            &mut game_state,
+            &memory,
+        )
+    });
+}
+
+fn bench_legacy(bencher: &mut Bencher, terrain: StandardTerrain) {
+    let (mut game_state, memory) = install_terrain(terrain);
+    let goals = PathGoals::new_from_pos(goal(), 1);
+    let opts = RoomPathfinderOpts::new();
+
+    bencher.iter(|| legacy::find_path(origin(), &goals, &opts, &mut game_state, &memory));
+}
+
+#[bench]
+fn open_room_search(bencher: &mut Bencher) {
+    bench_room_search(bencher, StandardTerrain::Open);
+}
+
+#[bench]
+fn open_legacy(bencher: &mut Bencher) {
+    bench_legacy(bencher, StandardTerrain::Open);
+}
+
+#[bench]
+fn swampy_room_search(bencher: &mut Bencher) {
+    bench_room_search(bencher, StandardTerrain::Swampy);
+}
+
+#[bench]
+fn swampy_legacy(bencher: &mut Bencher) {
+    bench_legacy(bencher, StandardTerrain::Swampy);
+}
+
+#[bench]
+fn maze_room_search(bencher: &mut Bencher) {
+    bench_room_search(bencher, StandardTerrain::Maze);
+}
+
+#[bench]
+fn maze_legacy(bencher: &mut Bencher) {
+    bench_legacy(bencher, StandardTerrain::Maze);
+}
+
+/// The pather as it was before room_search, kept only to benchmark against
+mod legacy {
+    use super::*;
+    use crate::{constants::general::GeneralResult, utils::pos::get_positions_in_range_in_room};
+
+    #[derive(Copy, Clone, Eq, PartialEq, Debug)]
+    struct PathfinderOpenSetEntry {
+        pos: Position,
+        g_score: u32,
+        f_score: u32,
+        open_dir: Option<Direction>,
+    }
+
+    impl Ord for PathfinderOpenSetEntry {
+        fn cmp(&self, other: &Self) -> Ordering {
+            other.f_score.cmp(&self.f_score)
+        }
+    }
+
+    impl PartialOrd for PathfinderOpenSetEntry {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl PathfinderOpenSetEntry {
+        fn new(
+            pos: Position,
+            g_score: u32,
+            goals_set: &HashSet<Position>,
+            open_dir: Option<Direction>,
+        ) -> Self {
+            let heuristic_cost = goals_set
+                .iter()
+                .map(|goal| {
+                    pos.world_x().abs_diff(goal.world_x()) + pos.world_y().abs_diff(goal.world_y())
+                })
+                .min()
+                .unwrap_or(u32::MAX);
+
+            Self {
+                pos,
+                g_score,
+                f_score: g_score + heuristic_cost,
+                open_dir,
+            }
+        }
+    }
+
+    pub fn find_path(
+        origin: Position,
+        goals: &PathGoals,
+        opts: &RoomPathfinderOpts,
+        game_state: &mut GameState,
+        memory: &GameMemory,
+    ) -> Result<Vec<Position>, GeneralResult> {
+        let origin_room_name = origin.room_name();
+
+        let mut open_set = BinaryHeap::new();
+        let mut visited = HashMap::new();
+
+        let goals_exact = goals.0.keys().copied().collect::<HashSet<Position>>();
+        let mut goals_set = goals_exact.clone();
+        for goal in goals.0.keys() {
+            for position in get_positions_in_range_in_room(goal, 1) {
+                goals_set.insert(position);
+            }
+        }
+        let mut rooms_costs: HashMap<RoomName, SparseCostMatrix> = HashMap::new();
+
+        open_set.push(PathfinderOpenSetEntry::new(origin, 0, &goals_exact, None));
+        visited.insert(origin, None);
+
+        while let Some(open_set_entry) = open_set.pop() {
+            for direction in DIAGONAL_CARDINAL_DIRECTIONS {
+                if Some(-direction) == open_set_entry.open_dir {
+                    continue;
+                }
+                let Ok(pos) = open_set_entry.pos.checked_add((direction).into()) else {
+                    continue;
+                };
+
+                if visited.contains_key(&pos) {
+                    continue;
+                }
+                visited.insert(pos, Some(direction));
+
+                if goals_set.contains(&pos) {
+                    let mut path = vec![pos];
+                    let mut cursor_pos = pos;
+                    while let Some(Some(search_dir)) = visited.get(&cursor_pos) {
+                        let Ok(next_pos) = cursor_pos.checked_add((-*search_dir).into()) else {
+                            break;
+                        };
+                        path.push(next_pos);
+                        cursor_pos = next_pos;
+                    }
+                    path.reverse();
+
+                    return Ok(path);
+                }
+
+                let room_name = pos.room_name();
+                if !opts.allow_outside_origin_room && room_name != origin_room_name {
+                    continue;
+                }
+
+                let room_costs = rooms_costs
+                    .entry(room_name)
+                    .or_insert((opts.cost_profile.callback())(&room_name, game_state, memory));
+
+                let traverse_cost = room_costs.get(pos.xy());
+                if traverse_cost == u8::MAX {
+                    continue;
+                }
+
+                open_set.push(PathfinderOpenSetEntry::new(
+                    pos,
+                    open_set_entry.g_score + traverse_cost as u32,
+                    &goals_exact,
+                    Some(direction),
+                ));
+            }
+        }
+
+        Err(GeneralResult::Fail)
+    }
+}
@@ -1,16 +1,13 @@
-use std::{
-    cmp::Ordering,
-    collections::{BinaryHeap, HashMap, HashSet},
-};
+use std::collections::{HashMap, HashSet};
 
-use screeps::{Direction, Position, RoomName, RoomVisual, pathfinder::SearchGoal, visual};
-use screeps_utils::sparse_cost_matrix::{self, SparseCostMatrix};
+use screeps::{Position, RoomName};
+use screeps_utils::sparse_cost_matrix::SparseCostMatrix;
 
 use crate::{
-    constants::general::{GeneralResult, DIAGONAL_CARDINAL_DIRECTIONS, DIRECTIONS}, memory::game_memory::GameMemory, room::room_ops::{self, sparse_terrain, terrain}, state::game::GameState, utils::{general::GeneralUtils, pos::get_positions_in_range_in_room, visuals::visualize_path}
+    constants::{general::GeneralResult, move_costs::MAX_COST}, memory::game_memory::GameMemory, room::room_ops, state::game::GameState, utils::{pos::is_xy_exit, visuals::visualize_path}, world
 };
 
-use super::{room_costs::economy_room_costs, RoomPathfinderOpts};
+use super::{room_search, RoomPathfinderOpts};
 
 /// Position -> range map
 pub struct PathGoals(pub HashMap<Position, u8>);
@@ -27,176 +24,634 @@ impl PathGoals {
     }
 }
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
-struct PathfinderOpenSetEntry {
-    /// the position the entry is for
-    pos: Position,
-    /// g_score represents the cost of the best known path to get to this node
-    g_score: u32,
-    /// f_score represents the estimated total cost of a path through this node,
-    /// adding the best known cost to the node (the g_score) to the heuristic's estimate of the
-    /// cost to get from the node to the goal
-    f_score: u32,
-    /// direction this entry was opened from
-    open_dir: Option<Direction>,
-}
-
-impl Ord for PathfinderOpenSetEntry {
-    fn cmp(&self, other: &Self) -> Ordering {
-        // we're using a max-heap but the behavior we want is min-heap, usual ordering is inverted
-        other.f_score.cmp(&self.f_score)
-    }
-}
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+pub fn find_path(
+    origin: Position,
+    goals: &PathGoals,
+    allowed_rooms: HashSet<RoomName>,
+    opts: &RoomPathfinderOpts,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+) -> Result<Vec<Position>, GeneralResult> {
+    log::info!("Trying to find a path");
 
-impl PartialOrd for PathfinderOpenSetEntry {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    if opts.flee {
+        return find_flee_path(origin, goals, &allowed_rooms, opts, game_state, memory);
     }
-}
 
-impl PathfinderOpenSetEntry {
-    pub fn new(
-        pos: Position,
-        g_score: u32,
-        goals_set: &HashSet<Position>,
-        open_dir: Option<Direction>,
-    ) -> Self {
-        let heuristic_cost = get_heuristic_cost_to_closest_goal(pos, goals_set);
-
-        PathfinderOpenSetEntry {
-            pos,
-            g_score,
-            f_score: g_score + heuristic_cost,
-            open_dir,
-        }
-    }
+    let path_vec = room_search::search(
+        origin,
+        &allowed_rooms,
+        opts,
+        game_state,
+        memory,
+        |pos| {
+            goals
+                .0
+                .iter()
+                .any(|(goal, range)| pos.get_range_to(*goal) <= *range as u32)
+        },
+        |pos| get_heuristic_cost_to_closest_goal(pos, goals),
+    )?;
+
+    visualize_path(&path_vec);
+
+    Ok(path_vec)
 }
 
+/// Find the cheapest path to a tile out of range of every threat.
+/// Exits are never the destination, since creeps standing on them are pulled into the next room
 #[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-pub fn find_path(
+fn find_flee_path(
     origin: Position,
-    goals: &PathGoals,
-    allowed_rooms: HashSet<RoomName>,
+    threats: &PathGoals,
+    allowed_rooms: &HashSet<RoomName>,
     opts: &RoomPathfinderOpts,
     game_state: &mut GameState,
     memory: &GameMemory,
 ) -> Result<Vec<Position>, GeneralResult> {
-    log::info!("Trying to find a path");
-    let origin_room_name = origin.room_name();
+    // Enemy attackers we can see are threats too
 
-    let mut open_set = BinaryHeap::new();
-    let mut visited = HashMap::new();
+    let mut enemy_threat_positions: HashMap<RoomName, SparseCostMatrix> = HashMap::new();
+    if opts.avoid_enemy_attackers {
+        let mut room_names = vec![origin.room_name()];
+        if opts.allow_outside_origin_room {
+            room_names.extend(world::exits(origin.room_name()).into_iter().map(|(_, room_name)| room_name));
+        }
 
-    let goals_exact = goals.0.keys().copied().collect::<HashSet<Position>>();
-    let goals_set = {
-        let mut goals_set = goals_exact.clone();
-        for goal in goals.0.iter() {
-            for position in get_positions_in_range_in_room(goal.0, 1) {
-                goals_set.insert(position);
+        for room_name in room_names {
+            if !game_state.rooms.contains_key(&room_name) {
+                continue;
             }
+
+            game_state.get_or_create_room_state_mut(&room_name);
+            let threat_positions = room_ops::enemy_threat_positions(&room_name, game_state, memory);
+            enemy_threat_positions.insert(room_name, threat_positions);
         }
-        goals_set
+    }
+
+    let path_vec = room_search::search(
+        origin,
+        allowed_rooms,
+        opts,
+        game_state,
+        memory,
+        |pos| {
+            !is_xy_exit(pos.x().u8(), pos.y().u8())
+                && threats
+                    .0
+                    .iter()
+                    .all(|(threat, range)| pos.get_range_to(*threat) > *range as u32)
+                && !enemy_threat_positions
+                    .get(&pos.room_name())
+                    .is_some_and(|threat_positions| threat_positions.get(pos.xy()) == MAX_COST)
+        },
+        |pos| get_heuristic_cost_to_flee(pos, threats),
+    )?;
+
+    visualize_path(&path_vec);
+
+    Ok(path_vec)
+}
+
+/// Find cost as the fewest tiles to get out of range of the threat we are deepest inside
+fn get_heuristic_cost_to_flee(pos: Position, threats: &PathGoals) -> u32 {
+    threats
+        .0
+        .iter()
+        .map(|(threat, range)| (*range as u32 + 1).saturating_sub(pos.get_range_to(*threat)))
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+/// Find cost as the fewest tiles to get in range of any goal
+fn get_heuristic_cost_to_closest_goal(pos: Position, goals: &PathGoals) -> u32 {
+    goals
+        .0
+        .iter()
+        .map(|(goal, range)| room_search::tiles_to_range(pos, *goal, *range))
+        .min()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cmp::Reverse,
+        collections::{BinaryHeap, HashSet},
     };
-    let mut rooms_costs: HashMap<RoomName, SparseCostMatrix> = HashMap::new();
 
-    open_set.push(PathfinderOpenSetEntry::new(origin, 0, &goals_exact, None));
-    visited.insert(origin, None);
+    use screeps::{Position, ResourceType, RoomName, StructureType};
+
+    use super::{find_path, PathGoals};
+    use crate::{
+        constants::{
+            general::DIRECTIONS,
+            move_costs::{DEFAULT_LAND_COST, MAX_COST},
+        },
+        memory::game_memory::GameMemory,
+        pathfinding::{room_costs::economy_room_costs, RoomPathfinderOpts},
+        room::room_ops,
+        state::game::GameState,
+        utils::pos::is_xy_exit,
+        world::{
+            mock_world::{self, MockWorld},
+            simulation::world_from_map,
+        },
+    };
 
-    while let Some(open_set_entry) = open_set.pop() {
-        // Traverse diagonals before cardinals? Might not do what I think it will
-        for direction in DIAGONAL_CARDINAL_DIRECTIONS {
-            if Some(-direction) == open_set_entry.open_dir {
-                continue;
-            }
-            let Ok(pos) = open_set_entry.pos.checked_add((direction).into()) else {
-                continue;
-            };
+    fn room_name() -> RoomName {
+        RoomName::new("W1N1").unwrap()
+    }
+
+    /// A map for world_from_map with each tile chosen by its coordinates
+    fn map(tile: impl Fn(u8, u8) -> char) -> String {
+        (0..50u8)
+            .map(|y| (0..50u8).map(|x| tile(x, y)).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
 
-            if visited.contains_key(&pos) {
+    fn start(world: MockWorld) -> (GameState, GameMemory) {
+        mock_world::install(world);
+
+        (GameState::new(), mock_world::new_memory())
+    }
+
+    fn tile_cost(pos: Position, game_state: &mut GameState, memory: &GameMemory) -> u32 {
+        match economy_room_costs(&pos.room_name(), game_state, memory).get(pos.xy()) {
+            0 => DEFAULT_LAND_COST as u32,
+            cost => cost as u32,
+        }
+    }
+
+    fn path_cost(path: &[Position], game_state: &mut GameState, memory: &GameMemory) -> u32 {
+        path.iter()
+            .skip(1)
+            .map(|pos| tile_cost(*pos, game_state, memory))
+            .sum()
+    }
+
+    /// Cheapest cost to get in range of the goal within one room, by exhaustive Dijkstra
+    fn optimal_cost(
+        origin: Position,
+        goal: Position,
+        range: u8,
+        game_state: &mut GameState,
+        memory: &GameMemory,
+    ) -> Option<u32> {
+        let mut open = BinaryHeap::new();
+        let mut closed = HashSet::new();
+        open.push(Reverse((0, origin.x().u8(), origin.y().u8())));
+
+        while let Some(Reverse((cost, x, y))) = open.pop() {
+            let pos = mock_world::pos(origin.room_name(), x, y);
+            if !closed.insert(pos) {
                 continue;
             }
-            visited.insert(pos, Some(direction));
+            if pos.get_range_to(goal) <= range as u32 {
+                return Some(cost);
+            }
 
-            // Need to check if it's sufficiently in range to any of the goals
-            if goals_set.contains(&pos) {
-                let path = resolve_completed_path(pos, &visited);
-                let mut path_vec = path.into_iter().collect::<Vec<Position>>();
-                path_vec.reverse();
+            for direction in DIRECTIONS {
+                let Ok(next) = pos.checked_add(direction.into()) else {
+                    continue;
+                };
+                if next.room_name() != origin.room_name() || closed.contains(&next) {
+                    continue;
+                }
 
-                visualize_path(&path_vec);
+                let next_cost = tile_cost(next, game_state, memory);
+                if next_cost == u8::MAX as u32 {
+                    continue;
+                }
 
-                return Ok(path_vec);
+                open.push(Reverse((cost + next_cost, next.x().u8(), next.y().u8())));
             }
+        }
 
-            let room_name = pos.room_name();
-            if !opts.allow_outside_origin_room && room_name != origin_room_name {
-                continue;
-            }
+        None
+    }
+
+    fn assert_optimal(world: MockWorld, origin: (u8, u8), goal: (u8, u8), range: u8) {
+        let (mut game_state, memory) = start(world);
+        let origin = mock_world::pos(room_name(), origin.0, origin.1);
+        let goal = mock_world::pos(room_name(), goal.0, goal.1);
+
+        let path = find_path(
+            origin,
+            &PathGoals::new_from_pos(goal, range),
+            HashSet::new(),
+            &RoomPathfinderOpts::new(),
+            &mut game_state,
+            &memory,
+        )
+        .unwrap();
+
+        assert!(path.last().unwrap().get_range_to(goal) <= range as u32);
+        assert_eq!(
+            Some(path_cost(&path, &mut game_state, &memory)),
+            optimal_cost(origin, goal, range, &mut game_state, &memory)
+        );
+    }
 
-            let room_costs = rooms_costs
-                .entry(room_name)
-                .or_insert((opts.cost_callback)(&room_name, game_state, memory));
+    #[test]
+    fn diagonal_path_costs_its_chebyshev_distance() {
+        let world = world_from_map(room_name(), &map(|_, _| '.'));
+        let (mut game_state, memory) = start(world);
+
+        let origin = mock_world::pos(room_name(), 5, 5);
+        let goal = mock_world::pos(room_name(), 40, 25);
+        let path = find_path(
+            origin,
+            &PathGoals::new_from_pos(goal, 3),
+            HashSet::new(),
+            &RoomPathfinderOpts::new(),
+            &mut game_state,
+            &memory,
+        )
+        .unwrap();
+
+        // 35 tiles across less a range of 3
+        assert_eq!(path_cost(&path, &mut game_state, &memory), 32);
+    }
 
-            // let room_costs = match rooms_costs.get(&room_name) {
-            //     Some(costs) => costs,
-            //     None => &rooms_costs.insert(room_name, (opts.cost_callback)(&room_name, game_state)).unwrap(),
-            // };
+    #[test]
+    fn detours_around_swamp_when_cheaper() {
+        // A swamp band with a plain crossing far from the straight line
+        let world = world_from_map(
+            room_name(),
+            &map(|x, y| match (x, y) {
+                (15..=34, 1..=48) if y != 6 => '~',
+                _ => '.',
+            }),
+        );
+
+        assert_optimal(world, (5, 40), (44, 40), 1);
+    }
 
-            let traverse_cost = room_costs.get(pos.xy());
-            if traverse_cost == u8::MAX {
+    #[test]
+    fn optimal_through_scattered_walls_and_swamp() {
+        for seed in 0..5 {
+            let mut rng = fastrand::Rng::with_seed(seed);
+            let tiles: Vec<char> = (0..2500)
+                .map(|_| match rng.u8(..100) {
+                    0..=19 => '#',
+                    20..=49 => '~',
+                    _ => '.',
+                })
+                .collect();
+
+            let world = world_from_map(
+                room_name(),
+                &map(|x, y| match (x, y) {
+                    (3, 3) | (46, 46) => '.',
+                    _ => tiles[y as usize * 50 + x as usize],
+                }),
+            );
+
+            let (mut game_state, memory) = start(world);
+            let origin = mock_world::pos(room_name(), 3, 3);
+            let goal = mock_world::pos(room_name(), 46, 46);
+            let Some(optimal) = optimal_cost(origin, goal, 2, &mut game_state, &memory) else {
                 continue;
-            }
+            };
 
-            open_set.push(PathfinderOpenSetEntry::new(
-                pos,
-                open_set_entry.g_score + traverse_cost as u32,
-                &goals_exact,
-                Some(direction),
-            ));
+            let path = find_path(
+                origin,
+                &PathGoals::new_from_pos(goal, 2),
+                HashSet::new(),
+                &RoomPathfinderOpts::new(),
+                &mut game_state,
+                &memory,
+            )
+            .unwrap();
+
+            assert_eq!(path_cost(&path, &mut game_state, &memory), optimal);
         }
     }
 
-    Err(GeneralResult::Fail)
-}
+    #[test]
+    fn each_goal_keeps_its_own_range() {
+        let world = world_from_map(room_name(), &map(|_, _| '.'));
+        let (mut game_state, memory) = start(world);
+
+        let origin = mock_world::pos(room_name(), 25, 25);
+        let near = mock_world::pos(room_name(), 25, 15);
+        let far = mock_world::pos(room_name(), 25, 45);
+
+        let mut goals = PathGoals::new();
+        goals.0.insert(near, 0);
+        goals.0.insert(far, 18);
+
+        let path = find_path(
+            origin,
+            &goals,
+            HashSet::new(),
+            &RoomPathfinderOpts::new(),
+            &mut game_state,
+            &memory,
+        )
+        .unwrap();
+
+        // The far goal's range brings it within 2 moves, the near goal is 10 away
+        assert_eq!(path.len(), 3);
+        assert!(path.last().unwrap().get_range_to(far) <= 18);
+    }
 
-#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-/// Find cost as the lowest manhattan distance to any goal
-fn get_heuristic_cost_to_closest_goal(pos: Position, goals: &HashSet<Position>) -> u32 {
-    let mut lowest_cost = u32::MAX;
-    let pos_world_x = pos.world_x();
-    let pos_world_y = pos.world_y();
-
-    for goal in goals {
-        let cost = pos_world_x.abs_diff(goal.world_x()) + pos_world_y.abs_diff(goal.world_y());
-        if cost < lowest_cost {
-            lowest_cost = cost;
-        }
+    #[test]
+    fn ranges_reach_across_room_edges() {
+        let west = RoomName::new("W2N1").unwrap();
+        let mut world = world_from_map(room_name(), &map(|_, _| '.'));
+        world.add_room(west);
+        let (mut game_state, memory) = start(world);
+
+        let origin = mock_world::pos(room_name(), 1, 25);
+        let goal = mock_world::pos(west, 46, 25);
+
+        let path = find_path(
+            origin,
+            &PathGoals::new_from_pos(goal, 2),
+            HashSet::new(),
+            &RoomPathfinderOpts::new(),
+            &mut game_state,
+            &memory,
+        )
+        .unwrap();
+
+        let last = *path.last().unwrap();
+        assert_eq!(last.room_name(), west);
+        assert!(last.get_range_to(goal) <= 2);
     }
-    lowest_cost
-}
 
-#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
-/// navigate backwards across our map of where tiles came from to construct a path
-fn resolve_completed_path(
-    pos: Position,
-    visited: &HashMap<Position, Option<Direction>>,
-) -> Vec<Position> {
-    let mut path = Vec::new();
-    path.push(pos);
-
-    let mut cursor_pos = pos;
-
-    while let Some(optional_search_direction) = visited.get(&cursor_pos) {
-        match optional_search_direction {
-            Some(search_dir) => {
-                if let Ok(next_pos) = cursor_pos.checked_add((-*search_dir).into()) {
-                    path.push(next_pos);
-                    cursor_pos = next_pos;
-                }
+    #[test]
+    fn paths_cross_room_edges_straight_across() {
+        let west = RoomName::new("W2N1").unwrap();
+        let mut world = world_from_map(room_name(), &map(|_, _| '.'));
+        world.add_room(west);
+        let (mut game_state, memory) = start(world);
+
+        let origin = mock_world::pos(room_name(), 2, 10);
+        let goal = mock_world::pos(west, 47, 40);
+
+        let path = find_path(
+            origin,
+            &PathGoals::new_from_pos(goal, 0),
+            HashSet::new(),
+            &RoomPathfinderOpts::new(),
+            &mut game_state,
+            &memory,
+        )
+        .unwrap();
+
+        assert_eq!(*path.last().unwrap(), goal);
+        for step in path.windows(2) {
+            if step[0].room_name() != step[1].room_name() {
+                assert_eq!(step[0].y(), step[1].y());
+            } else {
+                assert!(!(is_xy_exit(step[0].x().u8(), step[0].y().u8())
+                    && is_xy_exit(step[1].x().u8(), step[1].y().u8())));
             }
-            None => break,
         }
     }
 
-    path
+    #[test]
+    fn walled_in_goals_fail_without_leaving_their_allowed_rooms() {
+        let goal = mock_world::pos(room_name(), 25, 25);
+        let world = world_from_map(
+            room_name(),
+            &map(|x, y| match goal.get_range_to(mock_world::pos(room_name(), x, y)) {
+                1 => '#',
+                _ => '.',
+            }),
+        );
+        let (mut game_state, memory) = start(world);
+        let origin = mock_world::pos(room_name(), 10, 10);
+        let goals = PathGoals::new_from_pos(goal, 0);
+
+        let allowed_rooms = HashSet::from([room_name()]);
+        assert!(find_path(
+            origin,
+            &goals,
+            allowed_rooms,
+            &RoomPathfinderOpts::new(),
+            &mut game_state,
+            &memory,
+        )
+        .is_err());
+        assert!(game_state.room_states.keys().all(|name| *name == room_name()));
+
+        // Without allowed rooms the search gives up once it has reached too many
+        assert!(find_path(
+            origin,
+            &goals,
+            HashSet::new(),
+            &RoomPathfinderOpts::new(),
+            &mut game_state,
+            &memory,
+        )
+        .is_err());
+    }
+
+    fn flee(
+        origin: Position,
+        threats: PathGoals,
+        avoid_enemy_attackers: bool,
+        game_state: &mut GameState,
+        memory: &GameMemory,
+    ) -> Vec<Position> {
+        let mut opts = RoomPathfinderOpts::new();
+        opts.flee = true;
+        opts.avoid_enemy_attackers = avoid_enemy_attackers;
+
+        find_path(origin, &threats, HashSet::new(), &opts, game_state, memory).unwrap()
+    }
+
+    #[test]
+    fn flees_out_of_range_by_the_cheapest_path() {
+        // Walls on the far side of the threat leave only swamp to flee across
+        let world = world_from_map(
+            room_name(),
+            &map(|x, y| match (x, y) {
+                (_, 22) => '#',
+                (_, 28..=29) => '~',
+                _ => '.',
+            }),
+        );
+        let (mut game_state, memory) = start(world);
+
+        let threat = mock_world::pos(room_name(), 25, 25);
+        let origin = mock_world::pos(room_name(), 25, 26);
+        let path = flee(
+            origin,
+            PathGoals::new_from_pos(threat, 3),
+            false,
+            &mut game_state,
+            &memory,
+        );
+
+        let last = *path.last().unwrap();
+        assert!(last.get_range_to(threat) > 3);
+        // Four plains sideways are cheaper than south through two swamp tiles
+        assert_eq!(path_cost(&path, &mut game_state, &memory), 4);
+        assert!(path
+            .iter()
+            .all(|pos| tile_cost(*pos, &mut game_state, &memory) != u8::MAX as u32));
+    }
+
+    #[test]
+    fn flee_never_ends_on_an_exit() {
+        let world = world_from_map(room_name(), &map(|_, _| '.'));
+        let (mut game_state, memory) = start(world);
+
+        let threat = mock_world::pos(room_name(), 3, 25);
+        let origin = mock_world::pos(room_name(), 1, 25);
+        let path = flee(
+            origin,
+            PathGoals::new_from_pos(threat, 3),
+            false,
+            &mut game_state,
+            &memory,
+        );
+
+        let last = *path.last().unwrap();
+        assert_eq!(last.room_name(), room_name());
+        assert!(last.x().u8() > 0 && last.x().u8() < 49);
+        assert!(last.get_range_to(threat) > 3);
+    }
+
+    #[test]
+    fn flee_avoids_enemy_attackers_when_asked() {
+        let mut world = world_from_map(room_name(), &map(|_, _| '.'));
+        let mut enemy = mock_world::new_creep(
+            "invader",
+            None,
+            "Invader",
+            mock_world::pos(room_name(), 25, 31),
+            &[screeps::Part::RangedAttack, screeps::Part::Move],
+        );
+        enemy.my = false;
+        world.room_mut(room_name()).hostile_creeps.push(enemy);
+        let (mut game_state, mut memory) = start(world);
+        game_state.tick_update(&mut memory);
+
+        let threat = mock_world::pos(room_name(), 25, 25);
+        let origin = mock_world::pos(room_name(), 25, 27);
+        let path = flee(
+            origin,
+            PathGoals::new_from_pos(threat, 3),
+            true,
+            &mut game_state,
+            &memory,
+        );
+
+        let last = *path.last().unwrap();
+        assert!(last.get_range_to(threat) > 3);
+        assert!(last.get_range_to(mock_world::pos(room_name(), 25, 31)) > 3);
+    }
+
+    fn add_enemy(world: &mut MockWorld, x: u8, y: u8, body: &[screeps::Part]) {
+        let mut enemy = mock_world::new_creep(
+            &format!("enemy{}", world.room_mut(room_name()).hostile_creeps.len()),
+            None,
+            "Enemy",
+            mock_world::pos(room_name(), x, y),
+            body,
+        );
+        enemy.my = false;
+        world.room_mut(room_name()).hostile_creeps.push(enemy);
+    }
+
+    #[test]
+    fn threat_ranges_follow_body_parts() {
+        let mut world = world_from_map(room_name(), &map(|_, _| '.'));
+        add_enemy(&mut world, 10, 10, &[screeps::Part::Attack, screeps::Part::Move]);
+        add_enemy(&mut world, 30, 30, &[screeps::Part::RangedAttack, screeps::Part::Move]);
+        add_enemy(&mut world, 40, 10, &[screeps::Part::Work, screeps::Part::Move]);
+        let (mut game_state, mut memory) = start(world);
+        game_state.tick_update(&mut memory);
+        game_state.get_or_create_room_state_mut(&room_name());
+
+        let threats = room_ops::enemy_threat_positions(&room_name(), &mut game_state, &memory);
+        let threat = |x, y| threats.get(mock_world::pos(room_name(), x, y).xy());
+
+        assert_eq!(threat(11, 11), MAX_COST);
+        assert_eq!(threat(10, 12), 0);
+        assert_eq!(threat(33, 27), MAX_COST);
+        assert_eq!(threat(30, 34), 0);
+        assert_eq!(threat(40, 10), 0);
+        assert_eq!(threat(41, 11), 0);
+    }
+
+    #[test]
+    fn enemy_tower_damage_falls_off_with_range() {
+        let mut world = world_from_map(room_name(), &map(|_, _| '.'));
+        let tower_id = world.add_structure(
+            StructureType::Tower,
+            mock_world::pos(room_name(), 25, 25),
+        );
+        let tower = world.structure_mut(tower_id).unwrap();
+        tower.my = Some(false);
+        tower.owner = Some("Enemy".to_string());
+        tower
+            .store
+            .as_mut()
+            .unwrap()
+            .resources
+            .insert(ResourceType::Energy, 500);
+        let (mut game_state, mut memory) = start(world);
+        game_state.tick_update(&mut memory);
+        game_state.get_or_create_room_state_mut(&room_name());
+
+        let threats = room_ops::enemy_threat_positions(&room_name(), &mut game_state, &memory);
+        let threat = |x, y| threats.get(mock_world::pos(room_name(), x, y).xy());
+
+        // Full damage within 5 tiles, quarter damage from 20
+        assert_eq!(threat(25, 30), 20);
+        assert_eq!(threat(25, 45), 5);
+        assert!(threat(25, 37) < threat(25, 33));
+        assert!(threat(25, 37) > threat(25, 41));
+    }
+
+    #[test]
+    fn paths_go_around_enemy_attackers() {
+        // A wall with a near gap the attacker guards and a far one it doesn't
+        let mut world = world_from_map(
+            room_name(),
+            &map(|x, y| match (x, y) {
+                (25, 25) | (45, 25) => '.',
+                (_, 25) => '#',
+                _ => '.',
+            }),
+        );
+        add_enemy(&mut world, 25, 27, &[screeps::Part::Attack, screeps::Part::Move]);
+        let (mut game_state, mut memory) = start(world);
+        game_state.tick_update(&mut memory);
+
+        let origin = mock_world::pos(room_name(), 25, 10);
+        let goals = PathGoals::new_from_pos(mock_world::pos(room_name(), 25, 40), 1);
+        let enemy = mock_world::pos(room_name(), 25, 27);
+
+        let mut opts = RoomPathfinderOpts::new();
+        opts.avoid_enemy_attackers = true;
+        opts.avoid_enemy_creeps = true;
+        let path = find_path(origin, &goals, HashSet::new(), &opts, &mut game_state, &memory).unwrap();
+
+        assert!(path.contains(&mock_world::pos(room_name(), 45, 25)));
+        assert!(path.iter().all(|pos| pos.get_range_to(enemy) > 1));
+
+        // Without the threat map the near gap is cheapest
+        let path = find_path(
+            origin,
+            &goals,
+            HashSet::new(),
+            &RoomPathfinderOpts::new(),
+            &mut game_state,
+            &memory,
+        )
+        .unwrap();
+
+        assert!(path.contains(&mock_world::pos(room_name(), 25, 25)));
+    }
 }
@@ -0,0 +1,374 @@
+use std::collections::{HashMap, HashSet};
+
+use screeps::{Position, RoomCoordinate, RoomName, RoomXY};
+
+use crate::{
+    constants::{
+        general::{GeneralResult, DIRECTIONS},
+        move_costs::{DEFAULT_LAND_COST, MAX_COST},
+    },
+    memory::game_memory::GameMemory,
+    room::room_ops,
+    state::game::GameState,
+    utils::pos::{exit_destination, is_xy_exit},
+};
+
+use super::RoomPathfinderOpts;
+
+const ROOM_AREA: usize = 2500;
+/// A search that reaches more rooms than this has wandered off looking for a goal it can't reach
+const MAX_SEARCH_ROOMS: usize = 16;
+/// Parent of tiles no search has entered, and of the origin
+const NO_PARENT: u8 = 0;
+
+/// Search state for one room, allocated the first time the search reaches it
+struct SearchRoom {
+    room_name: RoomName,
+    /// Cost to enter each tile. Tiles the cost callback left unset cost as much as plains
+    costs: Box<[u8; ROOM_AREA]>,
+    /// Cost of the best known path to each tile
+    g_scores: Box<[u32; ROOM_AREA]>,
+    /// Index into DIRECTIONS plus one of the move that entered each tile
+    parents: Box<[u8; ROOM_AREA]>,
+}
+
+/// Every room the search has reached. Nodes are room index * ROOM_AREA + y * 50 + x
+struct SearchRooms {
+    rooms: Vec<SearchRoom>,
+    indexes: HashMap<RoomName, usize>,
+}
+
+impl SearchRooms {
+    fn new() -> Self {
+        Self {
+            rooms: Vec::new(),
+            indexes: HashMap::new(),
+        }
+    }
+
+    /// The node for a position, asking for the room's costs only the first time the room is reached
+    fn node(
+        &mut self,
+        pos: Position,
+        opts: &RoomPathfinderOpts,
+        game_state: &mut GameState,
+        memory: &GameMemory,
+    ) -> usize {
+        let room_name = pos.room_name();
+        let tile = pos.y().u8() as usize * 50 + pos.x().u8() as usize;
+
+        if let Some(index) = self.indexes.get(&room_name) {
+            return index * ROOM_AREA + tile;
+        }
+
+        let sparse_costs = (opts.cost_profile.callback())(&room_name, game_state, memory);
+        let mut costs = Box::new([DEFAULT_LAND_COST; ROOM_AREA]);
+        for (tile, cost) in costs.iter_mut().enumerate() {
+            let xy = RoomXY::checked_new((tile % 50) as u8, (tile / 50) as u8).unwrap();
+
+            let sparse_cost = sparse_costs.get(xy);
+            if sparse_cost != 0 {
+                *cost = sparse_cost;
+            }
+        }
+        add_enemy_costs(&room_name, &mut costs, opts, game_state, memory);
+
+        let index = self.rooms.len();
+        self.rooms.push(SearchRoom {
+            room_name,
+            costs,
+            g_scores: Box::new([u32::MAX; ROOM_AREA]),
+            parents: Box::new([NO_PARENT; ROOM_AREA]),
+        });
+        self.indexes.insert(room_name, index);
+
+        index * ROOM_AREA + tile
+    }
+
+    /// Whether the search would have to reach a new room, and is already at its limit, to enter the position
+    fn is_room_limited(&self, pos: Position) -> bool {
+        self.rooms.len() >= MAX_SEARCH_ROOMS && !self.indexes.contains_key(&pos.room_name())
+    }
+
+    fn room(&self, node: usize) -> (&SearchRoom, usize) {
+        (&self.rooms[node / ROOM_AREA], node % ROOM_AREA)
+    }
+
+    fn cost(&self, node: usize) -> u8 {
+        let (room, tile) = self.room(node);
+        room.costs[tile]
+    }
+
+    fn g_score(&self, node: usize) -> u32 {
+        let (room, tile) = self.room(node);
+        room.g_scores[tile]
+    }
+
+    fn parent(&self, node: usize) -> u8 {
+        let (room, tile) = self.room(node);
+        room.parents[tile]
+    }
+
+    fn set(&mut self, node: usize, g_score: u32, parent: u8) {
+        let room = &mut self.rooms[node / ROOM_AREA];
+        let tile = node % ROOM_AREA;
+
+        room.g_scores[tile] = g_score;
+        room.parents[tile] = parent;
+    }
+
+    fn pos(&self, node: usize) -> Position {
+        let (room, tile) = self.room(node);
+
+        Position::new(
+            RoomCoordinate::new((tile % 50) as u8).unwrap(),
+            RoomCoordinate::new((tile / 50) as u8).unwrap(),
+            room.room_name,
+        )
+    }
+
+    /// Walk back along parents from the node to the origin. The path starts with the origin
+    fn resolve_path(&self, node: usize) -> Vec<Position> {
+        let mut path = vec![self.pos(node)];
+        let mut cursor = node;
+
+        loop {
+            let parent = self.parent(cursor);
+            if parent == NO_PARENT {
+                break;
+            }
+
+            let direction = DIRECTIONS[parent as usize - 1];
+            let Ok(previous) = path.last().unwrap().checked_add((-direction).into()) else {
+                break;
+            };
+
+            cursor = self.indexes[&previous.room_name()] * ROOM_AREA
+                + previous.y().u8() as usize * 50
+                + previous.x().u8() as usize;
+            path.push(previous);
+        }
+
+        path.reverse();
+        path
+    }
+}
+
+/// Compose what enemies threaten into a room's costs, for the rooms we can see.
+/// Fleeing creeps may need to cross attacked tiles to get out, so fleeing only adds tower costs
+fn add_enemy_costs(
+    room_name: &RoomName,
+    costs: &mut [u8; ROOM_AREA],
+    opts: &RoomPathfinderOpts,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+) {
+    if !opts.avoid_enemy_attackers && !opts.avoid_enemy_creeps {
+        return;
+    }
+    if !game_state.rooms.contains_key(room_name) {
+        return;
+    }
+
+    game_state.get_or_create_room_state_mut(room_name);
+
+    if opts.avoid_enemy_attackers {
+        let threat_positions = room_ops::enemy_threat_positions(room_name, game_state, memory);
+
+        for (tile, cost) in costs.iter_mut().enumerate() {
+            let xy = RoomXY::checked_new((tile % 50) as u8, (tile / 50) as u8).unwrap();
+
+            match threat_positions.get(xy) {
+                0 => (),
+                MAX_COST if opts.flee => (),
+                MAX_COST => *cost = MAX_COST,
+                threat_cost if *cost != MAX_COST => {
+                    *cost = cost.saturating_add(threat_cost).min(MAX_COST - 1)
+                }
+                _ => (),
+            }
+        }
+    }
+
+    if opts.avoid_enemy_creeps {
+        for creep in room_ops::not_my_creeps(room_name, game_state, memory).enemy {
+            costs[creep.pos.y().u8() as usize * 50 + creep.pos.x().u8() as usize] = MAX_COST;
+        }
+    }
+}
+
+/// Fewest moves from a position to get within range of a goal. Moves are 8-directional
+/// and world coordinates run continuously across room edges, so this is the Chebyshev distance less the range
+pub fn tiles_to_range(pos: Position, goal: Position, range: u8) -> u32 {
+    pos.get_range_to(goal).saturating_sub(range as u32)
+}
+
+/// Min priority queue for small integer priorities: one bucket of entries per priority.
+/// Priorities of A* are whole move costs, so popping is a scan to the next non-empty bucket
+/// instead of a heap operation
+pub struct BucketQueue<T> {
+    buckets: Vec<Vec<T>>,
+    /// No bucket below this holds entries
+    lowest: usize,
+    len: usize,
+}
+
+impl<T> BucketQueue<T> {
+    pub fn new() -> Self {
+        Self {
+            buckets: Vec::new(),
+            lowest: 0,
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, priority: u32, entry: T) {
+        let priority = priority as usize;
+        if priority >= self.buckets.len() {
+            self.buckets.resize_with(priority + 1, Vec::new);
+        }
+
+        self.buckets[priority].push(entry);
+        self.lowest = self.lowest.min(priority);
+        self.len += 1;
+    }
+
+    /// Entries of equal priority come out last in, first out
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        while self.buckets[self.lowest].is_empty() {
+            self.lowest += 1;
+        }
+
+        self.len -= 1;
+        self.buckets[self.lowest].pop()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// A* from the origin to the first position that satisfies is_goal, only through allowed rooms unless none are given.
+/// The heuristic is the fewest tiles from a position to any goal, and is scaled by the cheapest tile cost
+/// so it never overestimates. The path includes the origin.
+/// Fails once the search would reach more than MAX_SEARCH_ROOMS rooms
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+pub fn search(
+    origin: Position,
+    allowed_rooms: &HashSet<RoomName>,
+    opts: &RoomPathfinderOpts,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+    is_goal: impl Fn(Position) -> bool,
+    heuristic: impl Fn(Position) -> u32,
+) -> Result<Vec<Position>, GeneralResult> {
+    let origin_room_name = origin.room_name();
+
+    let mut rooms = SearchRooms::new();
+    let mut open_set: BucketQueue<(usize, u32)> = BucketQueue::new();
+
+    let origin_node = rooms.node(origin, opts, game_state, memory);
+    rooms.set(origin_node, 0, NO_PARENT);
+    let min_traverse_cost = opts.min_traverse_cost.max(1) as u32;
+
+    open_set.push(heuristic(origin) * min_traverse_cost, (origin_node, 0));
+
+    while let Some((node, g_score)) = open_set.pop() {
+        // A cheaper path to the tile was found after this entry was queued
+        if g_score > rooms.g_score(node) {
+            continue;
+        }
+
+        let pos = rooms.pos(node);
+        if is_goal(pos) {
+            return Ok(rooms.resolve_path(node));
+        }
+
+        for (index, direction) in DIRECTIONS.iter().enumerate() {
+            let Ok(next_pos) = pos.checked_add((*direction).into()) else {
+                continue;
+            };
+
+            if !opts.allow_outside_origin_room && next_pos.room_name() != origin_room_name {
+                continue;
+            }
+
+            if !is_edge_move_allowed(pos, next_pos) {
+                continue;
+            }
+
+            if !allowed_rooms.is_empty() && !allowed_rooms.contains(&next_pos.room_name()) {
+                continue;
+            }
+
+            if rooms.is_room_limited(next_pos) {
+                return Err(GeneralResult::Fail);
+            }
+
+            let next_node = rooms.node(next_pos, opts, game_state, memory);
+            let traverse_cost = rooms.cost(next_node);
+            if traverse_cost == u8::MAX {
+                continue;
+            }
+
+            let next_g_score = g_score + traverse_cost as u32;
+            if next_g_score >= rooms.g_score(next_node) {
+                continue;
+            }
+
+            rooms.set(next_node, next_g_score, index as u8 + 1);
+            open_set.push(
+                next_g_score + heuristic(next_pos) * min_traverse_cost,
+                (next_node, next_g_score),
+            );
+        }
+    }
+
+    Err(GeneralResult::Fail)
+}
+
+/// Creeps cross borders straight across, and moving along an edge would put them back across it
+fn is_edge_move_allowed(pos: Position, next_pos: Position) -> bool {
+    if !is_xy_exit(pos.x().u8(), pos.y().u8()) {
+        return true;
+    }
+
+    if next_pos.room_name() != pos.room_name() {
+        return exit_destination(&pos) == Some(next_pos);
+    }
+
+    !is_xy_exit(next_pos.x().u8(), next_pos.y().u8())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BucketQueue;
+
+    #[test]
+    fn bucket_queue_pops_lowest_first() {
+        let mut queue = BucketQueue::new();
+        queue.push(5, 'a');
+        queue.push(2, 'b');
+        queue.push(9, 'c');
+        queue.push(2, 'd');
+
+        assert_eq!(queue.pop(), Some('d'));
+        assert_eq!(queue.pop(), Some('b'));
+
+        // Pushing below the lowest bucket seen so far still comes out first
+        queue.push(1, 'e');
+        assert_eq!(queue.pop(), Some('e'));
+        assert_eq!(queue.pop(), Some('a'));
+        assert_eq!(queue.pop(), Some('c'));
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+    }
+}
@@ -1,72 +1,147 @@
-use std::collections::{HashMap, HashSet};
-
 use screeps::RoomName;
 
-use crate::{memory::{game_memory::GameMemory, room_memory::StaticRoomType}, settings::Settings};
-
-/* pub fn weight_room_types<'a>(
-    room_type_weights: HashMap<RoomType, u8>,
-    memory: &'a GameMemory,
-) -> impl for<'a> Fn(&'a RoomName) -> u8 {
-    let f: (dyn for<'a> Fn(&'a RoomName) -> u8 + 'static) = |room_name: &RoomName| -> u8 {
-        let Some(room_memory) = memory.rooms.get(room_name) else {
-            return u8::MAX;
-        };
+use crate::{
+    memory::{game_memory::GameMemory, room_memory::StaticRoomType},
+    settings::RouteWeights,
+    world, SETTINGS,
+};
 
-        let Some(weight) = room_type_weights.get(&room_memory.room_type) else {
-            return 0;
-        };
+/// The highest cost a passable room can have
+const MAX_PASSABLE_COST: u8 = u8::MAX - 1;
 
-        *weight
-    };
+pub fn economy_creep_costs(room_name: &RoomName, memory: &GameMemory) -> u8 {
+    let tick = world::time();
 
-    f
-} */
+    SETTINGS.with_borrow(|settings| room_route_cost(room_name, &settings.route_weights, tick, memory))
+}
 
-pub fn economy_creep_costs(room_name: &RoomName, memory: &GameMemory) -> u8 {
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+/// The cost of travelling through a room based on what we last scouted of it. u8::MAX means impassable
+pub fn room_route_cost(room_name: &RoomName, weights: &RouteWeights, tick: u32, memory: &GameMemory) -> u8 {
     let Some(room_memory) = memory.rooms.get(room_name) else {
-        return u8::MAX;
+        return weights.unscouted;
     };
 
-    // Room owner
+    // Rooms owned by anyone but us and our allies
     if let Some(claimable_memory) = memory.claimable_rooms.get(room_name) {
-        // If we own the room, return 0 cost
-        if claimable_memory.my_claim.is_some() {
-            return 0;
-        }
-
-        // If someone else owns the room 
         if let Some(non_me_owner) = &claimable_memory.non_me_owner {
-            // If it's an ally we're happy to pass through
-            if memory.allies.contains_key(non_me_owner) {
-                return 0;
-            }
-            // Otherwise the room is owner by an enemy
-            else {
-                return u8::MAX;
-
+            if !memory.allies.contains_key(non_me_owner) {
+                return weights.hostile_owner;
             }
         }
     }
 
-    // If there is danger, avoid the room
+    let mut cost = match room_memory.room_type {
+        StaticRoomType::Claimable => weights.claimable,
+        StaticRoomType::CardinalHighway | StaticRoomType::Intersection => weights.highway,
+        StaticRoomType::Center => weights.center,
+        StaticRoomType::Keeper => weights.keeper,
+    };
+
+    if memory
+        .portal_rooms
+        .get(room_name)
+        .is_some_and(|portal_memory| !portal_memory.portals.is_empty())
+    {
+        cost = cost.saturating_add(weights.portal);
+    }
+
+    cost = cost.saturating_add(weights.enemy_tower.saturating_mul(room_memory.enemy_towers));
+
     if let Some(danger) = room_memory.danger {
-        if danger > 0 {
-            return u8::MAX;
-        }
+        cost = cost.saturating_add(decayed_danger(danger.combat_parts, tick.saturating_sub(danger.tick), weights));
     }
 
-    let room_type_weights = HashMap::from([
-        (StaticRoomType::Claimable, 1),
-        (StaticRoomType::Center, 1),
-        (StaticRoomType::CardinalHighway, 1),
-        (StaticRoomType::Intersection, 1),
-        (StaticRoomType::Keeper, 1),
-    ]);
+    cost.min(MAX_PASSABLE_COST)
+}
 
-    let Some(weight) = room_type_weights.get(&room_memory.room_type) else {
+/// Danger capped to the danger weight, shrinking linearly to 0 as it ages
+fn decayed_danger(combat_parts: u32, age: u32, weights: &RouteWeights) -> u8 {
+    if age >= weights.danger_decay_ticks {
         return 0;
+    }
+
+    let danger = combat_parts.min(weights.danger as u32);
+    let remaining = weights.danger_decay_ticks - age;
+
+    (danger * remaining).div_ceil(weights.danger_decay_ticks) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use screeps::RoomName;
+
+    use crate::{
+        memory::{
+            ally::AllyMemory,
+            room_memory::{RoomDanger, RoomMemory, StaticRoomType},
+            static_room_memory::ClaimableRoomMemory,
+        },
+        settings::Settings,
+        world::mock_world::{self, pos, MockWorld},
     };
 
-    *weight
+    use super::room_route_cost;
+
+    fn room_name() -> RoomName {
+        RoomName::new("W1N1").unwrap()
+    }
+
+    fn room_memory(room_type: StaticRoomType, danger: Option<RoomDanger>) -> RoomMemory {
+        RoomMemory {
+            room_type,
+            danger,
+            enemy_towers: 0,
+            last_scout: 1,
+        }
+    }
+
+    #[test]
+    fn danger_decays_over_time() {
+        mock_world::install(MockWorld::new());
+
+        let weights = Settings::new().route_weights;
+        let mut memory = mock_world::new_memory();
+        memory.rooms.insert(
+            room_name(),
+            room_memory(
+                StaticRoomType::Claimable,
+                Some(RoomDanger {
+                    combat_parts: 20,
+                    tick: 1000,
+                }),
+            ),
+        );
+
+        let fresh = room_route_cost(&room_name(), &weights, 1000, &memory);
+        let aged = room_route_cost(&room_name(), &weights, 1000 + weights.danger_decay_ticks / 2, &memory);
+        let decayed = room_route_cost(&room_name(), &weights, 1000 + weights.danger_decay_ticks, &memory);
+
+        assert_eq!(fresh, weights.claimable + 20);
+        assert_eq!(aged, weights.claimable + 10);
+        assert_eq!(decayed, weights.claimable);
+    }
+
+    #[test]
+    fn hostile_owners_are_impassable_and_allies_are_not() {
+        mock_world::install(MockWorld::new());
+
+        let weights = Settings::new().route_weights;
+        let mut memory = mock_world::new_memory();
+        memory.rooms.insert(room_name(), room_memory(StaticRoomType::Claimable, None));
+        memory.claimable_rooms.insert(
+            room_name(),
+            ClaimableRoomMemory {
+                non_me_owner: Some("Enemy".to_string()),
+                my_claim: None,
+                controller_pos: pos(room_name(), 25, 25),
+            },
+        );
+
+        assert_eq!(room_route_cost(&room_name(), &weights, 1, &memory), u8::MAX);
+
+        memory.allies.insert("Enemy".to_string(), AllyMemory::new());
+
+        assert_eq!(room_route_cost(&room_name(), &weights, 1, &memory), weights.claimable);
+    }
 }
@@ -18,7 +18,7 @@ pub fn spawns_by_activity<'state>(
     let structures = room_ops::structures_by_type(room_name, game_state);
 
     for spawn in &structures.spawn {
-        match spawn.spawning() {
+        match &spawn.spawning {
             Some(spawning) => spawns_by_activity.active.push(spawn.clone()),
             _ => spawns_by_activity.inactive.push(spawn.clone()),
         }
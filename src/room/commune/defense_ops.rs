@@ -1,9 +1,9 @@
 use std::collections::HashSet;
 
-use screeps::{HasPosition, Position, RoomName};
+use screeps::{Position, RoomName};
 use screeps_utils::sparse_cost_matrix::SparseCostMatrix;
 
-use crate::{creep::any_creep_ops, memory::{enemy, game_memory::GameMemory}, room::room_ops, state::game::GameState};
+use crate::{creep::any_creep_ops, memory::{enemy, game_memory::GameMemory}, room::room_ops, state::game::GameState, world};
 
 pub fn try_active_safe_mode(room_name: &RoomName, game_state: &mut GameState, memory: &mut GameMemory) {
 
@@ -20,7 +20,7 @@ pub fn try_active_safe_mode(room_name: &RoomName, game_state: &mut GameState, me
         return
     };
 
-    controller.activate_safe_mode();
+    let _ = world::activate_safe_mode(controller.id);
 }
 
 fn can_safe_mode(room_name: &RoomName, game_state: &mut GameState, memory: &mut GameMemory) -> bool {
@@ -29,7 +29,7 @@ fn can_safe_mode(room_name: &RoomName, game_state: &mut GameState, memory: &mut
         return false
     };
        
-    let Some(safe_mode_cooldown) = controller.safe_mode_cooldown() else {
+    let Some(safe_mode_cooldown) = controller.safe_mode_cooldown else {
         // Consider recording the tick in room_state for when the safemode cooldown will be off
         return false
     };
@@ -45,7 +45,7 @@ fn should_safe_mode_for_base(room_name: &RoomName, game_state: &mut GameState, m
     for enemy_creep in enemy_creeps {
         // Check if the creep has combat capabilities
 
-        if !base_positions.contains(&enemy_creep.pos()) {
+        if !base_positions.contains(&enemy_creep.pos) {
             continue;
         };
 
@@ -1,3 +1,5 @@
+pub mod spawn_ops;
 pub mod spawn_request_arg_ops;
 pub mod spawn_request_arg_services;
-pub mod spawn_services;
\ No newline at end of file
+pub mod spawn_services;
+pub mod spawn_time_ops;
\ No newline at end of file
@@ -0,0 +1,207 @@
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{Hash, Hasher},
+};
+
+use screeps::{Direction, ObjectId, Position, RoomName, Structure};
+use screeps_utils::sparse_cost_matrix::SparseCostMatrix;
+
+use crate::{
+    constants::general::DIRECTIONS,
+    room::room_ops,
+    state::game::GameState,
+    utils,
+    world::objects::StructureData,
+};
+
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+/// Spawns and extensions ordered nearest to the fast filler first, so spawning drains what the fast filler refills.
+/// The order is cached until spawns or extensions are added or removed, or the fast filler is planned
+pub fn energy_structures(room_name: &RoomName, game_state: &mut GameState) -> Vec<ObjectId<Structure>> {
+    let anchors = fast_filler_anchors(room_name, game_state);
+    let structures = room_ops::structures_by_type(room_name, game_state);
+
+    let mut hasher = DefaultHasher::new();
+    anchors.hash(&mut hasher);
+    for structure in structures.spawn.iter().chain(structures.extension.iter()) {
+        structure.id.hash(&mut hasher);
+    }
+    let signature = hasher.finish();
+
+    let commune_state = game_state.commune_states.get(room_name).unwrap();
+    if commune_state.energy_structures_signature == Some(signature) {
+        return commune_state.energy_structures.clone();
+    }
+
+    if anchors.is_empty() {
+        return Vec::new();
+    }
+
+    let structures = room_ops::structures_by_type(room_name, game_state);
+    let mut ordered: Vec<_> = structures
+        .spawn
+        .iter()
+        .chain(structures.extension.iter())
+        .map(|structure| {
+            let range = anchors
+                .iter()
+                .map(|anchor| utils::general::pos_range(anchor, &structure.pos))
+                .min()
+                .unwrap();
+            ((range, structure.pos.x().u8(), structure.pos.y().u8()), structure.id)
+        })
+        .collect();
+    ordered.sort_by_key(|(key, _)| *key);
+
+    let energy_structures: Vec<ObjectId<Structure>> = ordered.into_iter().map(|(_, id)| id).collect();
+
+    let commune_state = game_state.commune_states.get_mut(room_name).unwrap();
+    commune_state.energy_structures = energy_structures.clone();
+    commune_state.energy_structures_signature = Some(signature);
+
+    energy_structures
+}
+
+/// Where the base plan starts the fast filler, or the first spawn until the room has a plan
+fn fast_filler_anchors(room_name: &RoomName, game_state: &mut GameState) -> Vec<Position> {
+    let planned = game_state
+        .room_states
+        .get(room_name)
+        .and_then(|room_state| room_state.commune_plan.as_ref())
+        .and_then(|plan| plan.fast_filler_start_positions.clone())
+        .filter(|start_positions| !start_positions.is_empty());
+    if let Some(start_positions) = planned {
+        return start_positions;
+    }
+
+    let structures = room_ops::structures_by_type(room_name, game_state);
+    structures.spawn.first().map(|spawn| spawn.pos).into_iter().collect()
+}
+
+/// The index of the spawn closest to the spawn target, or the first spawn without one
+pub fn find_spawn_index(spawn_target: Option<Position>, spawns: &[&StructureData]) -> usize {
+    let Some(spawn_target) = spawn_target else {
+        return 0;
+    };
+
+    let (_, index) = utils::general::find_index_with_lowest_score(spawns, &|spawn| {
+        utils::general::pos_range(&spawn.pos, &spawn_target)
+    });
+
+    index as usize
+}
+
+/// Directions a creep may leave the spawn in, nearest to its spawn target first,
+/// only onto tiles the move costs let it walk and never onto reserved positions.
+/// None when no such tile is left, so the game picks a direction itself rather than the creep being stuck
+pub fn spawn_directions(
+    spawn_pos: &Position,
+    spawn_target: Option<Position>,
+    move_costs: &SparseCostMatrix,
+    reserved_positions: &HashSet<Position>,
+) -> Option<Vec<Direction>> {
+    let mut directions: Vec<(Direction, Position)> = DIRECTIONS
+        .iter()
+        .filter_map(|direction| {
+            let pos = spawn_pos.checked_add_direction(*direction).ok()?;
+            (move_costs.get(pos.xy()) != u8::MAX && !reserved_positions.contains(&pos)).then_some((*direction, pos))
+        })
+        .collect();
+
+    if directions.is_empty() {
+        return None;
+    }
+
+    if let Some(spawn_target) = spawn_target {
+        directions.sort_by_key(|(_, pos)| utils::general::pos_range(pos, &spawn_target));
+    }
+
+    Some(directions.into_iter().map(|(direction, _)| direction).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use screeps::{Direction, RoomName, StructureType};
+    use screeps_utils::sparse_cost_matrix::SparseCostMatrix;
+
+    use crate::{
+        state::room::CommunePlan,
+        world::mock_world::{self, pos, MockWorld},
+    };
+
+    use super::{energy_structures, spawn_directions};
+
+    #[test]
+    fn orders_nearest_to_the_fast_filler_and_refreshes_on_new_extensions() {
+        let room_name = RoomName::new("W1N1").unwrap();
+        let mut world = MockWorld::with_commune(room_name);
+        let far = world.add_structure(StructureType::Extension, pos(room_name, 30, 25));
+        let near = world.add_structure(StructureType::Extension, pos(room_name, 26, 25));
+        let spawn = world.rooms[&room_name]
+            .structures
+            .iter()
+            .find(|structure| structure.structure_type == StructureType::Spawn)
+            .unwrap()
+            .id;
+        let (mut game_state, mut memory) = mock_world::install_commune(world, room_name);
+
+        assert_eq!(energy_structures(&room_name, &mut game_state), vec![spawn, near, far]);
+
+        let middle = mock_world::with(|mock| mock.add_structure(StructureType::Extension, pos(room_name, 28, 25)));
+        mock_world::with(|mock| mock.next_tick());
+        game_state.tick_update(&mut memory);
+
+        assert_eq!(energy_structures(&room_name, &mut game_state), vec![spawn, near, middle, far]);
+
+        // Once planned, the fast filler is the anchor rather than the spawn
+        let mut plan = CommunePlan::new();
+        plan.fast_filler_start_positions = Some(vec![pos(room_name, 31, 25)]);
+        game_state.room_states.get_mut(&room_name).unwrap().commune_plan = Some(plan);
+
+        assert_eq!(energy_structures(&room_name, &mut game_state), vec![far, middle, near, spawn]);
+    }
+
+    #[test]
+    fn directions_lead_toward_the_target_and_skip_reserved_tiles() {
+        let room_name = RoomName::new("W1N1").unwrap();
+        let spawn_pos = pos(room_name, 25, 25);
+        let reserved = HashSet::from([pos(room_name, 26, 25)]);
+
+        let directions =
+            spawn_directions(&spawn_pos, Some(pos(room_name, 40, 25)), &SparseCostMatrix::new(), &reserved).unwrap();
+
+        assert_eq!(directions.len(), 7);
+        assert!(!directions.contains(&Direction::Right));
+        assert!(matches!(directions[0], Direction::TopRight | Direction::BottomRight));
+        assert!(matches!(directions[6], Direction::TopLeft | Direction::Left | Direction::BottomLeft));
+    }
+
+    #[test]
+    fn walled_spawns_only_leave_onto_walkable_tiles() {
+        let room_name = RoomName::new("W1N1").unwrap();
+        let spawn_pos = pos(room_name, 25, 25);
+
+        // Walls and extensions on every side but the top
+        let mut move_costs = SparseCostMatrix::new();
+        for direction in [
+            Direction::TopRight,
+            Direction::Right,
+            Direction::BottomRight,
+            Direction::Bottom,
+            Direction::BottomLeft,
+            Direction::Left,
+            Direction::TopLeft,
+        ] {
+            move_costs.set(spawn_pos.checked_add_direction(direction).unwrap().xy(), u8::MAX);
+        }
+
+        let directions = spawn_directions(&spawn_pos, None, &move_costs, &HashSet::new()).unwrap();
+        assert_eq!(directions, vec![Direction::Top]);
+
+        // Reserving the last walkable tile leaves nowhere to go
+        let reserved = HashSet::from([pos(room_name, 25, 24)]);
+        assert!(spawn_directions(&spawn_pos, None, &move_costs, &reserved).is_none());
+    }
+}
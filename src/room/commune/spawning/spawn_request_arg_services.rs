@@ -1,11 +1,12 @@
-use screeps::{Room, RoomName, ENERGY_REGEN_TIME, HARVEST_POWER, SOURCE_ENERGY_CAPACITY};
+use enum_map::EnumMap;
+use screeps::{Position, Room, RoomName, CREEP_SPAWN_TIME, ENERGY_REGEN_TIME, HARVEST_POWER, SOURCE_ENERGY_CAPACITY};
 
 use crate::{
     constants::{
         creep::{CreepPart, CreepRole},
         spawning::{
             spawn_priority_bounds, GroupDiverseSpawnRequestArgs, GroupUniformSpawnRequestArgs,
-            SpawnRequestArgs,
+            SpawnRequestArgs, PRE_SPAWN_CHECK_TICKS,
         },
     },
     memory::{
@@ -13,9 +14,14 @@ use crate::{
         game_memory::GameMemory,
         room_memory::{self, RoomMemory},
     },
-    state::{commune::CommuneState, game::GameState, room::RoomState},
+    pathfinding::{pathfinding_services_multi, room_pather_multi::PathGoals, PathfindingOpts},
+    room::room_ops,
+    state::{commune::CommuneState, game::GameState, room::RoomState, stats_segment::CommuneSegmentStats},
+    utils::general::pos_range,
 };
 
+use super::spawn_time_ops;
+
 // Construct args... not spawn requests
 pub fn create_spawn_request_args(
     room_name: &RoomName,
@@ -26,9 +32,120 @@ pub fn create_spawn_request_args(
 
     harvester_args(&mut spawn_request_args, room_name, game_state, memory);
 
+    let spawn_count = room_ops::structures_by_type(room_name, game_state).spawn.len() as u32;
+    let living_ticks_by_role = living_spawn_ticks(room_name, game_state, memory);
+    let (spawn_request_args, spawn_time_plan) =
+        spawn_time_ops::plan_spawn_time(spawn_request_args, spawn_count, living_ticks_by_role);
+
+    let spawn_usage_percent = spawn_time_plan.usage_percent();
+    game_state.commune_states.get_mut(room_name).unwrap().spawn_usage_percent = spawn_usage_percent;
+    game_state
+        .segments
+        .stats
+        .communes
+        .entry(*room_name)
+        .or_insert_with(CommuneSegmentStats::new)
+        .spawn_usage_percent = spawn_usage_percent;
+
     spawn_request_args
 }
 
+/// Spawn ticks the commune's living creeps took, by role. Creeps being recycled or whose replacement is due
+/// are left out, as their replacements are what the spawn request args ask for
+fn living_spawn_ticks(room_name: &RoomName, game_state: &GameState, memory: &GameMemory) -> EnumMap<CreepRole, u32> {
+    let mut ticks_by_role: EnumMap<CreepRole, u32> = EnumMap::default();
+
+    for (creep_name, creep) in &game_state.creeps {
+        let Some(creep_memory) = memory.creeps.get(creep_name) else {
+            continue;
+        };
+        if creep_memory.room_from != *room_name {
+            continue;
+        }
+
+        let my_creep_state = &game_state.my_creep_states[creep_name];
+        let creep = creep.inner();
+        let replacement_due = creep
+            .ticks_to_live
+            .zip(my_creep_state.pre_spawn_ticks)
+            .is_some_and(|(ticks_to_live, pre_spawn_ticks)| ticks_to_live <= pre_spawn_ticks);
+        if my_creep_state.recycling || replacement_due {
+            continue;
+        }
+
+        ticks_by_role[creep_memory.role] += creep.body.len() as u32 * CREEP_SPAWN_TIME;
+    }
+
+    ticks_by_role
+}
+
+/// Whether a creep still counts toward the strength of its role.
+/// Old creeps stop counting once a replacement spawned now would only just reach their work position as they die
+pub fn counts_toward_strength(
+    creep_name: &str,
+    work_pos: &Position,
+    room_name: &RoomName,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+) -> bool {
+    let creep = game_state.creeps.get(creep_name).unwrap().inner();
+    // Spawning creeps have no ticks to live yet
+    let Some(ticks_to_live) = creep.ticks_to_live else {
+        return true;
+    };
+
+    let spawn_ticks = creep.body.len() as u32 * CREEP_SPAWN_TIME;
+    if ticks_to_live > spawn_ticks + PRE_SPAWN_CHECK_TICKS {
+        return true;
+    }
+
+    ticks_to_live > pre_spawn_ticks(creep_name, spawn_ticks, work_pos, room_name, game_state, memory)
+}
+
+/// Spawn time plus travel time from the nearest spawn to the work position, found once per creep
+fn pre_spawn_ticks(
+    creep_name: &str,
+    spawn_ticks: u32,
+    work_pos: &Position,
+    room_name: &RoomName,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+) -> u32 {
+    if let Some(pre_spawn_ticks) = game_state.my_creep_states.get(creep_name).unwrap().pre_spawn_ticks {
+        return pre_spawn_ticks;
+    }
+
+    let Some(spawn_pos) = room_ops::structures_by_type(room_name, game_state)
+        .spawn
+        .iter()
+        .map(|spawn| spawn.pos)
+        .min_by_key(|pos| pos_range(pos, work_pos))
+    else {
+        return spawn_ticks;
+    };
+
+    let travel_ticks = match pathfinding_services_multi::try_find_path(
+        &spawn_pos,
+        &PathGoals::new_from_pos(*work_pos, 0),
+        PathfindingOpts::new(),
+        game_state,
+        memory,
+    ) {
+        Ok(path) => path.len() as u32,
+        Err(_) => pos_range(&spawn_pos, work_pos),
+    };
+
+    let pre_spawn_ticks = spawn_ticks + travel_ticks;
+    game_state.my_creep_states.get_mut(creep_name).unwrap().pre_spawn_ticks = Some(pre_spawn_ticks);
+
+    pre_spawn_ticks
+}
+
+/// Work parts a source needs to be harvested fully, derived from its regeneration
+pub fn source_work_need() -> u32 {
+    SOURCE_ENERGY_CAPACITY / ENERGY_REGEN_TIME / HARVEST_POWER + 1
+}
+
 fn harvester_args(
     spawn_request_args: &mut Vec<SpawnRequestArgs>,
     room_name: &RoomName,
@@ -43,8 +160,7 @@ fn harvester_args(
 
     for (source_index, source_position) in harvestable_room_memory.source_positions.iter().enumerate() {
 
-        // source harvest need derived from how many work parts
-        let work_need = SOURCE_ENERGY_CAPACITY / ENERGY_REGEN_TIME / HARVEST_POWER + 1;
+        let work_need = source_work_need();
         let Some(work_have) = commune_state.source_harvest_strengths.get(source_index) else {
             continue;
         };
@@ -110,3 +226,49 @@ fn harvester_args(
 fn hauler_args(room: &Room, request_args: &mut Vec<SpawnRequestArgs>, game_state: &GameState) {
     let _ = request_args;
 }
+
+#[cfg(test)]
+mod tests {
+    use screeps::{Part, RoomName};
+
+    use crate::{
+        constants::creep::CreepRole,
+        memory::{creep_memory::CreepMemory, game_memory::GameMemory},
+        state::game::GameState,
+        world::mock_world::{self, pos, MockWorld},
+    };
+
+    use super::counts_toward_strength;
+
+    #[test]
+    fn old_creeps_stop_counting_when_their_replacement_is_due() {
+        let room_name = RoomName::new("W1N1").unwrap();
+        let mut world = MockWorld::with_commune(room_name);
+        world.add_creep("harvester", pos(room_name, 11, 11), &[Part::Work, Part::Work, Part::Move]);
+        let (mut game_state, mut memory) = mock_world::install_commune(world, room_name);
+        memory
+            .creeps
+            .insert("harvester".to_string(), CreepMemory::new(CreepRole::SourceHarvester, room_name));
+
+        let work_pos = pos(room_name, 11, 11);
+        let counts_with_ticks_to_live = |ticks_to_live: u32, game_state: &mut GameState, memory: &mut GameMemory| {
+            mock_world::with(|mock| {
+                mock.next_tick();
+                mock.creeps.get_mut("harvester").unwrap().ticks_to_live = Some(ticks_to_live);
+            });
+            game_state.tick_update(memory);
+
+            counts_toward_strength("harvester", &work_pos, &room_name, game_state, memory)
+        };
+
+        assert!(counts_with_ticks_to_live(1000, &mut game_state, &mut memory));
+        assert_eq!(game_state.my_creep_states["harvester"].pre_spawn_ticks, None);
+
+        assert!(counts_with_ticks_to_live(40, &mut game_state, &mut memory));
+        // Spawn time for 3 parts plus at least the range from the spawn
+        let pre_spawn_ticks = game_state.my_creep_states["harvester"].pre_spawn_ticks.unwrap();
+        assert!(pre_spawn_ticks >= 9 + 14);
+
+        assert!(!counts_with_ticks_to_live(15, &mut game_state, &mut memory));
+    }
+}
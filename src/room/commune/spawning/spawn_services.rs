@@ -1,5 +1,5 @@
 use log::{debug, info};
-use screeps::{BodyPart, Part, Room, RoomName, SpawnOptions, StructureSpawn, StructureType};
+use screeps::{BodyPart, Part, RoomName, StructureType};
 use wasm_bindgen::JsValue;
 use web_sys::console::info;
 
@@ -16,11 +16,12 @@ use crate::{
     memory::{game_memory::GameMemory, room_memory::RoomMemory},
     room::room_ops,
     state::{commune::CommuneState, game::GameState, room::RoomState},
-    utils::{self, general::GeneralUtils},
+    utils::general::GeneralUtils,
+    world::{self, objects::{SpawnOpts, StructureData}},
 };
 
 use super::{
-    spawn_request_arg_ops, spawn_request_arg_services,
+    spawn_ops, spawn_request_arg_ops, spawn_request_arg_services,
 };
 
 pub fn try_spawn_creeps(room_name: &RoomName, game_state: &mut GameState, memory: &mut GameMemory) {
@@ -33,34 +34,36 @@ pub fn try_spawn_creeps(room_name: &RoomName, game_state: &mut GameState, memory
     
     let spawns = room_ops::structures_by_type(room_name, game_state).spawn.clone();
 
-    let mut active_spawns: Vec<&StructureSpawn> = Vec::new();
-    let mut inactive_spawns: Vec<&StructureSpawn> = Vec::new();
+    let mut active_spawns: Vec<&StructureData> = Vec::new();
+    let mut inactive_spawns: Vec<&StructureData> = Vec::new();
 
     for spawn in &spawns {
-        match spawn.spawning() {
+        match &spawn.spawning {
             Some(spawning) => active_spawns.push(spawn),
             _ => inactive_spawns.push(spawn),
         }
     }
 
-    try_use_inactive_spawns(room_name, game_state, memory, &mut inactive_spawns);
+    // Made every tick, busy spawns or not, so the commune's spawn usage stays current
+    let spawn_requests_args =
+        spawn_request_arg_services::create_spawn_request_args(room_name, game_state, memory);
+
+    try_use_inactive_spawns(room_name, spawn_requests_args, game_state, memory, &mut inactive_spawns);
 }
 
 fn try_use_inactive_spawns(
     room_name: &RoomName,
+    spawn_requests_args: Vec<SpawnRequestArgs>,
     game_state: &mut GameState,
     memory: &mut GameMemory,
-    inactive_spawns: &mut Vec<&StructureSpawn>,
+    inactive_spawns: &mut Vec<&StructureData>,
 ) {
     if inactive_spawns.is_empty() {
         return;
     }
 
-    let spawn_requests_args =
-        spawn_request_arg_services::create_spawn_request_args(room_name, game_state, memory);
-
     let room = game_state.rooms.get(room_name).unwrap();
-    let mut spawn_energy_remaining = room.energy_available();
+    let mut spawn_energy_remaining = room.energy_available;
 
     info!("spawn_requests_args: {:?}", spawn_requests_args);
 
@@ -96,7 +99,8 @@ fn try_use_inactive_spawns(
                 Ok((spawn_index, cost)) => {
                     spawn_energy_remaining -= cost;
 
-                    inactive_spawns.remove(spawn_index);
+                    let spawn = inactive_spawns.remove(spawn_index);
+                    game_state.commune_states.get_mut(room_name).unwrap().spawns_used.insert(spawn.id);
 
                     if inactive_spawns.is_empty() {
                         break;
@@ -113,7 +117,7 @@ fn try_use_inactive_spawns(
 
 fn process_spawn_request(
     spawn_request: SpawnRequest,
-    inactive_spawns: &mut [&StructureSpawn],
+    inactive_spawns: &mut [&StructureData],
     game_state: &mut GameState,
     memory: &mut GameMemory,
     spawn_enery_available: u32,
@@ -126,22 +130,26 @@ fn process_spawn_request(
 
     let body = construct_body_for_spawn_request(&spawn_request);
 
-    // TODO: the index should be selected based on the most optimal spawn to spawn from
-    let spawn = inactive_spawns[0];
-    let spawn_index = 0;
+    let spawn_index = spawn_ops::find_spawn_index(spawn_request.spawn_target, inactive_spawns);
+    let spawn = inactive_spawns[spawn_index];
+    let room_name = spawn.pos.room_name();
 
     let custom_id = new_creep_id(game_state, memory).ok().unwrap();
     // let name = format!("{:?}_{}", spawn_request.role, custom_id);
     let name = format!("{}", custom_id);
 
-    // let energy_structures;
+    let move_costs = room_ops::default_move_costs(&room_name, game_state, memory);
+    let reserved_positions = &game_state.commune_states.get(&room_name).unwrap().reserved_positions;
+    let directions =
+        spawn_ops::spawn_directions(&spawn.pos, spawn_request.spawn_target, &move_costs, reserved_positions);
+
+    let spawn_opts = SpawnOpts {
+        energy_structures: Some(spawn_ops::energy_structures(&room_name, game_state)),
+        directions,
+        ..SpawnOpts::new()
+    };
 
-    let spawn_result = spawn.spawn_creep_with_options(
-        body.as_slice(),
-        name.as_str(),
-        // Need energy structures
-        &SpawnOptions::new().dry_run(false),
-    );
+    let spawn_result = world::spawn_creep(spawn.id, body.as_slice(), name.as_str(), &spawn_opts);
 
     let Ok(spawn_result) = spawn_result else {
         log::error!("Failed to spawn creep: {:?}", spawn_result);
@@ -237,18 +245,3 @@ fn construct_body_for_spawn_request(spawn_request: &SpawnRequest) -> Vec<Part> {
 
     body
 }
-
-fn find_spawn_index_for_spawn_request(
-    spawn_request: &SpawnRequest,
-    inactive_spawns: Vec<StructureSpawn>,
-) -> u32 {
-    let Some(spawn_target) = spawn_request.spawn_target else {
-        return 0;
-    };
-
-    let (score, index) = utils::general::find_index_with_lowest_score(&inactive_spawns, &|spawn| {
-        utils::general::pos_range(&screeps::HasPosition::pos(&spawn), &spawn_target)
-    });
-
-    index
-}
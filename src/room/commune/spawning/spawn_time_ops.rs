@@ -0,0 +1,211 @@
+use enum_map::EnumMap;
+use log::debug;
+use screeps::{CREEP_LIFE_TIME, CREEP_SPAWN_TIME, MAX_CREEP_SIZE};
+
+use crate::constants::{
+    creep::CreepRole,
+    spawning::{SpawnRequestArgs, SpawnTimeBudget, SPAWN_TIME_BUDGET_BY_ROLE},
+};
+
+/// Spawn time the commune's living creeps and the emitted spawn request args take over a creep lifetime
+#[derive(Debug, Default)]
+pub struct SpawnTimePlan {
+    pub ticks_by_role: EnumMap<CreepRole, u32>,
+    /// Spawn ticks the commune's spawns can provide over a creep lifetime
+    pub capacity: u32,
+}
+
+impl SpawnTimePlan {
+    pub fn used_ticks(&self) -> u32 {
+        self.ticks_by_role.values().sum()
+    }
+
+    pub fn usage_percent(&self) -> u32 {
+        if self.capacity == 0 {
+            return 0;
+        }
+
+        self.used_ticks() * 100 / self.capacity
+    }
+}
+
+/// Rough number of body parts the args would spawn
+pub fn estimate_body_parts(args: &SpawnRequestArgs) -> u32 {
+    match args {
+        SpawnRequestArgs::IndividualUniform(args) => {
+            let parts_per_creep = args
+                .extra_parts_quota
+                .max(args.default_parts.len() as u32)
+                .min(MAX_CREEP_SIZE);
+
+            parts_per_creep * args.creeps_quota
+        }
+        SpawnRequestArgs::GroupUniform(args) => {
+            let max_parts = args.max_creeps.map_or(u32::MAX, |max_creeps| max_creeps.saturating_mul(MAX_CREEP_SIZE));
+
+            args.extra_parts_quota.min(max_parts)
+        }
+        SpawnRequestArgs::GroupDiverse(args) => {
+            let extra_parts = args.extra_parts.len() as u32 * args.extra_parts_quota;
+            let extra_parts_per_creep = (MAX_CREEP_SIZE - args.default_parts.len() as u32).max(1);
+
+            let mut creeps = extra_parts.div_ceil(extra_parts_per_creep);
+            if let Some(max_creeps) = args.max_creeps {
+                creeps = creeps.min(max_creeps);
+            }
+
+            (extra_parts + creeps * args.default_parts.len() as u32).min(creeps * MAX_CREEP_SIZE)
+        }
+    }
+}
+
+/// Spawn ticks the args would take, which is also what they take per creep lifetime as each creep is replaced once
+pub fn estimate_spawn_ticks(args: &SpawnRequestArgs) -> u32 {
+    estimate_body_parts(args) * CREEP_SPAWN_TIME
+}
+
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+/// Keep the args that fit in the spawn time of the commune's spawns left over by its living creeps.
+/// Essential roles are always kept, then commune and remote roles take what is left in order of spawn priority
+pub fn plan_spawn_time(
+    spawn_request_args: Vec<SpawnRequestArgs>,
+    spawn_count: u32,
+    living_ticks_by_role: EnumMap<CreepRole, u32>,
+) -> (Vec<SpawnRequestArgs>, SpawnTimePlan) {
+    let mut plan = SpawnTimePlan {
+        ticks_by_role: living_ticks_by_role,
+        capacity: spawn_count * CREEP_LIFE_TIME,
+    };
+
+    let mut budgeted: Vec<(SpawnTimeBudget, SpawnRequestArgs)> = SPAWN_TIME_BUDGET_BY_ROLE.with(|budget_by_role| {
+        spawn_request_args
+            .into_iter()
+            .map(|args| (budget_by_role[args.role()], args))
+            .collect()
+    });
+    budgeted.sort_by(|(a_budget, a), (b_budget, b)| {
+        a_budget
+            .cmp(b_budget)
+            .then(a.priority().total_cmp(&b.priority()))
+    });
+
+    let mut kept = Vec::new();
+
+    for (budget, args) in budgeted {
+        let ticks = estimate_spawn_ticks(&args);
+
+        if budget != SpawnTimeBudget::Essential && plan.used_ticks() + ticks > plan.capacity {
+            debug!("Not enough spawn time for {:?}, which needs {} ticks", args.role(), ticks);
+            continue;
+        }
+
+        plan.ticks_by_role[args.role()] += ticks;
+        kept.push(args);
+    }
+
+    (kept, plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use enum_map::EnumMap;
+    use screeps::RoomName;
+
+    use crate::{
+        constants::{
+            creep::{CreepPart, CreepRole},
+            spawning::{
+                GroupDiverseSpawnRequestArgs, GroupUniformSpawnRequestArgs, IndividualUniformSpawnRequestArgs,
+                SpawnRequestArgs,
+            },
+        },
+        memory::creep_memory::CreepMemory,
+    };
+
+    use super::plan_spawn_time;
+
+    fn room_name() -> RoomName {
+        RoomName::new("W1N1").unwrap()
+    }
+
+    #[test]
+    fn remotes_are_cut_before_essential_and_commune_roles() {
+        let harvester = SpawnRequestArgs::GroupUniform(GroupUniformSpawnRequestArgs {
+            role: CreepRole::SourceHarvester,
+            default_parts: vec![CreepPart::Move],
+            extra_parts: vec![CreepPart::Work],
+            extra_parts_quota: 12,
+            min_cost_per_creep: 100,
+            max_cost_per_creep: None,
+            memory_additions: CreepMemory::new(CreepRole::SourceHarvester, room_name()),
+            priority: 2.0,
+            max_creeps: None,
+            threshold: None,
+            spawn_target: None,
+        });
+        let remote_hauler = SpawnRequestArgs::GroupDiverse(GroupDiverseSpawnRequestArgs {
+            role: CreepRole::RemoteHauler,
+            default_parts: vec![],
+            extra_parts: vec![CreepPart::Carry, CreepPart::Move],
+            extra_parts_quota: 150,
+            min_cost_per_creep: 100,
+            max_cost_per_creep: None,
+            memory_additions: CreepMemory::new(CreepRole::RemoteHauler, room_name()),
+            priority: 10.3,
+            max_creeps: None,
+            threshold: None,
+            spawn_target: None,
+        });
+        let upgrader = SpawnRequestArgs::IndividualUniform(IndividualUniformSpawnRequestArgs {
+            role: CreepRole::Upgrader,
+            default_parts: vec![CreepPart::Carry],
+            extra_parts: vec![CreepPart::Work, CreepPart::Move],
+            extra_parts_quota: 50,
+            min_cost_per_creep: 200,
+            max_cost_per_creep: 3000,
+            memory_additions: CreepMemory::new(CreepRole::Upgrader, room_name()),
+            priority: 0.0,
+            creeps_quota: 5,
+            spawn_target: None,
+        });
+
+        let (kept, plan) = plan_spawn_time(vec![remote_hauler, upgrader, harvester], 1, EnumMap::default());
+
+        assert_eq!(kept.len(), 2);
+        assert!(matches!(kept[0].role(), CreepRole::SourceHarvester));
+        assert!(matches!(kept[1].role(), CreepRole::Upgrader));
+
+        assert_eq!(plan.capacity, 1500);
+        assert_eq!(plan.ticks_by_role[CreepRole::SourceHarvester], 36);
+        assert_eq!(plan.ticks_by_role[CreepRole::Upgrader], 750);
+        assert_eq!(plan.ticks_by_role[CreepRole::RemoteHauler], 0);
+        assert_eq!(plan.usage_percent(), 52);
+    }
+
+    #[test]
+    fn living_creeps_count_toward_usage_and_leave_less_time_for_args() {
+        let remote_hauler = SpawnRequestArgs::GroupDiverse(GroupDiverseSpawnRequestArgs {
+            role: CreepRole::RemoteHauler,
+            default_parts: vec![],
+            extra_parts: vec![CreepPart::Carry, CreepPart::Move],
+            extra_parts_quota: 20,
+            min_cost_per_creep: 100,
+            max_cost_per_creep: None,
+            memory_additions: CreepMemory::new(CreepRole::RemoteHauler, room_name()),
+            priority: 10.3,
+            max_creeps: None,
+            threshold: None,
+            spawn_target: None,
+        });
+
+        // A fully staffed commune asks for nothing but its creeps still took spawn time
+        let mut living_ticks_by_role = EnumMap::default();
+        living_ticks_by_role[CreepRole::Upgrader] = 1440;
+        let (_, plan) = plan_spawn_time(Vec::new(), 1, living_ticks_by_role);
+        assert_eq!(plan.usage_percent(), 96);
+
+        let (kept, plan) = plan_spawn_time(vec![remote_hauler], 1, living_ticks_by_role);
+        assert!(kept.is_empty());
+        assert_eq!(plan.ticks_by_role[CreepRole::RemoteHauler], 0);
+    }
+}
@@ -106,10 +106,10 @@ fn find_fast_filler_start_positions(
     let controller_pos = room_ops::controller(room_name, game_state)
         .as_ref()
         .unwrap()
-        .pos();
+        .pos;
 
     for source in sources {
-        let source_pos = source.pos();
+        let source_pos = source.pos;
 
         start_positions.push(source_pos);
 
@@ -1,4 +1,4 @@
-use screeps::{spawn, Resource, ResourceType, RoomName, StructureExtension};
+use screeps::{ResourceType, RoomName};
 
 use crate::{constants::structure::SpawningStructure, state::game::GameState};
 
@@ -18,7 +18,9 @@ pub fn create_power_spawn_logistics_requests(room_name: &RoomName, game_state: &
 
     let power_spawns = &room_ops::structures_by_type(room_name, game_state).power_spawn;
     for power_spawn in power_spawns {
-        let store = power_spawn.store();
+        let Some(store) = &power_spawn.store else {
+            continue;
+        };
 
         if store.get_used_capacity(Some(ResourceType::Energy)) == store.get_capacity(Some(ResourceType::Energy)) {
 
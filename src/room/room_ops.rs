@@ -1,5 +1,6 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{hash_map::DefaultHasher, HashMap, HashSet},
+    hash::{Hash, Hasher},
     io::Error,
     str::FromStr, u8,
 };
@@ -7,30 +8,30 @@ use std::{
 use enum_map::{enum_map, EnumMap};
 use log::{debug, warn};
 use screeps::{
-    find, game::{self, map::RoomStatus}, look, structure, ConstructionSite, Creep, HasPosition, LocalRoomTerrain, ObjectId, OwnedStructureProperties, Position, Room, RoomCoordinate, RoomName, RoomTerrain, RoomVisual, RoomXY, SharedCreepProperties, Source, Structure, StructureContainer, StructureController, StructureExtension, StructureExtractor, StructureFactory, StructureInvaderCore, StructureKeeperLair, StructureLink, StructureNuker, StructureObject, StructureObserver, StructurePowerBank, StructurePowerSpawn, StructureProperties, StructureRampart, StructureRoad, StructureSpawn, StructureStorage, StructureTerminal, StructureTower, StructureType, StructureWall, Terrain, CREEP_RANGED_ACTION_RANGE
+    game::map::RoomStatus, LocalRoomTerrain, ObjectId, Part, Position, ResourceType, RoomCoordinate, RoomName, RoomXY, StructureType, Terrain, CREEP_RANGED_ACTION_RANGE, TOWER_ENERGY_COST
 };
 use screeps_utils::sparse_cost_matrix::{SparseCostMatrix, ROOM_AREA};
 
 use crate::{
     constants::{
         general::{FlowResult, GeneralError, GeneralResult},
-        move_costs::{DEFAULT_SWAMP_COST, DEFAULT_WALL_COST, MAX_COST},
-        room::{NotMyCreeps, MAX_REMOTE_ROOM_DISTANCE, ROOM_DIMENSIONS},
+        move_costs::{DEFAULT_SWAMP_COST, DEFAULT_WALL_COST, MAX_COST, TOWER_DAMAGE_PER_COST},
+        room::{NotMyCreeps, DANGER_REFRESH_PERCENT, MAX_REMOTE_ROOM_DISTANCE, ROOM_DIMENSIONS},
         structure::{
             OldOrganizedStructures, OrganizedStructures, SpawnsByActivity, IMPASSIBLE_STRUCTURES,
         },
     }, creep::creep_move_ops::try_run_move_request, memory::{
         game_memory::GameMemory,
         room_memory::{
-            self, AllyRoomMemory, EnemyRoomMemory, HarvestableRoomMemory, HighwayRoomMemory, PortalRoomMemory, RemoteRoomMemory, RoomMemory, StaticRoomType
+            self, AllyRoomMemory, EnemyRoomMemory, HarvestableRoomMemory, HighwayRoomMemory, PortalRoomMemory, RemoteRoomMemory, RoomDanger, RoomMemory, StaticRoomType
         }, static_room_memory::{ClaimableRoomMemory, KeeperRoomMemory},
-    }, pathfinding::{portal_router_single, room_costs, route_costs, PathfindingOpts}, settings::Settings, state::{
+    }, pathfinding::{portal_router_single, room_costs, route_costs, PathfindingOpts}, settings::Settings, structures::tower_services, state::{
         commune::CommuneState, game::GameState, market::MarketState, room::{self, NotMyConstructionSites, RoomState}
     }, utils::{
         self,
         general::{for_adjacent_positions, GeneralUtils},
         pos::{for_positions_in_range_in_room, get_adjacent_positions_unbounded, get_positions_in_range_in_room},
-    }, GAME_STATE
+    }, world::{self, objects::{ConstructionSiteData, ControllerData, SourceData, StructureData, WorldVisual}}, GAME_STATE, SETTINGS
 };
 
 /// Acquires and caches structures in the room based on their structure type
@@ -55,10 +56,13 @@ use crate::{
     })
 } */
 
+/// Tiles enemy creeps can attack next tick, by the parts they have. ATTACK reaches adjacent tiles
+/// and RANGED_ATTACK reaches 3, and these tiles are MAX_COST. Enemy towers reach the whole room,
+/// so their damage at each tile adds a cost that falls off with range instead
 pub fn enemy_threat_positions<'state>(
     room_name: &RoomName,
     game_state: &'state mut GameState,
-    memory: &mut GameMemory,
+    memory: &GameMemory,
 ) -> SparseCostMatrix {
     {
         let room_state = game_state.room_states.get(room_name).unwrap();
@@ -72,30 +76,78 @@ pub fn enemy_threat_positions<'state>(
     let enemy_creeps = not_my_creeps(room_name, game_state, memory).enemy.clone();
 
     for creep in enemy_creeps {
-        let positions =
-            get_positions_in_range_in_room(&creep.pos(), CREEP_RANGED_ACTION_RANGE );
+        let range = if creep.get_active_bodyparts(Part::RangedAttack) > 0 {
+            CREEP_RANGED_ACTION_RANGE
+        } else if creep.get_active_bodyparts(Part::Attack) > 0 {
+            1
+        } else {
+            continue;
+        };
 
-        for pos in positions {
+        for pos in get_positions_in_range_in_room(&creep.pos, range) {
             threat_positions.set(pos.xy(), MAX_COST);
         }
     }
 
+    let enemy_towers = enemy_towers(room_name, game_state, memory);
+    if !enemy_towers.is_empty() {
+        for x in 0..ROOM_DIMENSIONS {
+            for y in 0..ROOM_DIMENSIONS {
+                let xy = RoomXY::checked_new(x, y).unwrap();
+                if threat_positions.get(xy) == MAX_COST {
+                    continue;
+                }
+
+                let pos = Position::new(xy.x, xy.y, *room_name);
+                let damage = tower_services::find_towers_attack_power(&enemy_towers, &pos);
+
+                // Never MAX_COST, towers alone don't make a tile impassable
+                let cost = (damage / TOWER_DAMAGE_PER_COST).min(MAX_COST as u32 - 1) as u8;
+                if cost > 0 {
+                    threat_positions.set(xy, cost);
+                }
+            }
+        }
+    }
+
     let room_state = game_state.room_states.get_mut(room_name).unwrap();
 
     room_state.enemy_threat_positions = Some(threat_positions.clone());
     threat_positions
 }
 
+/// Towers of players that aren't allies, with the energy to fire
+pub fn enemy_towers(
+    room_name: &RoomName,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+) -> Vec<StructureData> {
+    structures(room_name, game_state)
+        .iter()
+        .filter(|structure| {
+            structure.structure_type == StructureType::Tower
+                && structure.my == Some(false)
+                && !structure
+                    .owner
+                    .as_ref()
+                    .is_some_and(|owner| memory.allies.contains_key(owner))
+                && structure.store.as_ref().is_some_and(|store| {
+                    store.get_used_capacity(Some(ResourceType::Energy)) >= TOWER_ENERGY_COST
+                })
+        })
+        .cloned()
+        .collect()
+}
+
 pub fn structures<'state>(
     room_name: &RoomName,
     game_state: &'state mut GameState,
-) -> &'state Vec<StructureObject> {
+) -> &'state Vec<StructureData> {
     let room_state = game_state.room_states.get_mut(room_name).unwrap();
 
     let structures = room_state.structures.get_or_insert_with(
         (|| {
-            let room = game_state.rooms.get(room_name).unwrap();
-            room.find(find::STRUCTURES, None)
+            world::structures(*room_name)
         }),
     );
 
@@ -105,13 +157,14 @@ pub fn structures<'state>(
 pub fn my_construction_sites<'state>(
     room_name: &RoomName,
     game_state: &'state mut GameState,
-) -> &'state Vec<ConstructionSite> {
+) -> &'state Vec<ConstructionSiteData> {
     let room_state = game_state.room_states.get_mut(room_name).unwrap();
 
     let c_sites = room_state.my_construction_sites.get_or_insert_with(
         (|| {
-            let room = game_state.rooms.get(room_name).unwrap();
-            room.find(find::MY_CONSTRUCTION_SITES, None)
+            let mut c_sites = world::construction_sites(*room_name);
+            c_sites.retain(|c_site| c_site.my);
+            c_sites
         }),
     );
 
@@ -129,11 +182,11 @@ pub fn not_my_construction_sites<'state>(
         (|| {
             let mut not_my_construction_sites = NotMyConstructionSites::new();
 
-            let room = game_state.rooms.get(room_name).unwrap();
-            let hostile_c_sites = room.find(find::HOSTILE_CONSTRUCTION_SITES, None);
+            let mut hostile_c_sites = world::construction_sites(*room_name);
+            hostile_c_sites.retain(|c_site| !c_site.my);
 
             for c_site in hostile_c_sites {
-                if memory.allies.contains_key(&c_site.owner().username()) {
+                if memory.allies.contains_key(&c_site.owner) {
                     not_my_construction_sites.ally.push(c_site);
                     continue;
                 }
@@ -160,64 +213,62 @@ pub fn structures_by_type<'state>(
             ..Default::default()
         };
 
-        let room = game_state.rooms.get(room_name).unwrap();
-
-        for structure in room.find(find::STRUCTURES, None) {
-            match structure.structure_type() {
+        for structure in world::structures(*room_name) {
+            match structure.structure_type {
                 StructureType::Spawn => {
                     new_organized_structures
                         .spawn
-                        .push(TryInto::<StructureSpawn>::try_into(structure).unwrap());
+                        .push(structure);
                 }
                 StructureType::Extension => {
                     new_organized_structures
                         .extension
-                        .push(TryInto::<StructureExtension>::try_into(structure).unwrap());
+                        .push(structure);
                 }
                 StructureType::Road => {
                     new_organized_structures
                         .road
-                        .push(TryInto::<StructureRoad>::try_into(structure).unwrap());
+                        .push(structure);
                 }
                 StructureType::Wall => {
                     new_organized_structures
                         .wall
-                        .push(TryInto::<StructureWall>::try_into(structure).unwrap());
+                        .push(structure);
                 }
                 StructureType::Rampart => {
                     new_organized_structures
                         .rampart
-                        .push(TryInto::<StructureRampart>::try_into(structure).unwrap());
+                        .push(structure);
                 }
                 StructureType::Container => {
                     new_organized_structures
                         .container
-                        .push(TryInto::<StructureContainer>::try_into(structure).unwrap());
+                        .push(structure);
                 }
                 StructureType::Link => {
                     new_organized_structures
                         .link
-                        .push(TryInto::<StructureLink>::try_into(structure).unwrap());
+                        .push(structure);
                 }
                 StructureType::KeeperLair => {
                     new_organized_structures
                         .keeper_lair
-                        .push(TryInto::<StructureKeeperLair>::try_into(structure).unwrap());
+                        .push(structure);
                 }
                 StructureType::PowerBank => {
                     new_organized_structures
                         .power_bank
-                        .push(TryInto::<StructurePowerBank>::try_into(structure).unwrap());
+                        .push(structure);
                 }
                 StructureType::Tower => {
                     new_organized_structures
                         .tower
-                        .push(TryInto::<StructureTower>::try_into(structure).unwrap());
+                        .push(structure);
                 }
                 StructureType::InvaderCore => {
                     new_organized_structures
                         .invader_core
-                        .push(TryInto::<StructureInvaderCore>::try_into(structure).unwrap());
+                        .push(structure);
                 }
                 _ => {}
             }
@@ -232,12 +283,12 @@ pub fn structures_by_type<'state>(
 pub fn storage<'state>(
     room_name: &RoomName,
     game_state: &'state mut GameState,
-) -> &'state Option<StructureStorage> {
-    let room = game_state.rooms.get(room_name).unwrap();
+) -> &'state Option<StructureData> {
+    let storage = structure_of_type(room_name, game_state, StructureType::Storage);
 
     let room_state = game_state.room_states.get_mut(room_name).unwrap();
 
-    room_state.storage = room.storage();
+    room_state.storage = storage;
     &room_state.storage
 }
 
@@ -245,12 +296,12 @@ pub fn storage<'state>(
 pub fn controller<'state>(
     room_name: &RoomName,
     game_state: &'state mut GameState,
-) -> &'state Option<StructureController> {
+) -> &'state Option<ControllerData> {
     let room = game_state.rooms.get(room_name).unwrap();
 
     let room_state = game_state.room_states.get_mut(room_name).unwrap();
 
-    room_state.controller = room.controller();
+    room_state.controller = room.controller.clone();
     &room_state.controller
 }
 
@@ -258,15 +309,27 @@ pub fn controller<'state>(
 pub fn terminal<'state>(
     room_name: &RoomName,
     game_state: &'state mut GameState,
-) -> &'state Option<StructureTerminal> {
-    let room = game_state.rooms.get(room_name).unwrap();
+) -> &'state Option<StructureData> {
+    let terminal = structure_of_type(room_name, game_state, StructureType::Terminal);
 
     let room_state = game_state.room_states.get_mut(room_name).unwrap();
 
-    room_state.terminal = room.terminal();
+    room_state.terminal = terminal;
     &room_state.terminal
 }
 
+/// The room's only structure of a type, for structures such as the storage and terminal that a room has at most one of
+fn structure_of_type(
+    room_name: &RoomName,
+    game_state: &mut GameState,
+    structure_type: StructureType,
+) -> Option<StructureData> {
+    structures(room_name, game_state)
+        .iter()
+        .find(|structure| structure.structure_type == structure_type)
+        .cloned()
+}
+
 // pub fn spawns_by_activity<'state>(
 //     room_name: &RoomName,
 //     game_state: &'state mut GameState,
@@ -332,11 +395,9 @@ pub fn not_my_creeps(
 
     let mut new_not_my_creeps: NotMyCreeps = NotMyCreeps::new();
 
-    let room = game_state.rooms.get(room_name).unwrap();
-
-    let unorganized_not_my_creeps: Vec<Creep> = room.find(find::HOSTILE_CREEPS, None);
+    let unorganized_not_my_creeps = world::hostile_creeps(*room_name);
     for creep in unorganized_not_my_creeps {
-        if memory.allies.contains_key(&creep.name()) {
+        if memory.allies.contains_key(&creep.name) {
             new_not_my_creeps.ally.push(creep);
             continue;
         }
@@ -348,7 +409,7 @@ pub fn not_my_creeps(
     new_not_my_creeps
 }
 
-pub fn get_sources(room_name: &RoomName, game_state: &mut GameState) -> Vec<Source> {
+pub fn get_sources(room_name: &RoomName, game_state: &mut GameState) -> Vec<SourceData> {
     let room_data = game_state.room_states.get_mut(room_name).unwrap();
 
     let mut sources = &room_data.sources;
@@ -356,14 +417,12 @@ pub fn get_sources(room_name: &RoomName, game_state: &mut GameState) -> Vec<Sour
         return sources.clone();
     };
 
-    let room = game_state.rooms.get(room_name).unwrap();
-
-    let new_sources: Vec<Source> = room.find(find::SOURCES, None);
+    let new_sources = world::sources(*room_name);
     room_data.sources = Some(new_sources.clone());
     new_sources
 }
 
-pub fn commune_sources(room_name: &RoomName, game_state: &mut GameState) -> Vec<Source> {
+pub fn commune_sources(room_name: &RoomName, game_state: &mut GameState) -> Vec<SourceData> {
     get_sources(room_name, game_state)
 }
 
@@ -385,15 +444,15 @@ pub fn harvest_positions(
         return None;
     };
 
+    let source_positions = harvestable_room_memory.source_positions.clone();
     let mut new_harvest_positions: Vec<Vec<Position>> = Vec::new();
-    let room = game_state.rooms.get(room_name).unwrap();
+    let terrain = terrain(room_name, game_state);
 
-    for source_pos in harvestable_room_memory.source_positions.iter() {
+    for source_pos in source_positions.iter() {
         let mut positions = Vec::new();
         
         for adj_pos in get_adjacent_positions_unbounded(source_pos) {
-            let terrain = room.look_for_at(look::TERRAIN, &adj_pos);
-            if terrain.contains(&Terrain::Wall) { continue }
+            if terrain.get_xy(adj_pos.xy()) == Terrain::Wall { continue }
             
             positions.push(adj_pos);
         } 
@@ -401,7 +460,7 @@ pub fn harvest_positions(
         new_harvest_positions.push(positions);
     }
 
-    let room_data = game_state.room_states.get_mut(&room.name()).unwrap();
+    let room_data = game_state.room_states.get_mut(room_name).unwrap();
 
     room_data.harvest_positions = Some(new_harvest_positions.clone());
     Some(new_harvest_positions)
@@ -416,8 +475,7 @@ pub fn room_status(room_name: &RoomName, game_state: &mut GameState) -> RoomStat
         return status;
     }
 
-    let status_result = game::map::get_room_status(*room_name).unwrap();
-    let new_status = status_result.status();
+    let new_status = world::room_status(*room_name).unwrap();
 
     room_state.status = Some(new_status);
     new_status
@@ -458,8 +516,11 @@ pub fn try_add_remote(
 
     // Check if the commune is blacklisted
     {
-        let claimable_room_memory = memory.harvestable_rooms.get_mut(room_name).unwrap();
-        if let Some(remote_blacklist) = &mut claimable_room_memory.remote_blacklist {
+        // Harvestable room memory may not have loaded from its segments yet
+        let Some(claimable_room_memory) = memory.harvestable_rooms.get(room_name) else {
+            return FlowResult::Continue;
+        };
+        if let Some(remote_blacklist) = &claimable_room_memory.remote_blacklist {
             if remote_blacklist.contains(room_name) {
                 return FlowResult::Continue;
             }
@@ -485,9 +546,10 @@ pub fn try_add_remote(
     if route.len() as u8 > MAX_REMOTE_ROOM_DISTANCE {
         debug!("Route too long for room {}", room_name);
 
-        let harvestable_room_memory = memory.harvestable_rooms.get_mut(room_name).unwrap();
-        if let Some(remote_blacklist) = &mut harvestable_room_memory.remote_blacklist {
-            remote_blacklist.insert(*room_name);
+        if let Some(harvestable_room_memory) = memory.harvestable_rooms.get_mut(room_name) {
+            if let Some(remote_blacklist) = &mut harvestable_room_memory.remote_blacklist {
+                remote_blacklist.insert(*room_name);
+            }
         }
 
         return FlowResult::Continue;
@@ -535,8 +597,7 @@ pub fn terrain(room_name: &RoomName, game_state: &mut GameState) -> LocalRoomTer
 
     let room_state = game_state.room_states.get_mut(room_name).unwrap();
 
-    let js_terrain = game::map::get_room_terrain(*room_name).unwrap();
-    let terrain = LocalRoomTerrain::from(js_terrain);
+    let terrain = world::terrain(*room_name).unwrap();
 
     room_state.terrain = Some(terrain.clone());
     terrain
@@ -580,12 +641,16 @@ pub fn default_move_costs(
     game_state: &mut GameState,
     memory: &GameMemory,
 ) -> SparseCostMatrix {
+    let signature = move_costs_signature(room_name, game_state, memory);
     {
         // log::info!("room_name {} rooms {:?}", room_name, game_state.rooms);
         let room_state = game_state.get_or_create_room_state_mut(room_name);
 
+        // Rebuild if a structure or construction site has been added or removed since
         if let Some(default_move_ops) = &room_state.default_move_ops {
-            return default_move_ops.clone();
+            if room_state.default_move_ops_signature == signature {
+                return default_move_ops.clone();
+            }
         }
     }
 
@@ -595,36 +660,85 @@ pub fn default_move_costs(
 
     let structures = structures(room_name, game_state);
     for structure in structures {
-        if !IMPASSIBLE_STRUCTURES.contains(&structure.structure_type()) {
+        if !IMPASSIBLE_STRUCTURES.contains(&structure.structure_type) {
             continue;
         }
 
-        default_move_ops.set(structure.pos().xy(), u8::MAX);
+        default_move_ops.set(structure.pos.xy(), u8::MAX);
     }
 
     // Avoid construction sites we own that are impassible
 
     let my_construction_sites = my_construction_sites(room_name, game_state);
     for construction_site in my_construction_sites {
-        if !IMPASSIBLE_STRUCTURES.contains(&construction_site.structure_type()) {
+        if !IMPASSIBLE_STRUCTURES.contains(&construction_site.structure_type) {
             continue;
         }
 
-        default_move_ops.set(construction_site.pos().xy(), u8::MAX);
+        default_move_ops.set(construction_site.pos.xy(), u8::MAX);
     }
 
     // Avoid all ally construction sites
 
     let consturction_sites = &not_my_construction_sites(room_name, game_state, memory).ally;
     for construction_site in consturction_sites {
-        default_move_ops.set(construction_site.pos().xy(), u8::MAX);
+        default_move_ops.set(construction_site.pos.xy(), u8::MAX);
     }
 
     let room_state = game_state.room_states.get_mut(room_name).unwrap();
     room_state.default_move_ops = Some(default_move_ops.clone());
+    room_state.default_move_ops_signature = signature;
     default_move_ops
 }
 
+/// Hash of the structures and construction sites default_move_costs reads.
+/// It changes when one is added or removed, and so do the costs
+pub fn move_costs_signature(
+    room_name: &RoomName,
+    game_state: &mut GameState,
+    memory: &GameMemory,
+) -> u64 {
+    {
+        let room_state = game_state.get_or_create_room_state_mut(room_name);
+
+        if let Some(signature) = room_state.move_costs_signature {
+            return signature;
+        }
+    }
+
+    let mut hasher = DefaultHasher::new();
+
+    for structure in structures(room_name, game_state) {
+        if IMPASSIBLE_STRUCTURES.contains(&structure.structure_type) {
+            (structure.structure_type, structure.pos.xy()).hash(&mut hasher);
+        }
+    }
+
+    for construction_site in my_construction_sites(room_name, game_state) {
+        if IMPASSIBLE_STRUCTURES.contains(&construction_site.structure_type) {
+            (construction_site.structure_type, construction_site.pos.xy()).hash(&mut hasher);
+        }
+    }
+
+    for construction_site in &not_my_construction_sites(room_name, game_state, memory).ally {
+        construction_site.pos.xy().hash(&mut hasher);
+    }
+
+    let signature = hasher.finish();
+
+    let room_state = game_state.room_states.get_mut(room_name).unwrap();
+    room_state.move_costs_signature = Some(signature);
+    signature
+}
+
+/// Whether there are enemy creeps or towers that paths found in the room would have to avoid
+pub fn has_enemy_threats(room_name: &RoomName, game_state: &mut GameState, memory: &GameMemory) -> bool {
+    game_state.get_or_create_room_state_mut(room_name);
+
+    !not_my_creeps(room_name, game_state, memory).enemy.is_empty()
+        || !enemy_towers(room_name, game_state, memory).is_empty()
+}
+
 pub fn try_scout_room(room_name: &RoomName, game_state: &mut GameState, memory: &mut GameMemory) -> Result<GeneralResult, GeneralError> {
     // If we already have memory of this room
     if let Some(room_memory) = memory.rooms.get(room_name) {
@@ -692,6 +806,73 @@ pub fn try_scout_room(room_name: &RoomName, game_state: &mut GameState, memory:
     Ok(GeneralResult::Success)
 }
 
+/// Refresh the intel route costs rely on for a visible room, only touching memory when it changed
+pub fn update_room_intel(room_name: &RoomName, game_state: &mut GameState, memory: &mut GameMemory) {
+    if !memory.rooms.contains_key(room_name) {
+        return;
+    }
+
+    game_state.get_or_create_room_state_mut(room_name);
+
+    let combat_parts: u32 = not_my_creeps(room_name, game_state, memory)
+        .enemy
+        .iter()
+        .map(|creep| {
+            (creep.get_active_bodyparts(Part::Attack) + creep.get_active_bodyparts(Part::RangedAttack)) as u32
+        })
+        .sum();
+    let enemy_towers = enemy_towers(room_name, game_state, memory).len().min(u8::MAX as usize) as u8;
+
+    let danger_decay_ticks = SETTINGS.with_borrow(|settings| settings.route_weights.danger_decay_ticks);
+    let refresh_age = danger_decay_ticks * DANGER_REFRESH_PERCENT / 100;
+
+    let room_memory = &memory.rooms[room_name];
+    // Danger that hasn't changed keeps its timestamp until it is old enough to be worth restamping,
+    // so enemies still in sight don't decay away without writing memory every tick
+    let danger = match room_memory.danger {
+        _ if combat_parts == 0 => None,
+        Some(danger)
+            if danger.combat_parts == combat_parts
+                && game_state.tick.saturating_sub(danger.tick) < refresh_age =>
+        {
+            Some(danger)
+        }
+        _ => Some(RoomDanger {
+            combat_parts,
+            tick: game_state.tick,
+        }),
+    };
+
+    if room_memory.danger != danger || room_memory.enemy_towers != enemy_towers {
+        let room_memory = memory.rooms.get_mut(room_name).unwrap();
+        room_memory.danger = danger;
+        room_memory.enemy_towers = enemy_towers;
+    }
+
+    // Controllers change hands, so keep ownership current for hostile owner costs
+
+    let Some(owner) = game_state
+        .rooms
+        .get(room_name)
+        .and_then(|room| room.controller.as_ref())
+        .map(|controller| controller.owner.clone())
+    else {
+        return;
+    };
+    let Some(claimable_memory) = memory.claimable_rooms.get(room_name) else {
+        return;
+    };
+
+    let my_claim = owner.as_ref().filter(|owner| **owner == memory.me).map(|_| true);
+    let non_me_owner = owner.filter(|owner| *owner != memory.me);
+
+    if claimable_memory.my_claim != my_claim || claimable_memory.non_me_owner != non_me_owner {
+        let claimable_memory = memory.claimable_rooms.get_mut(room_name).unwrap();
+        claimable_memory.my_claim = my_claim;
+        claimable_memory.non_me_owner = non_me_owner;
+    }
+}
+
 pub fn find_room_type(
     room_name: &RoomName,
     game_state: &mut GameState,
@@ -753,14 +934,11 @@ pub fn try_create_commune_state(room_name: &RoomName, game_state: &mut GameState
 
 pub fn visualize_sparse_matrix(room_name: &RoomName, game_state: &GameState, matrix: &SparseCostMatrix) {
 
-    let room = game_state.rooms.get(room_name).unwrap();
-
-    let mut room_visual = RoomVisual::new(Some(*room_name));
     for (coord, cost) in matrix.iter() {
         if cost > 0 {
             let x = coord.x.0;
             let y = coord.y.0;
-            room_visual.text(x as f32, y as f32, format!("{}", cost), None);
+            world::draw(*room_name, WorldVisual::Text { x: x as f32, y: y as f32, text: format!("{}", cost) });
         }
     }
 }
\ No newline at end of file
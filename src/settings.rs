@@ -9,6 +9,34 @@ pub struct Settings {
     pub compressed_memory: bool,
     pub allies: HashSet<String>,
     pub log_filter: LevelFilter,
+    /// Ticks a creep fails to move before it finds a new path that prefers tiles without our creeps
+    pub stuck_repath_ticks: u8,
+    /// Ticks a creep fails to move before, and every so many ticks after, it finds a new path
+    /// that treats every creep as impassable
+    pub stuck_block_ticks: u8,
+    pub route_weights: RouteWeights,
+}
+
+/// Costs of travelling through a room when routing between rooms.
+/// Costs are summed per room and a cost of u8::MAX makes a room impassable
+#[derive(Debug, Clone)]
+pub struct RouteWeights {
+    /// Rooms we have never scouted
+    pub unscouted: u8,
+    pub claimable: u8,
+    /// Cardinal highways and intersections
+    pub highway: u8,
+    pub center: u8,
+    pub keeper: u8,
+    /// Added for rooms known to contain portals
+    pub portal: u8,
+    /// Rooms owned by players who are neither us nor allies
+    pub hostile_owner: u8,
+    /// Added for each enemy tower seen in the room
+    pub enemy_tower: u8,
+    /// Added for danger as it was first seen, shrinking to nothing over `danger_decay_ticks`
+    pub danger: u8,
+    pub danger_decay_ticks: u32,
 }
 
 impl Settings {
@@ -21,8 +49,22 @@ impl Settings {
         Settings {
             allies,
             compressed_memory: true,
-            breaking_version: 4,
+            breaking_version: 9,
             log_filter: LevelFilter::Trace,
+            stuck_repath_ticks: 2,
+            stuck_block_ticks: 5,
+            route_weights: RouteWeights {
+                unscouted: 2,
+                claimable: 1,
+                highway: 1,
+                center: 2,
+                keeper: 4,
+                portal: 2,
+                hostile_owner: u8::MAX,
+                enemy_tower: 10,
+                danger: 50,
+                danger_decay_ticks: 1500,
+            },
         }
     }
 }
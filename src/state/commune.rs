@@ -1,6 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
-use screeps::{source, Position, Room, RoomName};
+use screeps::{ObjectId, Position, RoomName, Structure};
 use screeps_utils::sparse_cost_matrix::SparseCostMatrix;
 
 use crate::{constants::structure::SpawnsByActivity, memory::game_memory::GameMemory};
@@ -28,6 +28,14 @@ pub struct CommuneState {
     pub rampart_plans: SparseCostMatrix,
     pub planning_completed: bool,
     pub reserved_positions: HashSet<Position>,
+    /// Spawns and extensions in the order spawning should drain them
+    pub energy_structures: Vec<ObjectId<Structure>>,
+    /// Identifies the spawns and extensions energy_structures was ordered from
+    pub energy_structures_signature: Option<u64>,
+    /// Share of the spawns' time the living creeps and this tick's spawn request args take
+    pub spawn_usage_percent: u32,
+    /// Spawns given a spawn intent this tick
+    pub spawns_used: HashSet<ObjectId<Structure>>,
 }
 
 impl CommuneState {
@@ -47,12 +55,12 @@ impl CommuneState {
 
         let room = game_state.rooms.get(&room_name).unwrap();
 
-        let controller = room.controller().unwrap();
-        let rcl = controller.level();
+        let controller = room.controller.as_ref().unwrap();
+        let rcl = controller.level;
 
         Self {
             name: room_name,
-            spawn_energy_capacity: room.energy_capacity_available(),
+            spawn_energy_capacity: room.energy_capacity_available,
             rcl,
             haul_need: 0,
             min_energy: Some(0),
@@ -69,11 +77,16 @@ impl CommuneState {
             rampart_plans: SparseCostMatrix::new(),
             planning_completed: false,
             reserved_positions: HashSet::new(),
+            energy_structures: Vec::new(),
+            energy_structures_signature: None,
+            spawn_usage_percent: 0,
+            spawns_used: HashSet::new(),
         }
     }
     
     pub fn tick_update(&mut self, room_name: &RoomName) {
         self.spawns_by_activity = None;
+        self.spawns_used.clear();
         
         self.source_harvest_strengths = self.source_harvest_strengths.iter().map(|_| 0).collect();
         self.source_harvest_creeps = self.source_harvest_creeps.iter().map(|_| 0).collect();
@@ -4,29 +4,30 @@ use std::{
 };
 
 use enum_map::EnumMap;
-use screeps::{
-    AccountPowerCreep, Creep, Flag, MaybeHasId, OwnedStructureProperties, Room, RoomName,
-    SharedCreepProperties, StructureType,
-    game::{self, shard},
-};
+use screeps::{RoomName, StructureType};
 
 use super::{
     commune::{self, CommuneState},
     market::MarketState,
     my_creep::MyCreepState,
+    my_power_creep::MyPowerCreepState,
+    path_cache::PathCache,
     room::RoomState,
     segments::Segments,
-    structure::{self, StructuresState},
     tick_intervals::TickIntervals,
 };
 use crate::{
     constants::{creep::CreepRole, general::GeneralResult},
     creep::my_creep::{self, MyCreep},
-    memory::{game_memory::GameMemory, room_memory::CommuneRoomMemory},
+    memory::{game_memory::GameMemory, memory_sections::SectionCache, room_memory::CommuneRoomMemory},
     room::room_ops,
     settings::Settings,
     state::creep::CreepState,
     utils::{self, general::GeneralUtils},
+    world::{
+        self,
+        objects::{FlagData, PowerCreepData, RoomData},
+    },
 };
 
 #[derive(Debug)]
@@ -38,57 +39,62 @@ pub struct GameState {
     pub shard: String,
     pub highest_rcl: u8,
     pub creeps: HashMap<String, MyCreep>,
-    pub account_power_creeps: HashMap<String, AccountPowerCreep>,
-    pub rooms: HashMap<RoomName, Room>,
+    /// Power creeps of ours that are spawned on this shard
+    pub power_creeps: HashMap<String, PowerCreepData>,
+    pub rooms: HashMap<RoomName, RoomData>,
     pub communes: HashSet<RoomName>,
     pub creep_id_index: u32,
     pub terminal_communes: HashSet<RoomName>,
     pub market_state: MarketState,
-    pub structures_state: StructuresState,
     pub room_states: HashMap<RoomName, RoomState>,
     pub commune_states: HashMap<RoomName, CommuneState>,
     pub creep_states: HashMap<String, CreepState>,
     pub my_creep_states: HashMap<String, MyCreepState>,
+    pub my_power_creep_states: HashMap<String, MyPowerCreepState>,
     /// Current scout targets by scout creeps
     pub scout_targets: HashSet<RoomName>,
     pub intervals: TickIntervals,
     pub segments: Segments,
-    pub flags: HashMap<String, Flag>,
+    /// The last encoding of each main memory section
+    pub memory_cache: SectionCache,
+    pub flags: HashMap<String, FlagData>,
+    pub path_cache: PathCache,
 }
 
 impl GameState {
     pub fn new() -> Self {
         Self {
-            init_tick: game::time(),
-            tick: game::time(),
-            shard: shard::name(),
+            init_tick: world::time(),
+            tick: world::time(),
+            shard: world::shard(),
             highest_rcl: 0,
             creeps: HashMap::new(),
-            account_power_creeps: HashMap::new(),
+            power_creeps: HashMap::new(),
             rooms: HashMap::new(),
             communes: HashSet::new(),
             creep_id_index: 0,
             terminal_communes: HashSet::new(),
             market_state: MarketState::new(),
-            structures_state: StructuresState::new(),
             room_states: HashMap::new(),
             commune_states: HashMap::new(),
             creep_states: HashMap::new(),
             my_creep_states: HashMap::new(),
+            my_power_creep_states: HashMap::new(),
             scout_targets: HashSet::new(),
             intervals: TickIntervals::new(),
             segments: Segments::new(),
+            memory_cache: SectionCache::new(),
             flags: HashMap::new(),
+            path_cache: PathCache::new(),
         }
     }
 
     /// Called every possible tick, including the tick when initialized
     pub fn tick_update(&mut self, memory: &mut GameMemory) {
-        self.tick = game::time();
+        self.tick = world::time();
 
         self.update_my_creeps();
-        // TODO
-        // GameStateOps::update_account_power_creeps(self);
+        self.update_my_power_creeps();
         self.update_rooms();
         self.update_communes(memory);
         self.update_creep_id_index();
@@ -99,8 +105,9 @@ impl GameState {
         self.update_rooms_state();
         self.update_communes_state();
         self.update_my_creeps_state();
+        self.update_my_power_creeps_state();
         self.update_creeps_state();
-        self.update_structures_state();
+        self.update_path_cache();
 
         //
 
@@ -111,17 +118,17 @@ impl GameState {
     fn update_my_creeps(&mut self) {
         self.creeps.clear();
 
-        let js_creeps = screeps::game::creeps();
+        for any_creep in world::my_creeps() {
+            let creep_name = any_creep.name.clone();
 
-        for (creep_name, any_creep) in js_creeps.keys().zip(js_creeps.values()) {
-            let Some(creep) = MyCreep::new(&any_creep).ok() else {
+            let Some(creep) = MyCreep::new(any_creep).ok() else {
                 continue;
             };
 
             if !self.my_creep_states.contains_key(&creep_name) {
                 self.my_creep_states.insert(
                     creep_name.clone(),
-                    MyCreepState::new(creep_name.as_str(), &any_creep),
+                    MyCreepState::new(creep_name.as_str(), creep.inner()),
                 );
             } else {
                 let my_creep_state = self.my_creep_states.get_mut(&creep_name).unwrap();
@@ -138,23 +145,30 @@ impl GameState {
         }
     }
 
-    // fn update_account_power_creeps(&mut self) {
-    //     self.account_power_creeps.clear();
+    fn update_my_power_creeps(&mut self) {
+        self.power_creeps.clear();
+
+        for power_creep in world::my_power_creeps() {
+            let power_creep_name = power_creep.name.clone();
 
-    //     let js_creeps = screeps::game::power_creeps();
+            match self.my_power_creep_states.get_mut(&power_creep_name) {
+                Some(my_power_creep_state) => my_power_creep_state.tick_update(&power_creep),
+                None => {
+                    self.my_power_creep_states
+                        .insert(power_creep_name.clone(), MyPowerCreepState::new(&power_creep));
+                }
+            }
 
-    //     for creep_name in js_creeps.keys() {
-    //         let Some(creep) = js_creeps.get(creep_name) else { continue; };
-    //         self.account_power_creeps.insert(creep_name, creep);
-    //     }
-    // }
+            self.power_creeps.insert(power_creep_name, power_creep);
+        }
+    }
 
     fn update_rooms(&mut self) {
         self.rooms.clear();
 
-        let js_rooms = screeps::game::rooms();
+        for room in world::rooms() {
+            let room_name = room.name;
 
-        for (room_name, room) in js_rooms.keys().zip(js_rooms.values()) {
             if !self.room_states.contains_key(&room_name) {
                 self.room_states
                     .insert(room_name, RoomState::new(room_name, self));
@@ -171,11 +185,11 @@ impl GameState {
         for room_name in room_names {
             let room = self.rooms.get(&room_name).unwrap();
 
-            let Some(controller) = room.controller() else {
+            let Some(controller) = &room.controller else {
                 return;
             };
 
-            if !controller.my() {
+            if !controller.my {
                 return;
             }
 
@@ -238,6 +252,17 @@ impl GameState {
         }
     }
 
+    fn update_my_power_creeps_state(&mut self) {
+        // Tick update done in update_my_power_creeps
+
+        if !utils::general::is_tick_interval(self.tick, 100) {
+            return;
+        }
+
+        self.my_power_creep_states
+            .retain(|power_creep_name, _| self.power_creeps.contains_key(power_creep_name));
+    }
+
     fn update_creeps_state(&mut self) {
         if !utils::general::is_tick_interval(self.tick, 100) {
             return;
@@ -251,12 +276,12 @@ impl GameState {
         // }
     }
 
-    fn update_structures_state(&mut self) {
+    fn update_path_cache(&mut self) {
         if !utils::general::is_tick_interval(self.tick, 100) {
             return;
         }
 
-        self.structures_state.active_statuses.clear()
+        self.path_cache.interval_update(self.tick);
     }
 
     fn find_highest_rcl(&mut self) {
@@ -293,10 +318,9 @@ impl GameState {
     }
 
     fn update_flags(&mut self) {
-        let js_flags = game::flags();
-        for (flag_name, flag) in js_flags.keys().zip(js_flags.values()) {
+        for flag in world::flags() {
             
-            self.flags.insert(flag_name, flag);
+            self.flags.insert(flag.name.clone(), flag);
         }
     }
 
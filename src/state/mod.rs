@@ -5,8 +5,10 @@ pub mod market;
 pub mod structure;
 pub mod commune;
 pub mod my_creep;
+pub mod my_power_creep;
 pub mod tick_intervals;
 pub mod simple_allies;
 pub mod segments;
 pub mod stats_segment;
-pub mod remote;
\ No newline at end of file
+pub mod remote;
+pub mod path_cache;
\ No newline at end of file
@@ -1,14 +1,15 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, rc::Rc};
 
 use enum_map::EnumMap;
-use screeps::{Creep, HasPosition, Part, Position, Spawning};
+use screeps::{Part, Position};
 
 use crate::{
     constants::creep::{ActiveCreepPartsByType, CreepParts, CreepPartsByType},
     creep::my_creep::MyCreep,
+    world::objects::CreepData,
 };
 
-use super::game::GameState;
+use super::{game::GameState, path_cache::CachedPath};
 
 pub type MyCreepStates = HashMap<String, MyCreepState>;
 
@@ -19,6 +20,12 @@ pub struct MyCreepState {
     pub spawning: bool,
     pub fatigue: u32,
     pub pos: Position,
+    /// Where the creep was last tick
+    pub last_pos: Position,
+    /// Consecutive ticks the creep asked to move and stayed put. None until counted this global
+    pub stuck_ticks: Option<u8>,
+    /// The position the creep asked to move to last tick
+    pub last_move_request: Option<Position>,
     /// The next position the creep intends to move to
     pub move_request: Option<Position>,
     pub move_options: Option<Vec<Position>>,
@@ -31,15 +38,26 @@ pub struct MyCreepState {
     pub parts_by_type: Option<CreepPartsByType>,
     pub active_parts_by_type: Option<ActiveCreepPartsByType>,
     pub harvest_pos: Option<Position>,
+    /// The shared path the creep last followed
+    pub cached_path: Option<Rc<CachedPath>>,
+    /// Ticks to live below which the creep's replacement should be spawning, found once it gets close
+    pub pre_spawn_ticks: Option<u32>,
+    /// Heading to a spawn to be renewed until its ticks to live are topped up
+    pub renewing: bool,
+    /// Surplus or obsolete, so heading to a spawn to be recycled rather than working
+    pub recycling: bool,
 }
 
 impl MyCreepState {
-    pub fn new(name: &str, creep: &Creep) -> Self {
+    pub fn new(name: &str, creep: &CreepData) -> Self {
         Self {
             cost: None,
-            spawning: creep.spawning(),
-            fatigue: creep.fatigue(),
-            pos: creep.pos(),
+            spawning: creep.spawning,
+            fatigue: creep.fatigue,
+            pos: creep.pos,
+            last_pos: creep.pos,
+            stuck_ticks: None,
+            last_move_request: None,
             move_request: None,
             move_options: None,
             action_pos: None,
@@ -47,15 +65,20 @@ impl MyCreepState {
             parts_by_type: None,
             active_parts_by_type: None,
             harvest_pos: None,
+            cached_path: None,
+            pre_spawn_ticks: None,
+            renewing: false,
+            recycling: false,
         }
     }
 
     pub fn tick_update(&mut self, creep: &MyCreep) {
-        self.spawning = creep.inner().spawning();
-        self.fatigue = creep.inner().fatigue();
-        self.pos = creep.inner().pos();
+        self.spawning = creep.inner().spawning;
+        self.fatigue = creep.inner().fatigue;
+        self.last_pos = self.pos;
+        self.pos = creep.inner().pos;
 
-        self.move_request = None;
+        self.last_move_request = self.move_request.take();
         self.move_options = None;
 
         self.active_parts_by_type = None;
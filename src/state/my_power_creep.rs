@@ -0,0 +1,33 @@
+use screeps::Position;
+
+use crate::world::objects::PowerCreepData;
+
+#[derive(Debug)]
+/// State for power creeps we own that are spawned, which move through traffic like our creeps
+pub struct MyPowerCreepState {
+    pub pos: Position,
+    /// Where the power creep was last tick
+    pub last_pos: Position,
+    /// The next position the power creep intends to move to
+    pub move_request: Option<Position>,
+    /// Where the power creep will end the tick
+    pub action_pos: Option<Position>,
+}
+
+impl MyPowerCreepState {
+    pub fn new(power_creep: &PowerCreepData) -> Self {
+        Self {
+            pos: power_creep.pos,
+            last_pos: power_creep.pos,
+            move_request: None,
+            action_pos: None,
+        }
+    }
+
+    pub fn tick_update(&mut self, power_creep: &PowerCreepData) {
+        self.last_pos = self.pos;
+        self.pos = power_creep.pos;
+
+        self.move_request = None;
+    }
+}
@@ -0,0 +1,81 @@
+use std::{collections::HashMap, rc::Rc};
+
+use screeps::{Position, RoomName};
+
+use crate::{
+    constants::move_costs::PATH_CACHE_LIFETIME,
+    pathfinding::{room_pather_single::PathGoal, CostProfile, PathfindingOpts, RouteProfile},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Creeps share a path when they go to the same goal with the same costs
+pub struct PathCacheKey {
+    pub goal: Position,
+    pub range: u8,
+    pub cost_profile: CostProfile,
+    pub route_profile: RouteProfile,
+    pub allow_outside_origin_room: bool,
+    pub avoid_enemy_attackers: bool,
+    pub avoid_enemy_creeps: bool,
+    pub min_traverse_cost: u8,
+    pub flee: bool,
+}
+
+impl PathCacheKey {
+    pub fn new(goal: &PathGoal, opts: &PathfindingOpts) -> Self {
+        let room_pathfinder_opts = &opts.room_pathfinder_opts;
+
+        Self {
+            goal: goal.pos,
+            range: goal.range,
+            cost_profile: room_pathfinder_opts.cost_profile,
+            route_profile: opts.route_profile,
+            allow_outside_origin_room: room_pathfinder_opts.allow_outside_origin_room,
+            avoid_enemy_attackers: room_pathfinder_opts.avoid_enemy_attackers,
+            avoid_enemy_creeps: opts
+                .avoid_enemy_creeps
+                .unwrap_or(room_pathfinder_opts.avoid_enemy_creeps),
+            min_traverse_cost: room_pathfinder_opts.min_traverse_cost,
+            flee: room_pathfinder_opts.flee,
+        }
+    }
+
+    /// Whether paths for the key depend on where enemies are this tick
+    pub fn avoids_enemies(&self) -> bool {
+        self.avoid_enemy_attackers || self.avoid_enemy_creeps
+    }
+}
+
+#[derive(Debug)]
+pub struct CachedPath {
+    pub key: PathCacheKey,
+    /// Starts where the path was first searched from
+    pub path: Vec<Position>,
+    /// Index of each position in the path, so a creep anywhere along it can follow the rest
+    pub indexes: HashMap<Position, usize>,
+    /// Move costs signature of each room the path crosses, as of when it was found
+    pub signatures: Vec<(RoomName, u64)>,
+    pub created: u32,
+}
+
+#[derive(Debug)]
+/// Paths creeps follow by reference instead of each searching and storing their own
+pub struct PathCache {
+    pub paths: HashMap<PathCacheKey, Vec<Rc<CachedPath>>>,
+}
+
+impl PathCache {
+    pub fn new() -> Self {
+        Self {
+            paths: HashMap::new(),
+        }
+    }
+
+    pub fn interval_update(&mut self, tick: u32) {
+        for cached_paths in self.paths.values_mut() {
+            cached_paths.retain(|cached_path| tick - cached_path.created < PATH_CACHE_LIFETIME);
+        }
+
+        self.paths.retain(|_, cached_paths| !cached_paths.is_empty());
+    }
+}
@@ -2,10 +2,8 @@ use std::collections::HashMap;
 
 use enum_map::{EnumMap, enum_map};
 use screeps::{
-    ConstructionSite, LocalRoomTerrain, ObjectId, Path, Position, Room, RoomName, Source,
-    Structure, StructureContainer, StructureController, StructureFactory, StructureNuker,
-    StructureObject, StructurePowerSpawn, StructureProperties, StructureStorage, StructureTerminal,
-    StructureType, find, game::map::RoomStatus,
+    LocalRoomTerrain, ObjectId, Path, Position, RoomName, Source, StructureType,
+    game::map::RoomStatus,
 };
 use screeps_utils::sparse_cost_matrix::SparseCostMatrix;
 
@@ -17,9 +15,10 @@ use crate::{
         structure::{OrganizedStructures, SpawnsByActivity},
     },
     creep::my_creep::MyCreep,
+    world::objects::{ConstructionSiteData, ControllerData, SourceData, StructureData},
 };
 
-use super::game::GameState;
+use super::{game::GameState, structure::StructureActiveStatuses};
 
 pub type RoomStates = HashMap<RoomName, RoomState>;
 
@@ -30,27 +29,34 @@ pub struct RoomState {
     pub terrain: Option<LocalRoomTerrain>,
     pub sparse_terrain: Option<SparseCostMatrix>,
     pub default_move_ops: Option<SparseCostMatrix>,
+    /// The move costs signature default_move_ops was built with
+    pub default_move_ops_signature: u64,
+    /// Hash of what default_move_ops is built from, for this tick
+    pub move_costs_signature: Option<u64>,
     pub enemy_threat_positions: Option<SparseCostMatrix>,
     pub last_seen: u32,
     pub expired: bool,
 
     // Structures
-    pub structures: Option<Vec<StructureObject>>,
+    pub structures: Option<Vec<StructureData>>,
     pub structures_by_type: Option<OrganizedStructures>,
-    pub storage: Option<StructureStorage>,
-    pub terminal: Option<StructureTerminal>,
-    pub controller: Option<StructureController>,
+    pub storage: Option<StructureData>,
+    pub terminal: Option<StructureData>,
+    pub controller: Option<ControllerData>,
+    /// Whether each structure asked about this tick is active
+    pub active_statuses: StructureActiveStatuses,
 
-    pub my_construction_sites: Option<Vec<ConstructionSite>>,
+    pub my_construction_sites: Option<Vec<ConstructionSiteData>>,
     pub not_my_construction_sites: Option<NotMyConstructionSites>,
     pub commune_plan: Option<CommunePlan>,
 
     // Sources
-    pub sources: Option<Vec<Source>>,
+    pub sources: Option<Vec<SourceData>>,
     pub harvest_positions: Option<Vec<Vec<Position>>>,
 
     // Creeps
     pub my_creeps: Vec<String>,
+    pub my_power_creeps: Vec<String>,
     pub creeps_by_role: EnumMap<CreepRole, Vec<String>>,
     pub not_my_creeps: Option<NotMyCreeps>,
 }
@@ -63,6 +69,8 @@ impl RoomState {
             terrain: None,
             sparse_terrain: None,
             default_move_ops: None,
+            default_move_ops_signature: 0,
+            move_costs_signature: None,
             enemy_threat_positions: None,
             last_seen: game_state.tick,
             expired: false,
@@ -71,12 +79,14 @@ impl RoomState {
             storage: None,
             terminal: None,
             controller: None,
+            active_statuses: HashMap::new(),
             my_construction_sites: None,
             not_my_construction_sites: None,
             commune_plan: None,
             sources: None,
             harvest_positions: None,
             my_creeps: Vec::new(),
+            my_power_creeps: Vec::new(),
             not_my_creeps: None,
             creeps_by_role: creeps_by_role(),
         }
@@ -104,6 +114,7 @@ impl RoomState {
         self.not_my_construction_sites = None;
 
         self.my_creeps = Vec::new();
+        self.my_power_creeps = Vec::new();
         self.creeps_by_role = creeps_by_role();
         self.not_my_creeps = None;
 
@@ -112,8 +123,10 @@ impl RoomState {
         self.storage = None;
         self.terminal = None;
         self.controller = None;
+        self.active_statuses.clear();
 
         self.enemy_threat_positions = None;
+        self.move_costs_signature = None;
     }
 
     pub fn interval_update(&mut self, room_name: &RoomName) {
@@ -125,8 +138,8 @@ impl RoomState {
 
 #[derive(Debug)]
 pub struct NotMyConstructionSites {
-    pub ally: Vec<ConstructionSite>,
-    pub enemy: Vec<ConstructionSite>,
+    pub ally: Vec<ConstructionSiteData>,
+    pub enemy: Vec<ConstructionSiteData>,
 }
 
 impl NotMyConstructionSites {
@@ -178,7 +191,7 @@ pub struct CommunePlanAttemptData {
     pub source_harvest_positions: Option<Vec<Position>>,
     pub source_paths: Option<Vec<Vec<Position>>>,
     pub source_structure_positions: Option<Vec<Position>>,
-    pub commune_sources: Option<Vec<Source>>,
+    pub commune_sources: Option<Vec<SourceData>>,
     pub unprotected_sources: Option<u32>,
     /// Mineral
     pub mineral_path: Option<Vec<Position>>,
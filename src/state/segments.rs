@@ -1,9 +1,18 @@
+use enum_map::EnumMap;
+
+use crate::memory::memory_segments::ColdSection;
+
 use super::{simple_allies::SimpleAlliesSegment, stats_segment::StatsSegment};
 
 #[derive(Debug)]
 pub struct Segments {
     pub allies: SimpleAlliesSegment,
     pub stats: StatsSegment,
+    pub memory: EnumMap<ColdSection, ColdSectionState>,
+    /// The segments we last asked the game to make active
+    pub active_memory_segments: Vec<u8>,
+    /// Memory segments already written this tick, so later writers stay within the game's limit
+    pub memory_segment_writes: usize,
 }
 
 impl Segments {
@@ -11,6 +20,17 @@ impl Segments {
         Self {
             allies: SimpleAlliesSegment::new(),
             stats: StatsSegment::new(),
+            memory: EnumMap::default(),
+            active_memory_segments: Vec::new(),
+            memory_segment_writes: 0,
         }
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ColdSectionState {
+    /// Whether the section has been read from its segments since the global reset
+    pub loaded: bool,
+    /// The generation of the section when it was last written to its segments
+    pub written_generation: Option<u32>,
+}
@@ -22,6 +22,14 @@ pub struct StatsSegment {
     pub game_time: u32,
     pub energy_harvested: u32,
     pub intents: u32,
+    /// Creeps that found a new path this tick after failing to move for too long
+    pub stuck_repaths: u32,
+    /// Characters in the main memory string as of the last write
+    pub memory_length: u32,
+    /// CPU spent encoding the main memory on the last write
+    pub memory_encode_cpu: f32,
+    /// Keyed by the name of the memory field, including the sections stored in segments
+    pub memory_sections: HashMap<String, MemorySectionStats>,
 }
 
 impl StatsSegment {
@@ -32,6 +40,22 @@ impl StatsSegment {
     }
 }
 
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct MemorySectionStats {
+    /// Size of the section's encoding before it is turned into a string
+    pub bytes: u32,
+    /// CPU spent encoding the section on the last write, 0 if it was unchanged
+    pub encode_cpu: f32,
+}
+
+impl MemorySectionStats {
+    pub fn new() -> Self {
+        Self {
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct RemoteSegmentStats {
     pub energy_in_harvest: u32,
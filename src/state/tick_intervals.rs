@@ -4,6 +4,7 @@ pub struct TickIntervals {
     pub decay_player_data: u32,
     pub write_stats: u32,
     pub write_memory: u32,
+    pub memory_snapshot: u32,
 }
 
 impl TickIntervals {
@@ -15,6 +16,7 @@ impl TickIntervals {
             decay_player_data: rng.u32(300..500),
             write_stats: 5,
             write_memory: 1,// rng.u32(10..20),
+            memory_snapshot: rng.u32(200..300),
         }
     }
 }
\ No newline at end of file
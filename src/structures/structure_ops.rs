@@ -1,21 +1,15 @@
-use screeps::{HasId, Structure};
+use crate::{state::game::GameState, world::{self, objects::StructureData}};
 
-use crate::state::game::GameState;
+/// Whether the structure is active, asking the game at most once a tick per structure
+pub fn is_active(structure: &StructureData, game_state: &mut GameState) -> bool {
+    let room_state = game_state.get_or_create_room_state_mut(&structure.pos.room_name());
 
-pub fn is_active(structure: &Structure, game_state: &mut GameState) -> bool {
-    if let Some(is_active) = game_state
-        .structures_state
-        .active_statuses
-        .get(&structure.id())
-    {
+    if let Some(is_active) = room_state.active_statuses.get(&structure.id) {
         return *is_active;
     }
 
-    let is_active = structure.is_active();
+    let is_active = world::structure_is_active(structure.id);
 
-    game_state
-        .structures_state
-        .active_statuses
-        .insert(structure.id(), is_active);
+    room_state.active_statuses.insert(structure.id, is_active);
     is_active
 }
@@ -2,8 +2,7 @@ use core::fmt;
 use std::error::Error;
 
 use screeps::{
-    find, Creep, HasHits, HasPosition, ObjectId, Position, Room, RoomName, RoomPosition,
-    StructureTower, HEAL_POWER, RAMPART_DECAY_AMOUNT, RAMPART_DECAY_TIME,
+    ObjectId, Position, RoomName, HEAL_POWER, RAMPART_DECAY_AMOUNT, RAMPART_DECAY_TIME,
 };
 
 use crate::{
@@ -11,6 +10,7 @@ use crate::{
     room::room_ops,
     state::game::GameState,
     utils::{self, general::{is_tick_interval, GeneralUtils}},
+    world::{self, objects::{CreepData, StructureData}},
 };
 
 pub fn run_towers(room_name: &RoomName, game_state: &mut GameState, memory: &mut GameMemory) {
@@ -31,7 +31,7 @@ fn towers_creep_actions(
     room_name: &RoomName,
     game_state: &mut GameState,
     memory: &GameMemory,
-    towers: &mut Vec<StructureTower>,
+    towers: &mut Vec<StructureData>,
 ) -> TowersResult {
     if tower_my_creep_actions(room_name, game_state, memory, towers) == TowersResult::Stop {
         return TowersResult::Stop;
@@ -47,7 +47,7 @@ fn tower_my_creep_actions(
     room_name: &RoomName,
     game_state: &mut GameState,
     memory: &GameMemory,
-    towers: &mut Vec<StructureTower>,
+    towers: &mut Vec<StructureData>,
 ) -> TowersResult {
     let room_state = game_state.room_states.get_mut(room_name).unwrap();
     let mut creep_names = &mut room_state.my_creeps.clone();
@@ -57,7 +57,7 @@ fn tower_my_creep_actions(
     creep_names.retain(|creep_name| {
         let my_creep = game_state.creeps.get(creep_name).unwrap();
 
-        my_creep.inner().hits() < my_creep.inner().hits_max()
+        my_creep.inner().hits < my_creep.inner().hits_max
     });
 
     if creep_names.is_empty() {
@@ -67,7 +67,9 @@ fn tower_my_creep_actions(
     towers.retain(|tower| {
 
         let my_creep = game_state.creeps.get(&creep_names[0]).unwrap();
-        tower.heal(my_creep.inner());
+        if let Some(creep_id) = my_creep.inner().id {
+            let _ = world::tower_heal(tower.id, creep_id);
+        }
 
         false
     });
@@ -82,7 +84,7 @@ fn tower_not_my_creep_actions(
     room_name: &RoomName,
     game_state: &mut GameState,
     memory: &GameMemory,
-    towers: &mut Vec<StructureTower>,
+    towers: &mut Vec<StructureData>,
 ) -> TowersResult {
     let mut creeps = room_ops::not_my_creeps(room_name, game_state, memory);
 
@@ -104,9 +106,9 @@ fn towers_attack_enemies(
     room_name: &RoomName,
     game_state: &mut GameState,
     memory: &GameMemory,
-    towers: &mut Vec<StructureTower>,
+    towers: &mut Vec<StructureData>,
     // Will need to mutate in the future to decide who to attack
-    creeps: &mut Vec<Creep>,
+    creeps: &mut Vec<CreepData>,
 ) -> TowersResult {
     if creeps.is_empty() {
         return TowersResult::Continue;
@@ -116,7 +118,7 @@ fn towers_attack_enemies(
 
     // Need to consider towers that don't have enough energy
     creeps.retain(|creep| {
-        let max_potential_damage = find_towers_attack_power(towers, &creep.pos());
+        let max_potential_damage = find_towers_attack_power(towers, &creep.pos);
         let max_potential_heal = HEAL_POWER as u8 * creep.get_active_bodyparts(screeps::Part::Heal);
 
         // If we can damage more than they can heal plus a little, keep them as a target
@@ -128,7 +130,9 @@ fn towers_attack_enemies(
     }
 
     towers.retain(|tower| {
-        tower.attack(&creeps[0]);
+        if let Some(creep_id) = creeps[0].id {
+            let _ = world::tower_attack(tower.id, creep_id);
+        }
 
         false
     });
@@ -139,11 +143,11 @@ fn towers_attack_enemies(
     }
 }
 
-pub fn find_towers_attack_power(towers: &[StructureTower], target_pos: &Position) -> u32 {
+pub fn find_towers_attack_power(towers: &[StructureData], target_pos: &Position) -> u32 {
     let mut total_attack_power = 0;
 
     for tower in towers {
-        let range = utils::general::pos_range(&tower.pos(), target_pos);
+        let range = utils::general::pos_range(&tower.pos, target_pos);
 
         total_attack_power += screeps_utils::math::tower_attack_power_at_range(range as u8)
     }
@@ -155,17 +159,19 @@ fn towers_heal_ally_creeps(
     room_name: &RoomName,
     game_state: &mut GameState,
     memory: &GameMemory,
-    towers: &mut Vec<StructureTower>,
-    creeps: &mut Vec<Creep>,
+    towers: &mut Vec<StructureData>,
+    creeps: &mut Vec<CreepData>,
 ) -> TowersResult {
-    creeps.retain(|creep| creep.hits() < creep.hits_max());
+    creeps.retain(|creep| creep.hits < creep.hits_max);
 
     if creeps.is_empty() {
         return TowersResult::Continue;
     }
 
     towers.retain(|tower| {
-        tower.heal(&creeps[0]);
+        if let Some(creep_id) = creeps[0].id {
+            let _ = world::tower_heal(tower.id, creep_id);
+        }
 
         false
     });
@@ -182,7 +188,7 @@ fn towers_structure_actions(
     room_name: &RoomName,
     game_state: &mut GameState,
     memory: &GameMemory,
-    towers: &mut Vec<StructureTower>,
+    towers: &mut Vec<StructureData>,
 ) -> TowersResult {
     if (is_tick_interval(game_state.tick, RAMPART_DECAY_TIME / 2)) {
         return TowersResult::Continue;
@@ -199,7 +205,7 @@ fn towers_repair_ramparts(
     room_name: &RoomName,
     game_state: &mut GameState,
     memory: &GameMemory,
-    towers: &mut Vec<StructureTower>,
+    towers: &mut Vec<StructureData>,
 ) -> TowersResult {
     let mut ramparts = room_ops::structures_by_type(room_name, game_state).rampart.clone();
 
@@ -207,14 +213,14 @@ fn towers_repair_ramparts(
         return TowersResult::Continue;
     }
 
-    ramparts.retain(|rampart| rampart.hits() <= RAMPART_DECAY_AMOUNT * 2);
+    ramparts.retain(|rampart| rampart.hits <= RAMPART_DECAY_AMOUNT * 2);
 
     if ramparts.is_empty() {
         return TowersResult::Continue;
     }
 
     towers.retain(|tower| {
-        tower.repair(&ramparts[0]);
+        let _ = world::tower_repair(tower.id, ramparts[0].id);
 
         false
     });
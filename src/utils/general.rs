@@ -1,10 +1,12 @@
 use js_sys::Math::max;
-use screeps::{game, OwnedStructureProperties, Position, RoomName};
+use screeps::{Position, RoomName};
+
+use crate::world;
 
 pub struct GeneralUtils;
 
 pub fn is_tick_interval(tick: u32, interval: u32) -> bool {
-    let tick = game::time();
+    let tick = world::time();
 
     tick % interval == 0
 }
@@ -66,23 +68,21 @@ pub fn for_adjacent_positions(position: Position, operation: &dyn Fn(&Position))
 }
 
 pub fn me() -> Result<String, ()> {
-    let js_rooms = screeps::game::rooms();
-
-    for (room_name, room) in js_rooms.keys().zip(js_rooms.values()) {
+    for room in world::rooms() {
 
-        let Some(controller) = room.controller() else {
+        let Some(controller) = room.controller else {
             continue;
         };
 
-        if !controller.my() {
+        if !controller.my {
             continue;
         };
 
-        let Some(owner) = controller.owner() else {
+        let Some(owner) = controller.owner else {
             continue;
         };
 
-        return Ok(owner.username());
+        return Ok(owner);
     }
 
     Err(())
@@ -111,4 +111,17 @@ pub fn is_xy_in_room(x: u8, y: u8) -> bool {
 pub fn is_xy_exit(x: u8, y: u8) -> bool {
 
     x == 0 || y == 0 || x == ROOM_SIZE - 1 || y == ROOM_SIZE - 1
-}
\ No newline at end of file
+}
+/// Where a creep on an exit ends up in the adjacent room
+pub fn exit_destination(pos: &Position) -> Option<Position> {
+
+    let offset = match (pos.x().u8(), pos.y().u8()) {
+        (0, _) => (-1, 0),
+        (_, 0) => (0, -1),
+        (x, _) if x == ROOM_SIZE - 1 => (1, 0),
+        (_, y) if y == ROOM_SIZE - 1 => (0, 1),
+        _ => return None,
+    };
+
+    pos.checked_add(offset).ok()
+}
@@ -1,6 +1,380 @@
-// use screeps::Position;
+use screeps::{Position, ROOM_SIZE};
+use serde::{Deserialize, Serialize};
 
-// pub fn serialize_pos<S>(pos: &Position, serializer: S) -> Result<S::Ok, S::Error> {
+use crate::constants::general::GeneralError;
 
+/// World offsets of each step, in the order of `constants::general::DIRECTIONS`
+const DIRECTION_OFFSETS: [(i32, i32); 8] = [
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+    (-1, 0),
+    (-1, -1),
+];
+const DIRECTION_BITS: usize = 3;
 
-// }
\ No newline at end of file
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+/// A path stored as origins followed by a stream of directions at 3 bits per step.
+/// A new origin starts wherever the path is not contiguous, such as through a portal
+pub struct PackedPath {
+    /// The packed origin position and how many steps follow it
+    runs: Vec<(u32, u16)>,
+    directions: Vec<u8>,
+}
+
+impl PackedPath {
+    pub fn pack(path: &[Position]) -> Self {
+        let mut packed = Self::default();
+        let mut steps: usize = 0;
+        let mut previous: Option<Position> = None;
+
+        for pos in path {
+            let direction = previous.and_then(|previous| step_index(previous, *pos));
+
+            match (direction, packed.runs.last_mut()) {
+                (Some(direction), Some(run)) if run.1 < u16::MAX => {
+                    write_bits(&mut packed.directions, steps * DIRECTION_BITS, direction);
+                    run.1 += 1;
+                    steps += 1;
+                }
+                _ => packed.runs.push((pos.packed_repr(), 0)),
+            }
+
+            previous = Some(*pos);
+        }
+
+        packed
+    }
+
+    pub fn unpack(&self) -> Result<Vec<Position>, GeneralError> {
+        let mut path = Vec::new();
+        let mut steps: usize = 0;
+
+        for (origin, run_steps) in &self.runs {
+            // Room coordinates fill their bytes, but x and y must be inside the room
+            if (origin >> 8 & 0xFF) as usize >= ROOM_SIZE as usize || (origin & 0xFF) as usize >= ROOM_SIZE as usize {
+                return Err(GeneralError::Fail);
+            }
+
+            let mut pos = Position::from_packed(*origin);
+            path.push(pos);
+
+            for _ in 0..*run_steps {
+                if (steps + 1) * DIRECTION_BITS > self.directions.len() * 8 {
+                    return Err(GeneralError::Fail);
+                }
+
+                let offset = DIRECTION_OFFSETS[read_bits(&self.directions, steps * DIRECTION_BITS) as usize];
+                let Ok(next_pos) = pos.checked_add(offset) else {
+                    return Err(GeneralError::Fail);
+                };
+                pos = next_pos;
+
+                path.push(pos);
+                steps += 1;
+            }
+        }
+
+        Ok(path)
+    }
+}
+
+/// The index of the direction that moves `from` onto `to`, if they are adjacent
+fn step_index(from: Position, to: Position) -> Option<u8> {
+    let offset = (to.world_x() - from.world_x(), to.world_y() - from.world_y());
+
+    DIRECTION_OFFSETS
+        .iter()
+        .position(|direction_offset| *direction_offset == offset)
+        .map(|index| index as u8)
+}
+
+fn write_bits(bytes: &mut Vec<u8>, bit: usize, value: u8) {
+    for i in 0..DIRECTION_BITS {
+        let index = bit + i;
+        if index / 8 >= bytes.len() {
+            bytes.push(0);
+        }
+
+        if value >> i & 1 == 1 {
+            bytes[index / 8] |= 1 << (index % 8);
+        }
+    }
+}
+
+fn read_bits(bytes: &[u8], bit: usize) -> u8 {
+    let mut value = 0;
+
+    for i in 0..DIRECTION_BITS {
+        let index = bit + i;
+        value |= (bytes[index / 8] >> (index % 8) & 1) << i;
+    }
+
+    value
+}
+
+#[derive(Serialize, Deserialize, Default, Debug, PartialEq)]
+/// Positions stored as room-local coordinates, with the room recorded once for each run of positions that share it
+pub struct PackedRoomPositions {
+    /// The packed room and how many coordinates belong to it
+    runs: Vec<(u16, u16)>,
+    /// Each coordinate as `x * ROOM_SIZE + y`
+    xys: Vec<u16>,
+}
+
+impl PackedRoomPositions {
+    pub fn pack(positions: &[Position]) -> Self {
+        let mut packed = Self::default();
+
+        for pos in positions {
+            let room = (pos.packed_repr() >> 16) as u16;
+
+            match packed.runs.last_mut() {
+                Some(run) if run.0 == room && run.1 < u16::MAX => run.1 += 1,
+                _ => packed.runs.push((room, 1)),
+            }
+
+            packed.xys.push(pos.x().u8() as u16 * ROOM_SIZE as u16 + pos.y().u8() as u16);
+        }
+
+        packed
+    }
+
+    pub fn unpack(&self) -> Result<Vec<Position>, GeneralError> {
+        let mut positions = Vec::new();
+        let mut xys = self.xys.iter();
+
+        for (room, count) in &self.runs {
+            for _ in 0..*count {
+                let Some(xy) = xys.next() else {
+                    return Err(GeneralError::Fail);
+                };
+
+                let (x, y) = (*xy / ROOM_SIZE as u16, *xy % ROOM_SIZE as u16);
+                if x >= ROOM_SIZE as u16 {
+                    return Err(GeneralError::Fail);
+                }
+
+                positions.push(Position::from_packed((*room as u32) << 16 | (x as u32) << 8 | y as u32));
+            }
+        }
+
+        Ok(positions)
+    }
+}
+
+// Modules for `#[serde(with = "...")]` that keep the fields' types while storing them packed
+
+pub mod path_packed {
+    use screeps::Position;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::PackedPath;
+
+    pub fn serialize<S: Serializer>(path: &[Position], serializer: S) -> Result<S::Ok, S::Error> {
+        PackedPath::pack(path).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Position>, D::Error> {
+        PackedPath::deserialize(deserializer)?
+            .unpack()
+            .map_err(|_| D::Error::custom("invalid packed path"))
+    }
+}
+
+pub mod option_path_packed {
+    use screeps::Position;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::PackedPath;
+
+    pub fn serialize<S: Serializer>(path: &Option<Vec<Position>>, serializer: S) -> Result<S::Ok, S::Error> {
+        path.as_deref().map(PackedPath::pack).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<Position>>, D::Error> {
+        Option::<PackedPath>::deserialize(deserializer)?
+            .map(|packed| packed.unpack())
+            .transpose()
+            .map_err(|_| D::Error::custom("invalid packed path"))
+    }
+}
+
+pub mod paths_packed {
+    use screeps::Position;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::PackedPath;
+
+    pub fn serialize<S: Serializer>(paths: &[Vec<Position>], serializer: S) -> Result<S::Ok, S::Error> {
+        paths
+            .iter()
+            .map(|path| PackedPath::pack(path))
+            .collect::<Vec<PackedPath>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<Position>>, D::Error> {
+        Vec::<PackedPath>::deserialize(deserializer)?
+            .iter()
+            .map(|packed| packed.unpack())
+            .collect::<Result<_, _>>()
+            .map_err(|_| D::Error::custom("invalid packed path"))
+    }
+}
+
+pub mod nested_room_positions_packed {
+    use screeps::Position;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    use super::PackedRoomPositions;
+
+    pub fn serialize<S: Serializer>(positions: &[Vec<Position>], serializer: S) -> Result<S::Ok, S::Error> {
+        positions
+            .iter()
+            .map(|positions| PackedRoomPositions::pack(positions))
+            .collect::<Vec<PackedRoomPositions>>()
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Vec<Position>>, D::Error> {
+        Vec::<PackedRoomPositions>::deserialize(deserializer)?
+            .iter()
+            .map(|packed| packed.unpack())
+            .collect::<Result<_, _>>()
+            .map_err(|_| D::Error::custom("invalid packed room positions"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use screeps::{Position, RoomCoordinate, RoomName};
+    use serde::{Deserialize, Serialize};
+
+    use super::{PackedPath, PackedRoomPositions};
+
+    fn pos(x: u8, y: u8, room: &str) -> Position {
+        Position::new(
+            RoomCoordinate::new(x).unwrap(),
+            RoomCoordinate::new(y).unwrap(),
+            RoomName::new(room).unwrap(),
+        )
+    }
+
+    #[test]
+    fn paths_round_trip() {
+        let path = vec![
+            pos(10, 10, "W1N1"),
+            pos(11, 9, "W1N1"),
+            pos(12, 9, "W1N1"),
+            pos(12, 10, "W1N1"),
+            pos(11, 11, "W1N1"),
+            pos(10, 11, "W1N1"),
+            pos(9, 10, "W1N1"),
+            pos(9, 9, "W1N1"),
+            pos(10, 8, "W1N1"),
+        ];
+
+        let packed = PackedPath::pack(&path);
+
+        assert_eq!(packed.runs.len(), 1);
+        // 8 steps at 3 bits each
+        assert_eq!(packed.directions.len(), 3);
+        assert_eq!(packed.unpack().unwrap(), path);
+    }
+
+    #[test]
+    fn paths_cross_room_edges() {
+        let path = vec![pos(48, 25, "W1N1"), pos(49, 25, "W1N1"), pos(0, 25, "W0N1"), pos(1, 26, "W0N1")];
+
+        let packed = PackedPath::pack(&path);
+
+        assert_eq!(packed.runs.len(), 1);
+        assert_eq!(packed.unpack().unwrap(), path);
+    }
+
+    #[test]
+    fn gaps_start_a_new_run() {
+        let path = vec![pos(10, 10, "W1N1"), pos(11, 10, "W1N1"), pos(25, 25, "W5N5"), pos(25, 26, "W5N5"), pos(25, 26, "W5N5")];
+
+        let packed = PackedPath::pack(&path);
+
+        // Repeating a position is not a step either
+        assert_eq!(packed.runs.len(), 3);
+        assert_eq!(packed.unpack().unwrap(), path);
+    }
+
+    #[test]
+    fn empty_paths_round_trip() {
+        let packed = PackedPath::pack(&[]);
+
+        assert!(packed.unpack().unwrap().is_empty());
+    }
+
+    #[test]
+    fn truncated_paths_are_rejected() {
+        let mut packed = PackedPath::pack(&[pos(10, 10, "W1N1"), pos(11, 10, "W1N1"), pos(12, 10, "W1N1")]);
+        packed.directions.clear();
+
+        assert!(packed.unpack().is_err());
+    }
+
+    #[test]
+    fn malformed_paths_are_rejected() {
+        // Stepping north west from the corner of the world
+        let corner = pos(0, 0, "W127N127");
+        let mut packed = PackedPath::pack(&[corner, pos(1, 0, "W127N127")]);
+        packed.directions = vec![7];
+
+        assert!(packed.unpack().is_err());
+
+        let json = serde_json::json!({ "path": packed, "paths": [], "positions": [] }).to_string();
+        assert!(serde_json::from_str::<Fields>(&json).is_err());
+
+        // An origin outside its room
+        let packed = PackedPath {
+            runs: vec![(corner.packed_repr() | 60 << 8, 0)],
+            directions: Vec::new(),
+        };
+
+        assert!(packed.unpack().is_err());
+    }
+
+    #[test]
+    fn room_positions_round_trip() {
+        let positions = vec![pos(0, 0, "W1N1"), pos(49, 49, "W1N1"), pos(7, 30, "E3S4"), pos(8, 30, "W1N1")];
+
+        let packed = PackedRoomPositions::pack(&positions);
+
+        assert_eq!(packed.runs.len(), 3);
+        assert_eq!(packed.unpack().unwrap(), positions);
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Fields {
+        #[serde(with = "super::option_path_packed")]
+        path: Option<Vec<Position>>,
+        #[serde(with = "super::paths_packed")]
+        paths: Vec<Vec<Position>>,
+        #[serde(with = "super::nested_room_positions_packed")]
+        positions: Vec<Vec<Position>>,
+    }
+
+    #[test]
+    fn fields_round_trip_through_bitcode_and_json() {
+        let fields = Fields {
+            path: Some(vec![pos(1, 1, "W1N1"), pos(2, 2, "W1N1")]),
+            paths: vec![vec![pos(3, 3, "W1N1"), pos(3, 4, "W1N1")], vec![]],
+            positions: vec![vec![pos(20, 21, "W1N1"), pos(21, 21, "W1N1")]],
+        };
+
+        let bits = bitcode::serialize(&fields).unwrap();
+        assert_eq!(bitcode::deserialize::<Fields>(&bits).unwrap(), fields);
+
+        let json = serde_json::to_string(&fields).unwrap();
+        assert_eq!(serde_json::from_str::<Fields>(&json).unwrap(), fields);
+    }
+}
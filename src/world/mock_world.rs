@@ -0,0 +1,952 @@
+use std::{any::Any, collections::HashMap, str::FromStr};
+
+use screeps::{
+    game::map::RoomStatus, ConstructionSite, Creep, Direction, ErrorCode, ExitDirection,
+    LocalRoomTerrain, ObjectId, Part, Position, RawObjectId, ResourceType, RoomCoordinate,
+    RoomName, Source, Structure, StructureController, StructureType, Terrain, CREEP_LIFE_TIME,
+};
+
+use crate::{memory::game_memory::GameMemory, room::room_ops, settings::Settings, state::game::GameState};
+
+use super::{
+    objects::{
+        BodyPartData, ConstructionSiteData, ControlLevels, ControllerData, CreepData, FlagData,
+        ForeignSegmentData, MineralData, PowerCreepData, RoomData, SourceData, SpawnOpts, StoreData,
+        StructureData, WorldVisual,
+    },
+    World,
+};
+
+pub const MOCK_USERNAME: &str = "mock_player";
+
+/// An intent accepted by the mock world. They are only recorded, it is up to the test to act on them
+#[derive(Debug, Clone)]
+pub enum Intent {
+    Move {
+        creep_name: String,
+        direction: Direction,
+    },
+    PowerCreepMove {
+        power_creep_name: String,
+        direction: Direction,
+    },
+    Harvest {
+        creep_name: String,
+        source: ObjectId<Source>,
+    },
+    Suicide {
+        creep_name: String,
+    },
+    SpawnCreep {
+        spawn: ObjectId<Structure>,
+        body: Vec<Part>,
+        name: String,
+        energy_structures: Option<Vec<ObjectId<Structure>>>,
+        directions: Option<Vec<Direction>>,
+    },
+    RenewCreep {
+        spawn: ObjectId<Structure>,
+        creep_name: String,
+    },
+    RecycleCreep {
+        spawn: ObjectId<Structure>,
+        creep_name: String,
+    },
+    TowerAttack {
+        tower: ObjectId<Structure>,
+        target: ObjectId<Creep>,
+    },
+    TowerHeal {
+        tower: ObjectId<Structure>,
+        target: ObjectId<Creep>,
+    },
+    TowerRepair {
+        tower: ObjectId<Structure>,
+        target: ObjectId<Structure>,
+    },
+    ActivateSafeMode {
+        controller: ObjectId<StructureController>,
+    },
+    RemoveConstructionSite {
+        id: ObjectId<ConstructionSite>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct MockRoom {
+    pub name: RoomName,
+    /// Rooms without vision still have terrain and a status, but return no objects
+    pub visible: bool,
+    pub status: RoomStatus,
+    /// Terrain bits by y * 50 + x, as the game packs them
+    pub terrain: Box<[u8; 2500]>,
+    pub controller: Option<ControllerData>,
+    pub structures: Vec<StructureData>,
+    pub construction_sites: Vec<ConstructionSiteData>,
+    pub sources: Vec<SourceData>,
+    pub minerals: Vec<MineralData>,
+    pub hostile_creeps: Vec<CreepData>,
+}
+
+impl MockRoom {
+    pub fn new(name: RoomName) -> Self {
+        Self {
+            name,
+            visible: true,
+            status: RoomStatus::Normal,
+            terrain: Box::new([0; 2500]),
+            controller: None,
+            structures: Vec::new(),
+            construction_sites: Vec::new(),
+            sources: Vec::new(),
+            minerals: Vec::new(),
+            hostile_creeps: Vec::new(),
+        }
+    }
+
+    pub fn set_terrain(&mut self, x: u8, y: u8, terrain: Terrain) {
+        self.terrain[y as usize * 50 + x as usize] = match terrain {
+            Terrain::Plain => 0,
+            Terrain::Wall => 1,
+            Terrain::Swamp => 2,
+        };
+    }
+
+    pub fn get_terrain(&self, x: u8, y: u8) -> Terrain {
+        match self.terrain[y as usize * 50 + x as usize] {
+            0 => Terrain::Plain,
+            2 => Terrain::Swamp,
+            _ => Terrain::Wall,
+        }
+    }
+
+    /// Energy in the room's spawns and extensions
+    pub fn energy_available(&self) -> u32 {
+        self.spawning_stores()
+            .map(|store| store.get_used_capacity(Some(ResourceType::Energy)))
+            .sum()
+    }
+
+    pub fn energy_capacity_available(&self) -> u32 {
+        self.spawning_stores()
+            .map(|store| store.get_capacity(Some(ResourceType::Energy)))
+            .sum()
+    }
+
+    fn spawning_stores(&self) -> impl Iterator<Item = &StoreData> {
+        self.structures
+            .iter()
+            .filter(|structure| {
+                matches!(
+                    structure.structure_type,
+                    StructureType::Spawn | StructureType::Extension
+                )
+            })
+            .filter_map(|structure| structure.store.as_ref())
+    }
+}
+
+/// An in-memory world for running the bot outside of the game.
+/// Everything is public so tests can arrange the world however they like between ticks
+#[derive(Debug)]
+pub struct MockWorld {
+    pub time: u32,
+    pub shard: String,
+    pub me: String,
+    pub cpu_used: f64,
+    pub cpu_bucket: i32,
+    pub control_levels: ControlLevels,
+    pub rooms: HashMap<RoomName, MockRoom>,
+    pub creeps: HashMap<String, CreepData>,
+    pub power_creeps: HashMap<String, PowerCreepData>,
+    pub flags: HashMap<String, FlagData>,
+    pub raw_memory: String,
+    pub segments: HashMap<u8, String>,
+    pub active_segments: Vec<u8>,
+    /// Segments asked for this tick, which become active next tick like in the game
+    pub requested_segments: Option<Vec<u8>>,
+    pub public_segments: Vec<u8>,
+    pub foreign_segment: Option<ForeignSegmentData>,
+    pub intents: Vec<Intent>,
+    pub notifications: Vec<String>,
+    pub visuals: Vec<(RoomName, WorldVisual)>,
+    pub next_id: u64,
+}
+
+impl MockWorld {
+    pub fn new() -> Self {
+        Self {
+            time: 1,
+            shard: "shard0".to_string(),
+            me: MOCK_USERNAME.to_string(),
+            cpu_used: 0.,
+            cpu_bucket: 10000,
+            control_levels: ControlLevels {
+                gcl_level: 1,
+                ..Default::default()
+            },
+            rooms: HashMap::new(),
+            creeps: HashMap::new(),
+            power_creeps: HashMap::new(),
+            flags: HashMap::new(),
+            raw_memory: String::new(),
+            segments: HashMap::new(),
+            active_segments: Vec::new(),
+            requested_segments: None,
+            public_segments: Vec::new(),
+            foreign_segment: None,
+            intents: Vec::new(),
+            notifications: Vec::new(),
+            visuals: Vec::new(),
+            next_id: 1,
+        }
+    }
+
+    /// A world with a freshly claimed RCL 1 room: a controller, a spawn with starting energy, two sources and a mineral
+    pub fn with_commune(room_name: RoomName) -> Self {
+        let mut world = Self::new();
+        world.add_room(room_name);
+        world.add_controller(room_name, 25, 40, 1);
+
+        let spawn_id = world.add_structure(StructureType::Spawn, pos(room_name, 25, 25));
+        let spawn = world.structure_mut(spawn_id).unwrap();
+        spawn.name = Some("Spawn1".to_string());
+        let store = spawn.store.as_mut().unwrap();
+        store.resources.insert(ResourceType::Energy, 300);
+
+        world.add_source(pos(room_name, 10, 10));
+        world.add_source(pos(room_name, 40, 12));
+        world.add_mineral(pos(room_name, 12, 38), ResourceType::Hydrogen);
+
+        world
+    }
+
+    pub fn new_id<T>(&mut self) -> ObjectId<T> {
+        let raw_id = RawObjectId::from_str(&format!("{:024x}", self.next_id)).unwrap();
+        self.next_id += 1;
+
+        ObjectId::from(raw_id)
+    }
+
+    pub fn add_room(&mut self, room_name: RoomName) -> &mut MockRoom {
+        self.rooms
+            .entry(room_name)
+            .or_insert_with(|| MockRoom::new(room_name))
+    }
+
+    pub fn room_mut(&mut self, room_name: RoomName) -> &mut MockRoom {
+        self.rooms.get_mut(&room_name).unwrap()
+    }
+
+    pub fn add_controller(&mut self, room_name: RoomName, x: u8, y: u8, level: u8) -> ObjectId<StructureController> {
+        let id = self.new_id();
+        let me = self.me.clone();
+
+        self.room_mut(room_name).controller = Some(ControllerData {
+            id,
+            pos: pos(room_name, x, y),
+            level,
+            my: level > 0,
+            owner: (level > 0).then_some(me),
+            reservation: None,
+            progress: Some(0),
+            progress_total: Some(200),
+            ticks_to_downgrade: Some(20000),
+            safe_mode: None,
+            safe_mode_available: 1,
+            safe_mode_cooldown: None,
+        });
+
+        id
+    }
+
+    /// Adds a structure we own, with the store and hits a fresh structure of its type has
+    pub fn add_structure(&mut self, structure_type: StructureType, pos: Position) -> ObjectId<Structure> {
+        let id = self.new_id();
+        let mut structure = StructureData::new(id, structure_type, pos);
+
+        structure.hits = match structure_type {
+            StructureType::Rampart | StructureType::Wall => 1,
+            _ => 1000,
+        };
+        structure.hits_max = structure.hits;
+        if !matches!(
+            structure_type,
+            StructureType::Road | StructureType::Container | StructureType::Wall | StructureType::Portal
+        ) {
+            structure.my = Some(true);
+            structure.owner = Some(self.me.clone());
+        }
+
+        structure.store = match structure_type {
+            StructureType::Spawn | StructureType::Extension | StructureType::Tower => {
+                let mut store = StoreData::new();
+                let capacity = match structure_type {
+                    StructureType::Spawn => 300,
+                    StructureType::Extension => 50,
+                    _ => 1000,
+                };
+                store.resource_capacities.insert(ResourceType::Energy, capacity);
+                Some(store)
+            }
+            StructureType::Container => Some(StoreData {
+                capacity: 2000,
+                ..Default::default()
+            }),
+            StructureType::Storage => Some(StoreData {
+                capacity: 1_000_000,
+                ..Default::default()
+            }),
+            StructureType::Terminal => Some(StoreData {
+                capacity: 300_000,
+                ..Default::default()
+            }),
+            _ => None,
+        };
+
+        self.add_room(pos.room_name()).structures.push(structure);
+        id
+    }
+
+    pub fn structure_mut(&mut self, id: ObjectId<Structure>) -> Option<&mut StructureData> {
+        self.rooms
+            .values_mut()
+            .flat_map(|room| room.structures.iter_mut())
+            .find(|structure| structure.id == id)
+    }
+
+    pub fn add_source(&mut self, pos: Position) -> ObjectId<Source> {
+        let id = self.new_id();
+
+        self.add_room(pos.room_name()).sources.push(SourceData {
+            id,
+            pos,
+            energy: 3000,
+            energy_capacity: 3000,
+            ticks_to_regeneration: None,
+        });
+
+        id
+    }
+
+    pub fn add_mineral(&mut self, pos: Position, mineral_type: ResourceType) {
+        let id = self.new_id();
+
+        self.add_room(pos.room_name()).minerals.push(MineralData {
+            id,
+            pos,
+            mineral_type,
+            mineral_amount: 70000,
+        });
+    }
+
+    /// Adds a spawned creep of ours
+    pub fn add_creep(&mut self, name: &str, pos: Position, body: &[Part]) -> &mut CreepData {
+        let id = self.new_id();
+        let creep = new_creep(name, Some(id), &self.me, pos, body);
+
+        self.creeps.insert(name.to_string(), creep);
+        self.creeps.get_mut(name).unwrap()
+    }
+
+    /// Adds a power creep of ours spawned at a position
+    pub fn add_power_creep(&mut self, name: &str, pos: Position) -> &mut PowerCreepData {
+        let power_creep = PowerCreepData {
+            name: name.to_string(),
+            id: self.new_id(),
+            pos,
+            hits: 1000,
+            hits_max: 1000,
+            ticks_to_live: Some(5000),
+            store: StoreData {
+                capacity: 100,
+                ..Default::default()
+            },
+        };
+
+        self.power_creeps.insert(name.to_string(), power_creep);
+        self.power_creeps.get_mut(name).unwrap()
+    }
+
+    /// Starts the next tick: segments asked for become active and last tick's intents are forgotten
+    pub fn next_tick(&mut self) {
+        self.time += 1;
+        self.intents.clear();
+        self.visuals.clear();
+
+        if let Some(requested_segments) = self.requested_segments.take() {
+            self.active_segments = requested_segments;
+        }
+    }
+
+    fn find_creep(&self, creep_name: &str) -> Result<&CreepData, ErrorCode> {
+        let creep = self.creeps.get(creep_name).ok_or(ErrorCode::NotFound)?;
+        if creep.spawning {
+            return Err(ErrorCode::Busy);
+        }
+
+        Ok(creep)
+    }
+
+    fn find_structure(&self, id: ObjectId<Structure>) -> Result<&StructureData, ErrorCode> {
+        self.rooms
+            .values()
+            .flat_map(|room| room.structures.iter())
+            .find(|structure| structure.id == id)
+            .ok_or(ErrorCode::NotFound)
+    }
+}
+
+impl Default for MockWorld {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl World for MockWorld {
+    fn time(&mut self) -> u32 {
+        self.time
+    }
+
+    fn cpu_used(&mut self) -> f64 {
+        self.cpu_used
+    }
+
+    fn cpu_bucket(&mut self) -> i32 {
+        self.cpu_bucket
+    }
+
+    fn shard(&mut self) -> String {
+        self.shard.clone()
+    }
+
+    fn control_levels(&mut self) -> ControlLevels {
+        self.control_levels.clone()
+    }
+
+    fn notify(&mut self, message: &str) {
+        self.notifications.push(message.to_string());
+    }
+
+    fn my_creeps(&mut self) -> Vec<CreepData> {
+        self.creeps.values().cloned().collect()
+    }
+
+    fn my_power_creeps(&mut self) -> Vec<PowerCreepData> {
+        self.power_creeps.values().cloned().collect()
+    }
+
+    fn rooms(&mut self) -> Vec<RoomData> {
+        self.rooms
+            .values()
+            .filter(|room| room.visible)
+            .map(|room| RoomData {
+                name: room.name,
+                controller: room.controller.clone(),
+                energy_available: room.energy_available(),
+                energy_capacity_available: room.energy_capacity_available(),
+            })
+            .collect()
+    }
+
+    fn flags(&mut self) -> Vec<FlagData> {
+        self.flags.values().cloned().collect()
+    }
+
+    fn my_construction_sites(&mut self) -> Vec<ConstructionSiteData> {
+        self.rooms
+            .values()
+            .flat_map(|room| room.construction_sites.iter())
+            .filter(|construction_site| construction_site.my)
+            .cloned()
+            .collect()
+    }
+
+    fn structures(&mut self, room_name: RoomName) -> Vec<StructureData> {
+        match self.rooms.get(&room_name) {
+            Some(room) if room.visible => room.structures.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn construction_sites(&mut self, room_name: RoomName) -> Vec<ConstructionSiteData> {
+        match self.rooms.get(&room_name) {
+            Some(room) if room.visible => room.construction_sites.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn sources(&mut self, room_name: RoomName) -> Vec<SourceData> {
+        match self.rooms.get(&room_name) {
+            Some(room) if room.visible => room.sources.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn minerals(&mut self, room_name: RoomName) -> Vec<MineralData> {
+        match self.rooms.get(&room_name) {
+            Some(room) if room.visible => room.minerals.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn hostile_creeps(&mut self, room_name: RoomName) -> Vec<CreepData> {
+        match self.rooms.get(&room_name) {
+            Some(room) if room.visible => room.hostile_creeps.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    fn structure_is_active(&mut self, id: ObjectId<Structure>) -> bool {
+        self.find_structure(id).is_ok()
+    }
+
+    fn terrain(&mut self, room_name: RoomName) -> Option<LocalRoomTerrain> {
+        let bits = match self.rooms.get(&room_name) {
+            Some(room) => room.terrain.clone(),
+            None => Box::new([0; 2500]),
+        };
+
+        Some(LocalRoomTerrain::new_from_bits(bits))
+    }
+
+    fn room_status(&mut self, room_name: RoomName) -> Option<RoomStatus> {
+        match self.rooms.get(&room_name) {
+            Some(room) => Some(room.status),
+            None => Some(RoomStatus::Normal),
+        }
+    }
+
+    /// Neighbouring rooms that exist in the mock
+    fn exits(&mut self, room_name: RoomName) -> Vec<(ExitDirection, RoomName)> {
+        let center = pos(room_name, 25, 25);
+        let (x, y) = (center.world_x(), center.world_y());
+
+        [
+            (ExitDirection::Top, (0, -50)),
+            (ExitDirection::Right, (50, 0)),
+            (ExitDirection::Bottom, (0, 50)),
+            (ExitDirection::Left, (-50, 0)),
+        ]
+        .into_iter()
+        .map(|(exit, (offset_x, offset_y))| {
+            let neighbour = Position::from_world_coords(x + offset_x, y + offset_y).room_name();
+            (exit, neighbour)
+        })
+        .filter(|(_, neighbour)| self.rooms.contains_key(neighbour))
+        .collect()
+    }
+
+    fn room_linear_distance(&mut self, from: RoomName, to: RoomName) -> u32 {
+        from.x_coord()
+            .abs_diff(to.x_coord())
+            .max(from.y_coord().abs_diff(to.y_coord()))
+    }
+
+    fn creep_move(&mut self, creep_name: &str, direction: Direction) -> Result<(), ErrorCode> {
+        let creep = self.find_creep(creep_name)?;
+        if creep.get_active_bodyparts(Part::Move) == 0 {
+            return Err(ErrorCode::NoBodypart);
+        }
+        if creep.fatigue > 0 {
+            return Err(ErrorCode::Tired);
+        }
+
+        self.intents.push(Intent::Move {
+            creep_name: creep_name.to_string(),
+            direction,
+        });
+        Ok(())
+    }
+
+    fn power_creep_move(&mut self, power_creep_name: &str, direction: Direction) -> Result<(), ErrorCode> {
+        self.power_creeps
+            .get(power_creep_name)
+            .ok_or(ErrorCode::NotFound)?;
+
+        self.intents.push(Intent::PowerCreepMove {
+            power_creep_name: power_creep_name.to_string(),
+            direction,
+        });
+        Ok(())
+    }
+
+    fn creep_harvest(&mut self, creep_name: &str, source: ObjectId<Source>) -> Result<(), ErrorCode> {
+        let creep = self.find_creep(creep_name)?;
+        if creep.get_active_bodyparts(Part::Work) == 0 {
+            return Err(ErrorCode::NoBodypart);
+        }
+
+        let source_data = self
+            .rooms
+            .values()
+            .flat_map(|room| room.sources.iter())
+            .find(|source_data| source_data.id == source)
+            .ok_or(ErrorCode::InvalidTarget)?;
+
+        if creep.pos.get_range_to(source_data.pos) > 1 {
+            return Err(ErrorCode::NotInRange);
+        }
+        if source_data.energy == 0 {
+            return Err(ErrorCode::NotEnough);
+        }
+
+        self.intents.push(Intent::Harvest {
+            creep_name: creep_name.to_string(),
+            source,
+        });
+        Ok(())
+    }
+
+    fn creep_suicide(&mut self, creep_name: &str) -> Result<(), ErrorCode> {
+        self.creeps.get(creep_name).ok_or(ErrorCode::NotFound)?;
+
+        self.intents.push(Intent::Suicide {
+            creep_name: creep_name.to_string(),
+        });
+        Ok(())
+    }
+
+    fn spawn_creep(
+        &mut self,
+        spawn: ObjectId<Structure>,
+        body: &[Part],
+        name: &str,
+        opts: &SpawnOpts,
+    ) -> Result<(), ErrorCode> {
+        let spawn_data = self.find_structure(spawn)?;
+        if body.is_empty() || body.len() > 50 {
+            return Err(ErrorCode::InvalidArgs);
+        }
+        if self.creeps.contains_key(name) {
+            return Err(ErrorCode::NameExists);
+        }
+
+        let already_spawning = self.intents.iter().any(|intent| {
+            matches!(intent, Intent::SpawnCreep { spawn: intent_spawn, .. } if *intent_spawn == spawn)
+        });
+        if spawn_data.spawning.is_some() || already_spawning {
+            return Err(ErrorCode::Busy);
+        }
+
+        let cost: u32 = body.iter().map(|part| part.cost()).sum();
+        let room = self.rooms.get(&spawn_data.pos.room_name()).unwrap();
+        // Like the game, only the listed structures can pay when energy structures are given
+        let energy_available = match &opts.energy_structures {
+            Some(energy_structures) => room
+                .structures
+                .iter()
+                .filter(|structure| energy_structures.contains(&structure.id))
+                .filter_map(|structure| structure.store.as_ref())
+                .map(|store| store.get_used_capacity(Some(ResourceType::Energy)))
+                .sum(),
+            None => room.energy_available(),
+        };
+        if cost > energy_available {
+            return Err(ErrorCode::NotEnough);
+        }
+
+        if opts.dry_run {
+            return Ok(());
+        }
+
+        self.intents.push(Intent::SpawnCreep {
+            spawn,
+            body: body.to_vec(),
+            name: name.to_string(),
+            energy_structures: opts.energy_structures.clone(),
+            directions: opts.directions.clone(),
+        });
+        Ok(())
+    }
+
+    fn renew_creep(&mut self, spawn: ObjectId<Structure>, creep_name: &str) -> Result<(), ErrorCode> {
+        let spawn_data = self.find_structure(spawn)?;
+        let creep = self.find_creep(creep_name)?;
+
+        if creep.pos.get_range_to(spawn_data.pos) > 1 {
+            return Err(ErrorCode::NotInRange);
+        }
+        if creep.body.iter().any(|body_part| body_part.part == Part::Claim) {
+            return Err(ErrorCode::InvalidTarget);
+        }
+
+        let already_renewing = self.intents.iter().any(|intent| {
+            matches!(intent, Intent::RenewCreep { spawn: intent_spawn, .. } if *intent_spawn == spawn)
+        });
+        if spawn_data.spawning.is_some() || already_renewing {
+            return Err(ErrorCode::Busy);
+        }
+        if creep.ticks_to_live.unwrap_or(0) + creep.renew_ticks() > CREEP_LIFE_TIME {
+            return Err(ErrorCode::Full);
+        }
+
+        let room = self.rooms.get(&spawn_data.pos.room_name()).unwrap();
+        if creep.renew_energy() > room.energy_available() {
+            return Err(ErrorCode::NotEnough);
+        }
+
+        self.intents.push(Intent::RenewCreep {
+            spawn,
+            creep_name: creep_name.to_string(),
+        });
+        Ok(())
+    }
+
+    fn recycle_creep(&mut self, spawn: ObjectId<Structure>, creep_name: &str) -> Result<(), ErrorCode> {
+        let spawn_data = self.find_structure(spawn)?;
+        let creep = self.find_creep(creep_name)?;
+
+        if creep.pos.get_range_to(spawn_data.pos) > 1 {
+            return Err(ErrorCode::NotInRange);
+        }
+
+        self.intents.push(Intent::RecycleCreep {
+            spawn,
+            creep_name: creep_name.to_string(),
+        });
+        Ok(())
+    }
+
+    fn tower_attack(&mut self, tower: ObjectId<Structure>, target: ObjectId<Creep>) -> Result<(), ErrorCode> {
+        self.find_structure(tower)?;
+
+        self.intents.push(Intent::TowerAttack { tower, target });
+        Ok(())
+    }
+
+    fn tower_heal(&mut self, tower: ObjectId<Structure>, target: ObjectId<Creep>) -> Result<(), ErrorCode> {
+        self.find_structure(tower)?;
+
+        self.intents.push(Intent::TowerHeal { tower, target });
+        Ok(())
+    }
+
+    fn tower_repair(&mut self, tower: ObjectId<Structure>, target: ObjectId<Structure>) -> Result<(), ErrorCode> {
+        self.find_structure(tower)?;
+
+        self.intents.push(Intent::TowerRepair { tower, target });
+        Ok(())
+    }
+
+    fn activate_safe_mode(&mut self, controller: ObjectId<StructureController>) -> Result<(), ErrorCode> {
+        self.intents.push(Intent::ActivateSafeMode { controller });
+        Ok(())
+    }
+
+    fn remove_construction_site(&mut self, id: ObjectId<ConstructionSite>) -> Result<(), ErrorCode> {
+        self.intents.push(Intent::RemoveConstructionSite { id });
+        Ok(())
+    }
+
+    fn raw_memory(&mut self) -> String {
+        self.raw_memory.clone()
+    }
+
+    fn set_raw_memory(&mut self, value: String) {
+        self.raw_memory = value;
+    }
+
+    fn segment(&mut self, segment: u8) -> Option<String> {
+        if !self.active_segments.contains(&segment) {
+            return None;
+        }
+
+        Some(self.segments.get(&segment).cloned().unwrap_or_default())
+    }
+
+    fn set_segment(&mut self, segment: u8, value: String) {
+        self.segments.insert(segment, value);
+    }
+
+    fn set_active_segments(&mut self, segments: &[u8]) {
+        self.requested_segments = Some(segments.to_vec());
+    }
+
+    fn set_public_segments(&mut self, segments: &[u8]) {
+        self.public_segments = segments.to_vec();
+    }
+
+    fn set_active_foreign_segment(&mut self, username: &str, segment: Option<u8>) {}
+
+    fn foreign_segment(&mut self) -> Option<ForeignSegmentData> {
+        self.foreign_segment.clone()
+    }
+
+    fn draw(&mut self, room_name: RoomName, visual: WorldVisual) {
+        self.visuals.push((room_name, visual));
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// A creep with full hits and an empty store sized by its carry parts
+pub fn new_creep(
+    name: &str,
+    id: Option<ObjectId<Creep>>,
+    owner: &str,
+    pos: Position,
+    body: &[Part],
+) -> CreepData {
+    let body: Vec<BodyPartData> = body
+        .iter()
+        .map(|part| BodyPartData {
+            part: *part,
+            hits: 100,
+            boost: None,
+        })
+        .collect();
+
+    let carry_parts = body.iter().filter(|part| part.part == Part::Carry).count() as u32;
+
+    CreepData {
+        name: name.to_string(),
+        id,
+        owner: owner.to_string(),
+        my: true,
+        pos,
+        hits: 100 * body.len() as u32,
+        hits_max: 100 * body.len() as u32,
+        body,
+        fatigue: 0,
+        ticks_to_live: Some(1500),
+        spawning: false,
+        store: StoreData {
+            capacity: carry_parts * 50,
+            ..Default::default()
+        },
+    }
+}
+
+pub fn pos(room_name: RoomName, x: u8, y: u8) -> Position {
+    Position::new(
+        RoomCoordinate::new(x).unwrap(),
+        RoomCoordinate::new(y).unwrap(),
+        room_name,
+    )
+}
+
+/// Fresh memory for the installed world's player. Unlike GameMemory::new, the player needn't own a room
+pub fn new_memory() -> GameMemory {
+    let settings = Settings::new();
+    let me = with(|mock| mock.me.clone());
+
+    GameMemory::empty(me, settings.breaking_version, settings.compressed_memory)
+}
+
+/// Installs a mock world for the current thread
+pub fn install(mock_world: MockWorld) {
+    super::install(Box::new(mock_world));
+}
+
+/// Installs a mock world and brings fresh state and memory up to date for a commune in it, as the first tick would
+pub fn install_commune(mock_world: MockWorld, room_name: RoomName) -> (GameState, GameMemory) {
+    install(mock_world);
+
+    let mut memory = new_memory();
+    let mut game_state = GameState::new();
+    game_state.tick_update(&mut memory);
+    game_state.get_or_create_room_state_mut(&room_name);
+    room_ops::try_create_commune_state(&room_name, &mut game_state, &mut memory);
+
+    (game_state, memory)
+}
+
+/// Run a function with the installed mock world. Panics if the installed world is not a mock
+pub fn with<R>(f: impl FnOnce(&mut MockWorld) -> R) -> R {
+    super::with(|world| {
+        let mock_world = world
+            .as_any_mut()
+            .downcast_mut::<MockWorld>()
+            .expect("the installed world is not a mock world");
+
+        f(mock_world)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use screeps::{RoomName, StructureType};
+
+    use super::{Intent, MockWorld};
+    use crate::{
+        constants::segments::HARVESTABLE_ROOMS_SEGMENTS, memory::game_memory::GameMemory, settings::Settings,
+        state::game::GameState, world,
+    };
+
+    #[test]
+    fn segments_activate_next_tick() {
+        super::install(MockWorld::new());
+
+        world::set_segment(3, "data".to_string());
+        world::set_active_segments(&[3]);
+        assert_eq!(world::segment(3), None);
+
+        super::with(|mock| mock.next_tick());
+        assert_eq!(world::segment(3), Some("data".to_string()));
+        assert_eq!(world::segment(4), None);
+    }
+
+    #[test]
+    fn loop_runs_on_a_fresh_commune() {
+        let room_name = RoomName::new("W1N1").unwrap();
+        super::install(MockWorld::with_commune(room_name));
+
+        let settings = Settings::new();
+        let mut memory = GameMemory::new(&settings);
+        let mut game_state = GameState::new();
+
+        let mut spawned = false;
+        for _ in 0..3 {
+            crate::loop_with_params(&mut memory, &mut game_state, &settings);
+
+            spawned |= super::with(|mock| {
+                mock.intents
+                    .iter()
+                    .any(|intent| matches!(intent, Intent::SpawnCreep { .. }))
+            });
+            super::with(|mock| mock.next_tick());
+        }
+
+        assert!(game_state.communes.contains(&room_name));
+        assert!(memory.communes.contains_key(&room_name));
+        assert!(memory.harvestable_rooms.contains_key(&room_name));
+        assert!(spawned);
+
+        // Memory written by the loop reads back
+        assert!(super::with(|mock| !mock.raw_memory.is_empty()));
+        let active_segments = super::with(|mock| mock.active_segments.clone());
+        assert!(HARVESTABLE_ROOMS_SEGMENTS.iter().all(|segment| active_segments.contains(segment)));
+    }
+
+    #[test]
+    fn spawn_intents_are_validated() {
+        let room_name = RoomName::new("W1N1").unwrap();
+        super::install(MockWorld::with_commune(room_name));
+
+        let spawn = super::with(|mock| {
+            mock.rooms[&room_name]
+                .structures
+                .iter()
+                .find(|structure| structure.structure_type == StructureType::Spawn)
+                .unwrap()
+                .id
+        });
+
+        let opts = world::objects::SpawnOpts::new();
+        let too_expensive = [screeps::Part::Work; 4];
+        assert_eq!(
+            world::spawn_creep(spawn, &too_expensive, "a", &opts),
+            Err(screeps::ErrorCode::NotEnough)
+        );
+
+        let body = [screeps::Part::Work, screeps::Part::Move];
+        assert_eq!(world::spawn_creep(spawn, &body, "a", &opts), Ok(()));
+        assert_eq!(
+            world::spawn_creep(spawn, &body, "b", &opts),
+            Err(screeps::ErrorCode::Busy)
+        );
+    }
+}
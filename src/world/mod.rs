@@ -0,0 +1,304 @@
+use std::{any::Any, cell::RefCell, mem};
+
+use screeps::{
+    game::map::RoomStatus, ConstructionSite, Creep, Direction, ErrorCode, ExitDirection,
+    LocalRoomTerrain, ObjectId, Part, RoomName, Source, Structure, StructureController,
+};
+
+use objects::{
+    ConstructionSiteData, ControlLevels, CreepData, FlagData, ForeignSegmentData, MineralData,
+    PowerCreepData, RoomData, SourceData, SpawnOpts, StructureData, WorldVisual,
+};
+
+pub mod mock_world;
+pub mod objects;
+pub mod recorder;
+pub mod screeps_world;
+pub mod simulation;
+
+/// Everything the bot reads from or asks of the game.
+/// The bot only talks to the game through the world installed for its thread,
+/// so the same code can run against the real game or an in-memory one under cargo test
+pub trait World {
+    // Game
+
+    fn time(&mut self) -> u32;
+    fn cpu_used(&mut self) -> f64;
+    fn cpu_bucket(&mut self) -> i32;
+    fn shard(&mut self) -> String;
+    fn control_levels(&mut self) -> ControlLevels;
+    fn notify(&mut self, message: &str);
+
+    // Objects
+
+    fn my_creeps(&mut self) -> Vec<CreepData>;
+    /// Power creeps of ours that are spawned on this shard
+    fn my_power_creeps(&mut self) -> Vec<PowerCreepData>;
+    /// Rooms we have vision of
+    fn rooms(&mut self) -> Vec<RoomData>;
+    fn flags(&mut self) -> Vec<FlagData>;
+    /// Construction sites we own in every room
+    fn my_construction_sites(&mut self) -> Vec<ConstructionSiteData>;
+    fn structures(&mut self, room_name: RoomName) -> Vec<StructureData>;
+    fn construction_sites(&mut self, room_name: RoomName) -> Vec<ConstructionSiteData>;
+    fn sources(&mut self, room_name: RoomName) -> Vec<SourceData>;
+    fn minerals(&mut self, room_name: RoomName) -> Vec<MineralData>;
+    fn hostile_creeps(&mut self, room_name: RoomName) -> Vec<CreepData>;
+    fn structure_is_active(&mut self, id: ObjectId<Structure>) -> bool;
+
+    // Map
+
+    fn terrain(&mut self, room_name: RoomName) -> Option<LocalRoomTerrain>;
+    fn room_status(&mut self, room_name: RoomName) -> Option<RoomStatus>;
+    fn exits(&mut self, room_name: RoomName) -> Vec<(ExitDirection, RoomName)>;
+    fn room_linear_distance(&mut self, from: RoomName, to: RoomName) -> u32;
+
+    // Intents
+
+    fn creep_move(&mut self, creep_name: &str, direction: Direction) -> Result<(), ErrorCode>;
+    fn power_creep_move(&mut self, power_creep_name: &str, direction: Direction) -> Result<(), ErrorCode>;
+    fn creep_harvest(&mut self, creep_name: &str, source: ObjectId<Source>) -> Result<(), ErrorCode>;
+    fn creep_suicide(&mut self, creep_name: &str) -> Result<(), ErrorCode>;
+    fn spawn_creep(
+        &mut self,
+        spawn: ObjectId<Structure>,
+        body: &[Part],
+        name: &str,
+        opts: &SpawnOpts,
+    ) -> Result<(), ErrorCode>;
+    fn renew_creep(&mut self, spawn: ObjectId<Structure>, creep_name: &str) -> Result<(), ErrorCode>;
+    fn recycle_creep(&mut self, spawn: ObjectId<Structure>, creep_name: &str) -> Result<(), ErrorCode>;
+    fn tower_attack(&mut self, tower: ObjectId<Structure>, target: ObjectId<Creep>) -> Result<(), ErrorCode>;
+    fn tower_heal(&mut self, tower: ObjectId<Structure>, target: ObjectId<Creep>) -> Result<(), ErrorCode>;
+    fn tower_repair(&mut self, tower: ObjectId<Structure>, target: ObjectId<Structure>) -> Result<(), ErrorCode>;
+    fn activate_safe_mode(&mut self, controller: ObjectId<StructureController>) -> Result<(), ErrorCode>;
+    fn remove_construction_site(&mut self, id: ObjectId<ConstructionSite>) -> Result<(), ErrorCode>;
+
+    // Raw memory
+
+    fn raw_memory(&mut self) -> String;
+    fn set_raw_memory(&mut self, value: String);
+    /// None unless the segment was made active
+    fn segment(&mut self, segment: u8) -> Option<String>;
+    fn set_segment(&mut self, segment: u8, value: String);
+    fn set_active_segments(&mut self, segments: &[u8]);
+    fn set_public_segments(&mut self, segments: &[u8]);
+    fn set_active_foreign_segment(&mut self, username: &str, segment: Option<u8>);
+    fn foreign_segment(&mut self) -> Option<ForeignSegmentData>;
+
+    // Visuals
+
+    fn draw(&mut self, room_name: RoomName, visual: WorldVisual);
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+thread_local! {
+    static WORLD: RefCell<Box<dyn World>> = RefCell::new(default_world());
+}
+
+#[cfg(target_arch = "wasm32")]
+fn default_world() -> Box<dyn World> {
+    Box::new(screeps_world::ScreepsWorld::new())
+}
+
+/// Outside of the game there is nothing to talk to, so start with an empty world
+#[cfg(not(target_arch = "wasm32"))]
+fn default_world() -> Box<dyn World> {
+    Box::new(mock_world::MockWorld::new())
+}
+
+/// Replace the world for the current thread, returning the previous one
+pub fn install(world: Box<dyn World>) -> Box<dyn World> {
+    WORLD.with_borrow_mut(|current| mem::replace(current, world))
+}
+
+/// Run a function with the installed world. World implementations must not call back into this
+pub fn with<R>(f: impl FnOnce(&mut dyn World) -> R) -> R {
+    WORLD.with_borrow_mut(|world| f(world.as_mut()))
+}
+
+// Game
+
+pub fn time() -> u32 {
+    with(|world| world.time())
+}
+
+pub fn cpu_used() -> f64 {
+    with(|world| world.cpu_used())
+}
+
+pub fn cpu_bucket() -> i32 {
+    with(|world| world.cpu_bucket())
+}
+
+pub fn shard() -> String {
+    with(|world| world.shard())
+}
+
+pub fn control_levels() -> ControlLevels {
+    with(|world| world.control_levels())
+}
+
+pub fn notify(message: &str) {
+    with(|world| world.notify(message))
+}
+
+// Objects
+
+pub fn my_creeps() -> Vec<CreepData> {
+    with(|world| world.my_creeps())
+}
+
+pub fn my_power_creeps() -> Vec<PowerCreepData> {
+    with(|world| world.my_power_creeps())
+}
+
+pub fn rooms() -> Vec<RoomData> {
+    with(|world| world.rooms())
+}
+
+pub fn flags() -> Vec<FlagData> {
+    with(|world| world.flags())
+}
+
+pub fn my_construction_sites() -> Vec<ConstructionSiteData> {
+    with(|world| world.my_construction_sites())
+}
+
+pub fn structures(room_name: RoomName) -> Vec<StructureData> {
+    with(|world| world.structures(room_name))
+}
+
+pub fn construction_sites(room_name: RoomName) -> Vec<ConstructionSiteData> {
+    with(|world| world.construction_sites(room_name))
+}
+
+pub fn sources(room_name: RoomName) -> Vec<SourceData> {
+    with(|world| world.sources(room_name))
+}
+
+pub fn minerals(room_name: RoomName) -> Vec<MineralData> {
+    with(|world| world.minerals(room_name))
+}
+
+pub fn hostile_creeps(room_name: RoomName) -> Vec<CreepData> {
+    with(|world| world.hostile_creeps(room_name))
+}
+
+pub fn structure_is_active(id: ObjectId<Structure>) -> bool {
+    with(|world| world.structure_is_active(id))
+}
+
+// Map
+
+pub fn terrain(room_name: RoomName) -> Option<LocalRoomTerrain> {
+    with(|world| world.terrain(room_name))
+}
+
+pub fn room_status(room_name: RoomName) -> Option<RoomStatus> {
+    with(|world| world.room_status(room_name))
+}
+
+pub fn exits(room_name: RoomName) -> Vec<(ExitDirection, RoomName)> {
+    with(|world| world.exits(room_name))
+}
+
+pub fn room_linear_distance(from: RoomName, to: RoomName) -> u32 {
+    with(|world| world.room_linear_distance(from, to))
+}
+
+// Intents
+
+pub fn creep_move(creep_name: &str, direction: Direction) -> Result<(), ErrorCode> {
+    with(|world| world.creep_move(creep_name, direction))
+}
+
+pub fn power_creep_move(power_creep_name: &str, direction: Direction) -> Result<(), ErrorCode> {
+    with(|world| world.power_creep_move(power_creep_name, direction))
+}
+
+pub fn creep_harvest(creep_name: &str, source: ObjectId<Source>) -> Result<(), ErrorCode> {
+    with(|world| world.creep_harvest(creep_name, source))
+}
+
+pub fn creep_suicide(creep_name: &str) -> Result<(), ErrorCode> {
+    with(|world| world.creep_suicide(creep_name))
+}
+
+pub fn spawn_creep(
+    spawn: ObjectId<Structure>,
+    body: &[Part],
+    name: &str,
+    opts: &SpawnOpts,
+) -> Result<(), ErrorCode> {
+    with(|world| world.spawn_creep(spawn, body, name, opts))
+}
+
+pub fn renew_creep(spawn: ObjectId<Structure>, creep_name: &str) -> Result<(), ErrorCode> {
+    with(|world| world.renew_creep(spawn, creep_name))
+}
+
+pub fn recycle_creep(spawn: ObjectId<Structure>, creep_name: &str) -> Result<(), ErrorCode> {
+    with(|world| world.recycle_creep(spawn, creep_name))
+}
+
+pub fn tower_attack(tower: ObjectId<Structure>, target: ObjectId<Creep>) -> Result<(), ErrorCode> {
+    with(|world| world.tower_attack(tower, target))
+}
+
+pub fn tower_heal(tower: ObjectId<Structure>, target: ObjectId<Creep>) -> Result<(), ErrorCode> {
+    with(|world| world.tower_heal(tower, target))
+}
+
+pub fn tower_repair(tower: ObjectId<Structure>, target: ObjectId<Structure>) -> Result<(), ErrorCode> {
+    with(|world| world.tower_repair(tower, target))
+}
+
+pub fn activate_safe_mode(controller: ObjectId<StructureController>) -> Result<(), ErrorCode> {
+    with(|world| world.activate_safe_mode(controller))
+}
+
+pub fn remove_construction_site(id: ObjectId<ConstructionSite>) -> Result<(), ErrorCode> {
+    with(|world| world.remove_construction_site(id))
+}
+
+// Raw memory
+
+pub fn raw_memory() -> String {
+    with(|world| world.raw_memory())
+}
+
+pub fn set_raw_memory(value: String) {
+    with(|world| world.set_raw_memory(value))
+}
+
+pub fn segment(segment: u8) -> Option<String> {
+    with(|world| world.segment(segment))
+}
+
+pub fn set_segment(segment: u8, value: String) {
+    with(|world| world.set_segment(segment, value))
+}
+
+pub fn set_active_segments(segments: &[u8]) {
+    with(|world| world.set_active_segments(segments))
+}
+
+pub fn set_public_segments(segments: &[u8]) {
+    with(|world| world.set_public_segments(segments))
+}
+
+pub fn set_active_foreign_segment(username: &str, segment: Option<u8>) {
+    with(|world| world.set_active_foreign_segment(username, segment))
+}
+
+pub fn foreign_segment() -> Option<ForeignSegmentData> {
+    with(|world| world.foreign_segment())
+}
+
+// Visuals
+
+pub fn draw(room_name: RoomName, visual: WorldVisual) {
+    with(|world| world.draw(room_name, visual))
+}
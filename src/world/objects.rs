@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+
+use screeps::{
+    ConstructionSite, Creep, Direction, Mineral, ObjectId, Part, Position, PowerCreep, ResourceType, RoomName,
+    Source, Structure, StructureController, StructureType, CREEP_LIFE_TIME, CREEP_SPAWN_TIME,
+};
+use serde::{Deserialize, Serialize};
+
+// Owned copies of what the game tells us about its objects.
+// They are read once through the world and never talk to the game themselves
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreepData {
+    pub name: String,
+    /// Creeps that have only just been ordered to spawn may not have an id yet
+    pub id: Option<ObjectId<Creep>>,
+    pub owner: String,
+    pub my: bool,
+    pub pos: Position,
+    pub body: Vec<BodyPartData>,
+    pub fatigue: u32,
+    pub hits: u32,
+    pub hits_max: u32,
+    pub ticks_to_live: Option<u32>,
+    pub spawning: bool,
+    pub store: StoreData,
+}
+
+impl CreepData {
+    /// Number of parts of a type that still have hits
+    pub fn get_active_bodyparts(&self, part: Part) -> u8 {
+        self.body
+            .iter()
+            .filter(|body_part| body_part.part == part && body_part.hits > 0)
+            .count() as u8
+    }
+
+    /// Energy the body cost to spawn, boosts aside
+    pub fn body_cost(&self) -> u32 {
+        self.body.iter().map(|body_part| body_part.part.cost()).sum()
+    }
+
+    pub fn is_boosted(&self) -> bool {
+        self.body.iter().any(|body_part| body_part.boost.is_some())
+    }
+
+    /// Ticks to live a single renew adds, as the game works it out with its renew ratio of 6 / 5
+    pub fn renew_ticks(&self) -> u32 {
+        CREEP_LIFE_TIME * 6 / 5 / CREEP_SPAWN_TIME / self.body.len().max(1) as u32
+    }
+
+    /// Energy a single renew costs, as the game works it out with its renew ratio of 6 / 5
+    pub fn renew_energy(&self) -> u32 {
+        (self.body_cost() * 6).div_ceil(5 * CREEP_SPAWN_TIME * self.body.len().max(1) as u32)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A power creep of ours that is spawned on this shard
+pub struct PowerCreepData {
+    pub name: String,
+    pub id: ObjectId<PowerCreep>,
+    pub pos: Position,
+    pub hits: u32,
+    pub hits_max: u32,
+    pub ticks_to_live: Option<u32>,
+    pub store: StoreData,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BodyPartData {
+    pub part: Part,
+    pub hits: u32,
+    pub boost: Option<ResourceType>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StoreData {
+    pub resources: HashMap<ResourceType, u32>,
+    /// Capacity shared by every resource, zero for stores that limit each resource separately
+    pub capacity: u32,
+    /// Capacity of each resource, for stores such as spawns and power spawns that limit them separately
+    pub resource_capacities: HashMap<ResourceType, u32>,
+}
+
+impl StoreData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Amount of a resource, or of every resource when None
+    pub fn get_used_capacity(&self, resource: Option<ResourceType>) -> u32 {
+        match resource {
+            Some(resource) => self.resources.get(&resource).copied().unwrap_or(0),
+            None => self.resources.values().sum(),
+        }
+    }
+
+    pub fn get_capacity(&self, resource: Option<ResourceType>) -> u32 {
+        match resource.and_then(|resource| self.resource_capacities.get(&resource)) {
+            Some(capacity) => *capacity,
+            None => self.capacity,
+        }
+    }
+
+    pub fn get_free_capacity(&self, resource: Option<ResourceType>) -> u32 {
+        if let Some(resource) = resource {
+            if self.resource_capacities.contains_key(&resource) {
+                return self
+                    .get_capacity(Some(resource))
+                    .saturating_sub(self.get_used_capacity(Some(resource)));
+            }
+        }
+
+        self.capacity.saturating_sub(self.get_used_capacity(None))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomData {
+    pub name: RoomName,
+    pub controller: Option<ControllerData>,
+    pub energy_available: u32,
+    pub energy_capacity_available: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ControllerData {
+    pub id: ObjectId<StructureController>,
+    pub pos: Position,
+    pub level: u8,
+    pub my: bool,
+    pub owner: Option<String>,
+    pub reservation: Option<String>,
+    pub progress: Option<u32>,
+    pub progress_total: Option<u32>,
+    pub ticks_to_downgrade: Option<u32>,
+    pub safe_mode: Option<u32>,
+    pub safe_mode_available: u32,
+    pub safe_mode_cooldown: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Any structure other than the controller. Fields that only some structure types have are None for the rest
+pub struct StructureData {
+    pub id: ObjectId<Structure>,
+    pub structure_type: StructureType,
+    pub pos: Position,
+    pub hits: u32,
+    pub hits_max: u32,
+    /// None for structures that can not be owned, such as roads and containers
+    pub my: Option<bool>,
+    pub owner: Option<String>,
+    pub store: Option<StoreData>,
+    /// Name of a spawn
+    pub name: Option<String>,
+    /// What a spawn is currently spawning
+    pub spawning: Option<SpawningData>,
+    /// Room a portal leads to
+    pub portal_destination: Option<RoomName>,
+}
+
+impl StructureData {
+    pub fn new(id: ObjectId<Structure>, structure_type: StructureType, pos: Position) -> Self {
+        Self {
+            id,
+            structure_type,
+            pos,
+            hits: 0,
+            hits_max: 0,
+            my: None,
+            owner: None,
+            store: None,
+            name: None,
+            spawning: None,
+            portal_destination: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpawningData {
+    pub name: String,
+    pub need_time: u32,
+    pub remaining_time: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceData {
+    pub id: ObjectId<Source>,
+    pub pos: Position,
+    pub energy: u32,
+    pub energy_capacity: u32,
+    pub ticks_to_regeneration: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MineralData {
+    pub id: ObjectId<Mineral>,
+    pub pos: Position,
+    pub mineral_type: ResourceType,
+    pub mineral_amount: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConstructionSiteData {
+    pub id: Option<ObjectId<ConstructionSite>>,
+    pub pos: Position,
+    pub structure_type: StructureType,
+    pub my: bool,
+    pub owner: String,
+    pub progress: u32,
+    pub progress_total: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlagData {
+    pub name: String,
+    pub pos: Position,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ControlLevels {
+    pub gcl_level: u32,
+    pub gcl_progress: f64,
+    pub gcl_progress_total: f64,
+    pub gpl_progress: f64,
+    pub gpl_progress_total: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct ForeignSegmentData {
+    pub username: String,
+    pub data: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SpawnOpts {
+    pub dry_run: bool,
+    /// Spawns and extensions to draw energy from, in order. None uses the game's default order
+    pub energy_structures: Option<Vec<ObjectId<Structure>>>,
+    /// Directions the creep may leave the spawn in, by preference. None allows any direction
+    pub directions: Option<Vec<Direction>>,
+}
+
+impl SpawnOpts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorldVisual {
+    Circle { x: f32, y: f32 },
+    Text { x: f32, y: f32, text: String },
+    Poly { points: Vec<(f32, f32)> },
+}
@@ -0,0 +1,269 @@
+//! Records what the bot reads from the game at the start of a tick, so a tick that panicked can be
+//! replayed natively through the same loop.
+//!
+//! Recording is opt-in with the `record` feature. Each tick's record is chunked across the segments
+//! of one of the RECORDING_SEGMENTS slots in turn, so the last few ticks before a panic are always kept.
+//! The replay starts from a cold global: state cached on the heap between ticks is rebuilt, not recorded
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{
+        general::GeneralResult,
+        segments::{RECORDING_SEGMENTS, SNAPSHOT_SEGMENTS},
+    },
+    memory::{game_memory::GameMemory, memory_segments},
+    settings::Settings,
+    state::game::GameState,
+};
+
+use super::{
+    mock_world::{self, MockRoom, MockWorld},
+    objects::{
+        ConstructionSiteData, ControlLevels, CreepData, FlagData, MineralData, PowerCreepData,
+        RoomData, SourceData, StructureData,
+    },
+};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TickRecord {
+    pub time: u32,
+    pub shard: String,
+    pub cpu_bucket: i32,
+    pub control_levels: ControlLevels,
+    pub creeps: Vec<CreepData>,
+    pub power_creeps: Vec<PowerCreepData>,
+    pub rooms: Vec<RecordedRoom>,
+    pub flags: Vec<FlagData>,
+    pub raw_memory: String,
+    /// Segments that were active, such as those holding cold memory sections.
+    /// Snapshots are left out, the replay decodes the recorded memory rather than recovering it
+    pub segments: Vec<(u8, String)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+/// A room we had vision of
+pub struct RecordedRoom {
+    pub room: RoomData,
+    /// Terrain bits by y * 50 + x
+    pub terrain: Vec<u8>,
+    pub structures: Vec<StructureData>,
+    pub construction_sites: Vec<ConstructionSiteData>,
+    pub sources: Vec<SourceData>,
+    pub minerals: Vec<MineralData>,
+    pub hostile_creeps: Vec<CreepData>,
+}
+
+#[derive(Debug)]
+pub enum RecordError {
+    NotBase32768,
+    Undecodable,
+}
+
+/// Read everything the tick will from the installed world
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+pub fn capture() -> TickRecord {
+    let rooms = super::rooms()
+        .into_iter()
+        .map(|room| {
+            let room_name = room.name;
+            let terrain = super::terrain(room_name)
+                .map(|terrain| terrain.get_raw_buffer().to_vec())
+                .unwrap_or_default();
+
+            RecordedRoom {
+                room,
+                terrain,
+                structures: super::structures(room_name),
+                construction_sites: super::construction_sites(room_name),
+                sources: super::sources(room_name),
+                minerals: super::minerals(room_name),
+                hostile_creeps: super::hostile_creeps(room_name),
+            }
+        })
+        .collect();
+
+    let skipped_segments = [RECORDING_SEGMENTS.concat(), SNAPSHOT_SEGMENTS.concat()].concat();
+    let segments = (0..100)
+        .filter(|segment| !skipped_segments.contains(segment))
+        .filter_map(|segment| super::segment(segment).map(|data| (segment, data)))
+        .collect();
+
+    TickRecord {
+        time: super::time(),
+        shard: super::shard(),
+        cpu_bucket: super::cpu_bucket(),
+        control_levels: super::control_levels(),
+        creeps: super::my_creeps(),
+        power_creeps: super::my_power_creeps(),
+        rooms,
+        flags: super::flags(),
+        raw_memory: super::raw_memory(),
+        segments,
+    }
+}
+
+/// Capture this tick and write it over the oldest record
+#[cfg_attr(feature = "profile", screeps_timing_annotate::timing)]
+pub fn record_tick() {
+    let record = capture();
+
+    let Some(encoded) = encode_record(&record) else {
+        warn!("Failed to encode the record of tick {}", record.time);
+        return;
+    };
+
+    let slot = record.time as usize % RECORDING_SEGMENTS.len();
+    if memory_segments::write_chunks(&RECORDING_SEGMENTS[slot], &encoded) == GeneralResult::Fail {
+        warn!(
+            "The record of tick {} is {} characters, too long for its segments",
+            record.time,
+            encoded.chars().count()
+        );
+    }
+}
+
+pub fn encode_record(record: &TickRecord) -> Option<String> {
+    let bits = bitcode::serialize(record).ok()?;
+    base32768::encode(&bits).ok()
+}
+
+pub fn decode_record(text: &str) -> Result<TickRecord, RecordError> {
+    let mut bits = Vec::new();
+    base32768::decode(text.trim(), &mut bits).map_err(|_| RecordError::NotBase32768)?;
+
+    bitcode::deserialize(&bits).map_err(|_| RecordError::Undecodable)
+}
+
+/// A mock world that reads back exactly what was recorded
+pub fn world_from_record(record: &TickRecord) -> MockWorld {
+    let mut world = MockWorld::new();
+
+    world.time = record.time;
+    world.shard = record.shard.clone();
+    world.cpu_bucket = record.cpu_bucket;
+    world.control_levels = record.control_levels.clone();
+    world.raw_memory = record.raw_memory.clone();
+
+    for (segment, data) in &record.segments {
+        world.segments.insert(*segment, data.clone());
+        world.active_segments.push(*segment);
+    }
+
+    for creep in &record.creeps {
+        world.creeps.insert(creep.name.clone(), creep.clone());
+    }
+
+    for power_creep in &record.power_creeps {
+        world
+            .power_creeps
+            .insert(power_creep.name.clone(), power_creep.clone());
+    }
+
+    for flag in &record.flags {
+        world.flags.insert(flag.name.clone(), flag.clone());
+    }
+
+    for recorded_room in &record.rooms {
+        let mut room = MockRoom::new(recorded_room.room.name);
+
+        if let Ok(terrain) = <[u8; 2500]>::try_from(recorded_room.terrain.as_slice()) {
+            room.terrain = Box::new(terrain);
+        }
+        room.controller = recorded_room.room.controller.clone();
+        room.structures = recorded_room.structures.clone();
+        room.construction_sites = recorded_room.construction_sites.clone();
+        room.sources = recorded_room.sources.clone();
+        room.minerals = recorded_room.minerals.clone();
+        room.hostile_creeps = recorded_room.hostile_creeps.clone();
+
+        world.rooms.insert(room.name, room);
+    }
+
+    // Mock ids must not collide with recorded ones when the replay spawns creeps
+    world.next_id = u64::MAX / 2;
+
+    world
+}
+
+/// Run the recorded tick through the loop. A panic in the recorded tick panics here too
+pub fn replay(record: &TickRecord) -> MockWorld {
+    mock_world::install(world_from_record(record));
+
+    let settings = Settings::new();
+    let mut memory = GameMemory::load_from_memory_or_default();
+    let mut game_state = GameState::new();
+
+    crate::loop_with_params(&mut memory, &mut game_state, &settings);
+
+    mock_world::with(std::mem::take)
+}
+
+#[cfg(test)]
+mod tests {
+    use screeps::RoomName;
+
+    use super::{capture, decode_record, encode_record, record_tick, replay};
+    use crate::{
+        constants::segments::{MAX_SEGMENT_LENGTH, RECORDING_SEGMENTS, SNAPSHOT_SEGMENTS},
+        memory::memory_segments,
+        world::mock_world::{self, Intent, MockWorld},
+    };
+
+    #[test]
+    fn records_round_trip_and_replay() {
+        let room_name = RoomName::new("W1N1").unwrap();
+        mock_world::install(MockWorld::with_commune(room_name));
+        mock_world::with(|mock| {
+            mock.time = 1234;
+            mock.room_mut(room_name).set_terrain(0, 0, screeps::Terrain::Wall);
+        });
+
+        let record = capture();
+        let encoded = encode_record(&record).unwrap();
+        let decoded = decode_record(&encoded).unwrap();
+
+        assert_eq!(decoded.time, 1234);
+        assert_eq!(decoded.rooms.len(), 1);
+        assert_eq!(decoded.rooms[0].terrain[0], 1);
+        assert_eq!(decoded.rooms[0].sources.len(), 2);
+
+        let replayed = replay(&decoded);
+
+        assert_eq!(replayed.time, 1234);
+        assert!(
+            replayed
+                .intents
+                .iter()
+                .any(|intent| matches!(intent, Intent::SpawnCreep { .. }))
+        );
+    }
+
+    #[test]
+    fn records_longer_than_a_segment_are_chunked_across_their_slot() {
+        let room_name = RoomName::new("W1N1").unwrap();
+        mock_world::install(MockWorld::with_commune(room_name));
+        mock_world::with(|mock| {
+            mock.time = 1235;
+            mock.raw_memory = "a".repeat(250_000);
+            mock.segments.insert(SNAPSHOT_SEGMENTS[0][0], "snapshot".to_string());
+            mock.active_segments = [SNAPSHOT_SEGMENTS.concat(), RECORDING_SEGMENTS.concat()].concat();
+        });
+
+        record_tick();
+
+        let slot = RECORDING_SEGMENTS[1235 % RECORDING_SEGMENTS.len()];
+        let chunks = mock_world::with(|mock| slot.map(|segment| mock.segments[&segment].chars().count()));
+        assert!(chunks.iter().all(|length| *length > 0 && *length <= MAX_SEGMENT_LENGTH));
+
+        let record = decode_record(&memory_segments::read_chunks(&slot).unwrap()).unwrap();
+        assert_eq!(record.raw_memory.len(), 250_000);
+        assert!(
+            record
+                .segments
+                .iter()
+                .all(|(segment, _)| !SNAPSHOT_SEGMENTS.concat().contains(segment))
+        );
+    }
+}
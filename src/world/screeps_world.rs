@@ -0,0 +1,525 @@
+use std::{any::Any, collections::HashMap};
+
+use js_sys::JsString;
+use screeps::{
+    find, game, game::map::RoomStatus, raw_memory, ConstructionSite, Creep, Direction, ErrorCode,
+    ExitDirection, HasHits, HasId, HasPosition, HasStore, LocalRoomTerrain, MaybeHasId, ObjectId,
+    OwnedStructureProperties, Part, PortalDestination, PowerCreep, ResourceType, RoomName, RoomVisual,
+    SharedCreepProperties, Source, SpawnOptions, Store, Structure, StructureController,
+    StructureObject, StructureProperties, StructureSpawn, StructureTower,
+};
+
+use super::{
+    objects::{
+        BodyPartData, ConstructionSiteData, ControlLevels, ControllerData, CreepData, FlagData,
+        ForeignSegmentData, MineralData, PowerCreepData, RoomData, SourceData, SpawnOpts,
+        SpawningData, StoreData, StructureData, WorldVisual,
+    },
+    World,
+};
+
+/// The world as seen through the game's API
+pub struct ScreepsWorld {
+    /// The tick the creep snapshots were taken on
+    creeps_tick: u32,
+    /// Snapshots of the creeps read this tick, so each body and store is copied out of the game once a tick
+    creeps: HashMap<ObjectId<Creep>, CreepData>,
+}
+
+impl ScreepsWorld {
+    pub fn new() -> Self {
+        Self {
+            creeps_tick: 0,
+            creeps: HashMap::new(),
+        }
+    }
+
+    fn cached_creep_data(&mut self, creep: &Creep) -> CreepData {
+        let tick = game::time();
+        if self.creeps_tick != tick {
+            self.creeps.clear();
+            self.creeps_tick = tick;
+        }
+
+        // Creeps only just ordered to spawn have no id to cache them by
+        let Some(id) = creep.try_id() else {
+            return creep_data(creep);
+        };
+
+        self.creeps
+            .entry(id)
+            .or_insert_with(|| creep_data(creep))
+            .clone()
+    }
+}
+
+impl World for ScreepsWorld {
+    fn time(&mut self) -> u32 {
+        game::time()
+    }
+
+    fn cpu_used(&mut self) -> f64 {
+        game::cpu::get_used()
+    }
+
+    fn cpu_bucket(&mut self) -> i32 {
+        game::cpu::bucket()
+    }
+
+    fn shard(&mut self) -> String {
+        game::shard::name()
+    }
+
+    fn control_levels(&mut self) -> ControlLevels {
+        ControlLevels {
+            gcl_level: game::gcl::level(),
+            gcl_progress: game::gcl::progress(),
+            gcl_progress_total: game::gcl::progress_total(),
+            gpl_progress: game::gpl::progress(),
+            gpl_progress_total: game::gpl::progress_total(),
+        }
+    }
+
+    fn notify(&mut self, message: &str) {
+        game::notify(message, None);
+    }
+
+    fn my_creeps(&mut self) -> Vec<CreepData> {
+        game::creeps()
+            .values()
+            .map(|creep| self.cached_creep_data(&creep))
+            .collect()
+    }
+
+    fn my_power_creeps(&mut self) -> Vec<PowerCreepData> {
+        game::power_creeps()
+            .values()
+            .filter_map(|account_power_creep| PowerCreep::try_from(account_power_creep).ok())
+            .map(|power_creep| PowerCreepData {
+                name: power_creep.name(),
+                id: power_creep.id(),
+                pos: power_creep.pos(),
+                hits: power_creep.hits(),
+                hits_max: power_creep.hits_max(),
+                ticks_to_live: power_creep.ticks_to_live(),
+                store: store_data(&power_creep.store(), &[]),
+            })
+            .collect()
+    }
+
+    fn rooms(&mut self) -> Vec<RoomData> {
+        game::rooms()
+            .values()
+            .map(|room| RoomData {
+                name: room.name(),
+                controller: room.controller().map(|controller| controller_data(&controller)),
+                energy_available: room.energy_available(),
+                energy_capacity_available: room.energy_capacity_available(),
+            })
+            .collect()
+    }
+
+    fn flags(&mut self) -> Vec<FlagData> {
+        game::flags()
+            .values()
+            .map(|flag| FlagData {
+                name: flag.name(),
+                pos: flag.pos(),
+            })
+            .collect()
+    }
+
+    fn my_construction_sites(&mut self) -> Vec<ConstructionSiteData> {
+        game::construction_sites()
+            .values()
+            .map(|construction_site| construction_site_data(&construction_site))
+            .collect()
+    }
+
+    fn structures(&mut self, room_name: RoomName) -> Vec<StructureData> {
+        let Some(room) = game::rooms().get(room_name) else {
+            return Vec::new();
+        };
+
+        room.find(find::STRUCTURES, None)
+            .iter()
+            .filter(|structure| !matches!(structure, StructureObject::StructureController(_)))
+            .map(structure_data)
+            .collect()
+    }
+
+    fn construction_sites(&mut self, room_name: RoomName) -> Vec<ConstructionSiteData> {
+        let Some(room) = game::rooms().get(room_name) else {
+            return Vec::new();
+        };
+
+        room.find(find::CONSTRUCTION_SITES, None)
+            .iter()
+            .map(construction_site_data)
+            .collect()
+    }
+
+    fn sources(&mut self, room_name: RoomName) -> Vec<SourceData> {
+        let Some(room) = game::rooms().get(room_name) else {
+            return Vec::new();
+        };
+
+        room.find(find::SOURCES, None)
+            .iter()
+            .map(|source| SourceData {
+                id: source.id(),
+                pos: source.pos(),
+                energy: source.energy(),
+                energy_capacity: source.energy_capacity(),
+                ticks_to_regeneration: source.ticks_to_regeneration(),
+            })
+            .collect()
+    }
+
+    fn minerals(&mut self, room_name: RoomName) -> Vec<MineralData> {
+        let Some(room) = game::rooms().get(room_name) else {
+            return Vec::new();
+        };
+
+        room.find(find::MINERALS, None)
+            .iter()
+            .map(|mineral| MineralData {
+                id: mineral.id(),
+                pos: mineral.pos(),
+                mineral_type: mineral.mineral_type(),
+                mineral_amount: mineral.mineral_amount(),
+            })
+            .collect()
+    }
+
+    fn hostile_creeps(&mut self, room_name: RoomName) -> Vec<CreepData> {
+        let Some(room) = game::rooms().get(room_name) else {
+            return Vec::new();
+        };
+
+        room.find(find::HOSTILE_CREEPS, None)
+            .iter()
+            .map(|creep| self.cached_creep_data(creep))
+            .collect()
+    }
+
+    fn structure_is_active(&mut self, id: ObjectId<Structure>) -> bool {
+        match game::get_object_by_id_typed(&id) {
+            Some(structure) => structure.is_active(),
+            None => false,
+        }
+    }
+
+    fn terrain(&mut self, room_name: RoomName) -> Option<LocalRoomTerrain> {
+        let js_terrain = game::map::get_room_terrain(room_name)?;
+        Some(LocalRoomTerrain::from(js_terrain))
+    }
+
+    fn room_status(&mut self, room_name: RoomName) -> Option<RoomStatus> {
+        game::map::get_room_status(room_name).map(|result| result.status())
+    }
+
+    fn exits(&mut self, room_name: RoomName) -> Vec<(ExitDirection, RoomName)> {
+        game::map::describe_exits(room_name).entries().collect()
+    }
+
+    fn room_linear_distance(&mut self, from: RoomName, to: RoomName) -> u32 {
+        game::map::get_room_linear_distance(from, to, false)
+    }
+
+    fn creep_move(&mut self, creep_name: &str, direction: Direction) -> Result<(), ErrorCode> {
+        let creep = game::creeps()
+            .get(creep_name.to_string())
+            .ok_or(ErrorCode::NotFound)?;
+
+        creep.move_direction(direction)
+    }
+
+    fn power_creep_move(&mut self, power_creep_name: &str, direction: Direction) -> Result<(), ErrorCode> {
+        let account_power_creep = game::power_creeps()
+            .get(power_creep_name.to_string())
+            .ok_or(ErrorCode::NotFound)?;
+        let power_creep = PowerCreep::try_from(account_power_creep).map_err(|_| ErrorCode::NotFound)?;
+
+        power_creep.move_direction(direction)
+    }
+
+    fn creep_harvest(&mut self, creep_name: &str, source: ObjectId<Source>) -> Result<(), ErrorCode> {
+        let creep = game::creeps()
+            .get(creep_name.to_string())
+            .ok_or(ErrorCode::NotFound)?;
+        let source = game::get_object_by_id_typed(&source).ok_or(ErrorCode::NotFound)?;
+
+        creep.harvest(&source)
+    }
+
+    fn creep_suicide(&mut self, creep_name: &str) -> Result<(), ErrorCode> {
+        let creep = game::creeps()
+            .get(creep_name.to_string())
+            .ok_or(ErrorCode::NotFound)?;
+
+        creep.suicide()
+    }
+
+    fn spawn_creep(
+        &mut self,
+        spawn: ObjectId<Structure>,
+        body: &[Part],
+        name: &str,
+        opts: &SpawnOpts,
+    ) -> Result<(), ErrorCode> {
+        let spawn_id: ObjectId<StructureSpawn> = spawn.into_type();
+        let spawn = game::get_object_by_id_typed(&spawn_id).ok_or(ErrorCode::NotFound)?;
+
+        let mut spawn_options = SpawnOptions::new().dry_run(opts.dry_run);
+        if let Some(energy_structures) = &opts.energy_structures {
+            let energy_structures: Vec<Structure> =
+                energy_structures.iter().filter_map(|id| id.resolve()).collect();
+            spawn_options = spawn_options.energy_structures(energy_structures);
+        }
+        if let Some(directions) = &opts.directions {
+            spawn_options = spawn_options.directions(directions);
+        }
+
+        spawn.spawn_creep_with_options(body, name, &spawn_options)
+    }
+
+    fn renew_creep(&mut self, spawn: ObjectId<Structure>, creep_name: &str) -> Result<(), ErrorCode> {
+        let spawn_id: ObjectId<StructureSpawn> = spawn.into_type();
+        let spawn = game::get_object_by_id_typed(&spawn_id).ok_or(ErrorCode::NotFound)?;
+        let creep = game::creeps()
+            .get(creep_name.to_string())
+            .ok_or(ErrorCode::NotFound)?;
+
+        spawn.renew_creep(&creep)
+    }
+
+    fn recycle_creep(&mut self, spawn: ObjectId<Structure>, creep_name: &str) -> Result<(), ErrorCode> {
+        let spawn_id: ObjectId<StructureSpawn> = spawn.into_type();
+        let spawn = game::get_object_by_id_typed(&spawn_id).ok_or(ErrorCode::NotFound)?;
+        let creep = game::creeps()
+            .get(creep_name.to_string())
+            .ok_or(ErrorCode::NotFound)?;
+
+        spawn.recycle_creep(&creep)
+    }
+
+    fn tower_attack(&mut self, tower: ObjectId<Structure>, target: ObjectId<Creep>) -> Result<(), ErrorCode> {
+        let tower = tower_by_id(tower)?;
+        let target = game::get_object_by_id_typed(&target).ok_or(ErrorCode::NotFound)?;
+
+        tower.attack(&target)
+    }
+
+    fn tower_heal(&mut self, tower: ObjectId<Structure>, target: ObjectId<Creep>) -> Result<(), ErrorCode> {
+        let tower = tower_by_id(tower)?;
+        let target = game::get_object_by_id_typed(&target).ok_or(ErrorCode::NotFound)?;
+
+        tower.heal(&target)
+    }
+
+    fn tower_repair(&mut self, tower: ObjectId<Structure>, target: ObjectId<Structure>) -> Result<(), ErrorCode> {
+        let tower = tower_by_id(tower)?;
+        let target = game::get_object_by_id_typed(&target).ok_or(ErrorCode::NotFound)?;
+
+        tower.repair(&target)
+    }
+
+    fn activate_safe_mode(&mut self, controller: ObjectId<StructureController>) -> Result<(), ErrorCode> {
+        let controller = game::get_object_by_id_typed(&controller).ok_or(ErrorCode::NotFound)?;
+
+        controller.activate_safe_mode()
+    }
+
+    fn remove_construction_site(&mut self, id: ObjectId<ConstructionSite>) -> Result<(), ErrorCode> {
+        let construction_site = game::get_object_by_id_typed(&id).ok_or(ErrorCode::NotFound)?;
+
+        construction_site.remove()
+    }
+
+    fn raw_memory(&mut self) -> String {
+        raw_memory::get().as_string().unwrap_or_default()
+    }
+
+    fn set_raw_memory(&mut self, value: String) {
+        raw_memory::set(&JsString::from(value));
+    }
+
+    fn segment(&mut self, segment: u8) -> Option<String> {
+        raw_memory::segments().get(segment)
+    }
+
+    fn set_segment(&mut self, segment: u8, value: String) {
+        raw_memory::segments().set(segment, value);
+    }
+
+    fn set_active_segments(&mut self, segments: &[u8]) {
+        raw_memory::set_active_segments(segments);
+    }
+
+    fn set_public_segments(&mut self, segments: &[u8]) {
+        raw_memory::set_public_segments(segments);
+    }
+
+    fn set_active_foreign_segment(&mut self, username: &str, segment: Option<u8>) {
+        raw_memory::set_active_foreign_segment(&JsString::from(username), segment);
+    }
+
+    fn foreign_segment(&mut self) -> Option<ForeignSegmentData> {
+        let foreign_segment = raw_memory::foreign_segment()?;
+
+        Some(ForeignSegmentData {
+            username: String::from(foreign_segment.username()),
+            data: String::from(foreign_segment.data()),
+        })
+    }
+
+    fn draw(&mut self, room_name: RoomName, visual: WorldVisual) {
+        let room_visual = RoomVisual::new(Some(room_name));
+
+        match visual {
+            WorldVisual::Circle { x, y } => room_visual.circle(x, y, None),
+            WorldVisual::Text { x, y, text } => room_visual.text(x, y, text, None),
+            WorldVisual::Poly { points } => room_visual.poly(points, None),
+        }
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+fn tower_by_id(id: ObjectId<Structure>) -> Result<StructureTower, ErrorCode> {
+    let tower_id: ObjectId<StructureTower> = id.into_type();
+    game::get_object_by_id_typed(&tower_id).ok_or(ErrorCode::NotFound)
+}
+
+fn creep_data(creep: &Creep) -> CreepData {
+    CreepData {
+        name: creep.name(),
+        id: creep.try_id(),
+        owner: creep.owner().username(),
+        my: creep.my(),
+        pos: creep.pos(),
+        body: creep
+            .body()
+            .iter()
+            .map(|body_part| BodyPartData {
+                part: body_part.part(),
+                hits: body_part.hits(),
+                boost: body_part.boost(),
+            })
+            .collect(),
+        fatigue: creep.fatigue(),
+        hits: creep.hits(),
+        hits_max: creep.hits_max(),
+        ticks_to_live: creep.ticks_to_live(),
+        spawning: creep.spawning(),
+        store: store_data(&creep.store(), &[]),
+    }
+}
+
+/// Stores without a shared capacity limit each resource separately, so record what they can hold
+fn store_data(store: &Store, extra_resources: &[ResourceType]) -> StoreData {
+    let mut store_data = StoreData::new();
+
+    for resource in store.store_types() {
+        store_data
+            .resources
+            .insert(resource, store.get_used_capacity(Some(resource)));
+    }
+
+    store_data.capacity = store.get_capacity(None);
+    if store_data.capacity > 0 {
+        return store_data;
+    }
+
+    let mut resources = vec![ResourceType::Energy];
+    resources.extend(store_data.resources.keys().copied());
+    resources.extend(extra_resources.iter().copied());
+
+    for resource in resources {
+        store_data
+            .resource_capacities
+            .insert(resource, store.get_capacity(Some(resource)));
+    }
+
+    store_data
+}
+
+fn structure_data(structure: &StructureObject) -> StructureData {
+    let mut structure_data = StructureData::new(
+        structure.as_structure().id(),
+        structure.structure_type(),
+        structure.pos(),
+    );
+
+    structure_data.hits = structure.as_structure().hits();
+    structure_data.hits_max = structure.as_structure().hits_max();
+
+    if let Some(owned) = structure.as_owned() {
+        structure_data.my = Some(owned.my());
+        structure_data.owner = owned.owner().map(|owner| owner.username());
+    }
+
+    if let Some(has_store) = structure.as_has_store() {
+        let extra_resources: &[ResourceType] = match structure {
+            StructureObject::StructurePowerSpawn(_) => &[ResourceType::Power],
+            StructureObject::StructureNuker(_) => &[ResourceType::Ghodium],
+            _ => &[],
+        };
+
+        structure_data.store = Some(store_data(&has_store.store(), extra_resources));
+    }
+
+    match structure {
+        StructureObject::StructureSpawn(spawn) => {
+            structure_data.name = Some(spawn.name());
+            structure_data.spawning = spawn.spawning().map(|spawning| SpawningData {
+                name: spawning.name().into(),
+                need_time: spawning.need_time(),
+                remaining_time: spawning.remaining_time(),
+            });
+        }
+        StructureObject::StructurePortal(portal) => {
+            structure_data.portal_destination = Some(match portal.destination() {
+                PortalDestination::InterRoom(destination) => destination.room_name(),
+                PortalDestination::InterShard(destination) => destination.room(),
+            });
+        }
+        _ => {}
+    }
+
+    structure_data
+}
+
+fn controller_data(controller: &StructureController) -> ControllerData {
+    ControllerData {
+        id: controller.id(),
+        pos: controller.pos(),
+        level: controller.level(),
+        my: controller.my(),
+        owner: controller.owner().map(|owner| owner.username()),
+        reservation: controller
+            .reservation()
+            .map(|reservation| reservation.username()),
+        progress: controller.progress(),
+        progress_total: controller.progress_total(),
+        ticks_to_downgrade: controller.ticks_to_downgrade(),
+        safe_mode: controller.safe_mode(),
+        safe_mode_available: controller.safe_mode_available(),
+        safe_mode_cooldown: controller.safe_mode_cooldown(),
+    }
+}
+
+fn construction_site_data(construction_site: &ConstructionSite) -> ConstructionSiteData {
+    ConstructionSiteData {
+        id: construction_site.try_id(),
+        pos: construction_site.pos(),
+        structure_type: construction_site.structure_type(),
+        my: construction_site.my(),
+        owner: construction_site.owner().username(),
+        progress: construction_site.progress(),
+        progress_total: construction_site.progress_total(),
+    }
+}
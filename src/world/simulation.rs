@@ -0,0 +1,641 @@
+use std::collections::{HashMap, HashSet};
+
+use screeps::{
+    Direction, ObjectId, Part, Position, ResourceType, RoomName, Structure, StructureType, Terrain,
+    CREEP_LIFE_TIME, CREEP_SPAWN_TIME, ENERGY_REGEN_TIME, HARVEST_POWER, SPAWN_ENERGY_CAPACITY,
+};
+
+use crate::{
+    constants::general::DIRECTIONS, memory::game_memory::GameMemory, settings::Settings,
+    state::game::GameState,
+};
+
+use super::{
+    mock_world::{self, Intent, MockWorld},
+    objects::{CreepData, SpawningData},
+};
+
+/// Builds a mock world for a claimed RCL 1 room from a 50 by 50 character map.
+///
+/// `#` is a wall, `~` is swamp, `S` a spawn with starting energy, `E` an extension, `R` a road,
+/// `N` a container, `K` the controller, `O` a source and `M` a mineral. Anything else is plain
+pub fn world_from_map(room_name: RoomName, map: &str) -> MockWorld {
+    let mut world = MockWorld::new();
+    world.add_room(room_name);
+
+    let rows: Vec<&str> = map
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect();
+    assert_eq!(rows.len(), 50, "maps need 50 rows");
+
+    let mut spawns = 0;
+
+    for (y, row) in rows.iter().enumerate() {
+        assert_eq!(row.chars().count(), 50, "map row {} needs 50 columns", y);
+
+        for (x, character) in row.chars().enumerate() {
+            let (x, y) = (x as u8, y as u8);
+            let pos = mock_world::pos(room_name, x, y);
+
+            match character {
+                '#' => world.room_mut(room_name).set_terrain(x, y, Terrain::Wall),
+                '~' => world.room_mut(room_name).set_terrain(x, y, Terrain::Swamp),
+                'S' => {
+                    spawns += 1;
+
+                    let id = world.add_structure(StructureType::Spawn, pos);
+                    let spawn = world.structure_mut(id).unwrap();
+                    spawn.name = Some(format!("Spawn{}", spawns));
+                    let store = spawn.store.as_mut().unwrap();
+                    store.resources.insert(ResourceType::Energy, SPAWN_ENERGY_CAPACITY);
+                }
+                'E' => {
+                    world.add_structure(StructureType::Extension, pos);
+                }
+                'R' => {
+                    world.add_structure(StructureType::Road, pos);
+                }
+                'N' => {
+                    world.add_structure(StructureType::Container, pos);
+                }
+                'K' => {
+                    world.add_controller(room_name, x, y, 1);
+                }
+                'O' => {
+                    world.add_source(pos);
+                }
+                'M' => world.add_mineral(pos, ResourceType::Hydrogen),
+                _ => {}
+            }
+        }
+    }
+
+    world
+}
+
+#[derive(Debug, Default)]
+pub struct SimulationStats {
+    pub energy_harvested: u32,
+    pub creeps_spawned: u32,
+    pub moves: u32,
+    /// Harvested energy that did not fit in the harvester and energy from recycled creeps, by where it landed
+    pub dropped_energy: HashMap<Position, u32>,
+}
+
+/// Steps the bot's real loop against a mock world, then plays out the intents it issued:
+/// spawning, harvesting, movement with fatigue, creep aging and energy regeneration.
+/// Nothing is random, so the same map always plays out the same way
+pub struct Simulation {
+    pub memory: GameMemory,
+    pub game_state: GameState,
+    pub settings: Settings,
+    pub stats: SimulationStats,
+}
+
+impl Simulation {
+    /// Installs the world for this thread and starts the bot with fresh memory
+    pub fn new(world: MockWorld) -> Self {
+        mock_world::install(world);
+
+        let settings = Settings::new();
+
+        Self {
+            memory: GameMemory::new(&settings),
+            game_state: GameState::new(),
+            settings,
+            stats: SimulationStats::default(),
+        }
+    }
+
+    pub fn time(&self) -> u32 {
+        mock_world::with(|mock| mock.time)
+    }
+
+    /// Runs the loop for the current tick and resolves what it asked for
+    pub fn tick(&mut self) {
+        crate::loop_with_params(&mut self.memory, &mut self.game_state, &self.settings);
+
+        let stats = &mut self.stats;
+        mock_world::with(|mock| {
+            let intents = std::mem::take(&mut mock.intents);
+
+            resolve_spawns(mock, &intents, stats);
+            resolve_renewals(mock, &intents);
+            resolve_recycles(mock, &intents, stats);
+            resolve_harvests(mock, &intents, stats);
+            resolve_moves(mock, &intents, stats);
+            resolve_suicides(mock, &intents);
+
+            end_tick(mock);
+            mock.next_tick();
+        });
+    }
+
+    /// Ticks until the world reaches the given game time
+    pub fn run_until(&mut self, time: u32) {
+        while self.time() < time {
+            self.tick();
+        }
+    }
+}
+
+fn resolve_spawns(mock: &mut MockWorld, intents: &[Intent], stats: &mut SimulationStats) {
+    for intent in intents {
+        let Intent::SpawnCreep {
+            spawn,
+            body,
+            name,
+            energy_structures,
+            ..
+        } = intent
+        else {
+            continue;
+        };
+
+        let Some(spawn_data) = mock.structure_mut(*spawn) else {
+            continue;
+        };
+        let spawn_pos = spawn_data.pos;
+        spawn_data.spawning = Some(SpawningData {
+            name: name.clone(),
+            need_time: body.len() as u32 * CREEP_SPAWN_TIME,
+            remaining_time: body.len() as u32 * CREEP_SPAWN_TIME,
+        });
+
+        let cost: u32 = body.iter().map(|part| part.cost()).sum();
+        spend_spawn_energy(mock, spawn_pos.room_name(), energy_structures.as_ref(), cost);
+
+        let id = mock.new_id();
+        let me = mock.me.clone();
+        let mut creep = mock_world::new_creep(name, Some(id), &me, spawn_pos, body);
+        creep.spawning = true;
+        creep.ticks_to_live = None;
+
+        mock.creeps.insert(name.clone(), creep);
+        stats.creeps_spawned += 1;
+    }
+}
+
+/// Spend energy from the given energy structures in order, otherwise from spawns and extensions in the order they were built
+fn spend_spawn_energy(
+    mock: &mut MockWorld,
+    room_name: RoomName,
+    energy_structures: Option<&Vec<ObjectId<Structure>>>,
+    mut cost: u32,
+) {
+    let room = mock.room_mut(room_name);
+
+    let order: Vec<usize> = match energy_structures {
+        Some(energy_structures) => energy_structures
+            .iter()
+            .filter_map(|id| room.structures.iter().position(|structure| structure.id == *id))
+            .collect(),
+        None => (0..room.structures.len()).collect(),
+    };
+
+    for index in order {
+        let structure = &mut room.structures[index];
+        if !matches!(
+            structure.structure_type,
+            StructureType::Spawn | StructureType::Extension
+        ) {
+            continue;
+        }
+        let Some(store) = &mut structure.store else {
+            continue;
+        };
+
+        let energy = store.resources.entry(ResourceType::Energy).or_insert(0);
+        let spent = (*energy).min(cost);
+        *energy -= spent;
+        cost -= spent;
+    }
+}
+
+fn resolve_renewals(mock: &mut MockWorld, intents: &[Intent]) {
+    for intent in intents {
+        let Intent::RenewCreep { spawn, creep_name } = intent else {
+            continue;
+        };
+
+        let Some(room_name) = mock.structure_mut(*spawn).map(|spawn_data| spawn_data.pos.room_name()) else {
+            continue;
+        };
+        let Some(creep) = mock.creeps.get_mut(creep_name) else {
+            continue;
+        };
+
+        let cost = creep.renew_energy();
+        let renew_ticks = creep.renew_ticks();
+        creep.ticks_to_live = creep
+            .ticks_to_live
+            .map(|ticks_to_live| (ticks_to_live + renew_ticks).min(CREEP_LIFE_TIME));
+        // Like the game, renewing removes boosts
+        for body_part in &mut creep.body {
+            body_part.boost = None;
+        }
+
+        spend_spawn_energy(mock, room_name, None, cost);
+    }
+}
+
+/// Recycled creeps leave behind energy for the body cost of the life they had left
+fn resolve_recycles(mock: &mut MockWorld, intents: &[Intent], stats: &mut SimulationStats) {
+    for intent in intents {
+        let Intent::RecycleCreep { creep_name, .. } = intent else {
+            continue;
+        };
+
+        let Some(creep) = mock.creeps.remove(creep_name) else {
+            continue;
+        };
+
+        let ticks_to_live = creep.ticks_to_live.unwrap_or(0);
+        let energy = creep.body_cost() * ticks_to_live / CREEP_LIFE_TIME;
+        *stats.dropped_energy.entry(creep.pos).or_insert(0) += energy;
+    }
+}
+
+fn resolve_harvests(mock: &mut MockWorld, intents: &[Intent], stats: &mut SimulationStats) {
+    for intent in intents {
+        let Intent::Harvest { creep_name, source } = intent else {
+            continue;
+        };
+
+        let Some(creep) = mock.creeps.get_mut(creep_name) else {
+            continue;
+        };
+        let Some(source_data) = mock
+            .rooms
+            .values_mut()
+            .flat_map(|room| room.sources.iter_mut())
+            .find(|source_data| source_data.id == *source)
+        else {
+            continue;
+        };
+
+        let amount = (creep.get_active_bodyparts(Part::Work) as u32 * HARVEST_POWER)
+            .min(source_data.energy);
+        source_data.energy -= amount;
+        if source_data.ticks_to_regeneration.is_none() {
+            source_data.ticks_to_regeneration = Some(ENERGY_REGEN_TIME);
+        }
+
+        stats.energy_harvested += amount;
+
+        let stored = amount.min(creep.store.get_free_capacity(Some(ResourceType::Energy)));
+        *creep.store.resources.entry(ResourceType::Energy).or_insert(0) += stored;
+
+        if amount > stored {
+            *stats.dropped_energy.entry(creep.pos).or_insert(0) += amount - stored;
+        }
+    }
+}
+
+/// Moves creeps whose destinations are free. Creeps may swap places, but a creep can't move
+/// onto one that stays put, and when several want the same tile the first to ask gets it
+fn resolve_moves(mock: &mut MockWorld, intents: &[Intent], stats: &mut SimulationStats) {
+    let mut targets: Vec<(String, Position)> = Vec::new();
+
+    for intent in intents {
+        let Intent::Move { creep_name, direction } = intent else {
+            continue;
+        };
+        // Only the last move intent of a creep counts
+        targets.retain(|(name, _)| name != creep_name);
+
+        let Some(creep) = mock.creeps.get(creep_name) else {
+            continue;
+        };
+        let Ok(target) = creep.pos.checked_add_direction(*direction) else {
+            continue;
+        };
+        if !is_walkable(mock, target) {
+            continue;
+        }
+
+        targets.push((creep_name.clone(), target));
+    }
+
+    // Drop moves until every remaining destination is free
+
+    loop {
+        let moving: HashSet<&String> = targets.iter().map(|(name, _)| name).collect();
+        let stationary: HashSet<Position> = mock
+            .creeps
+            .values()
+            .filter(|creep| !moving.contains(&creep.name))
+            .map(|creep| creep.pos)
+            .collect();
+
+        let mut claimed: HashSet<Position> = HashSet::new();
+        let blocked = targets
+            .iter()
+            .position(|(_, target)| stationary.contains(target) || !claimed.insert(*target));
+
+        let Some(blocked) = blocked else {
+            break;
+        };
+        targets.remove(blocked);
+    }
+
+    for (creep_name, target) in targets {
+        let cost = match mock.rooms[&target.room_name()]
+            .structures
+            .iter()
+            .any(|structure| {
+                structure.pos == target && structure.structure_type == StructureType::Road
+            }) {
+            true => 1,
+            false => match mock.rooms[&target.room_name()]
+                .get_terrain(target.x().u8(), target.y().u8())
+            {
+                Terrain::Swamp => 10,
+                _ => 2,
+            },
+        };
+
+        let creep = mock.creeps.get_mut(&creep_name).unwrap();
+        creep.fatigue += cost * fatigue_parts(creep);
+        creep.pos = target;
+
+        stats.moves += 1;
+    }
+}
+
+/// Parts that weigh a creep down: everything but move parts and empty carry parts
+fn fatigue_parts(creep: &CreepData) -> u32 {
+    let mut carry_parts_needed = creep
+        .store
+        .get_used_capacity(None)
+        .div_ceil(50);
+
+    creep
+        .body
+        .iter()
+        .filter(|part| part.hits > 0)
+        .filter(|part| match part.part {
+            Part::Move => false,
+            Part::Carry if carry_parts_needed == 0 => false,
+            Part::Carry => {
+                carry_parts_needed -= 1;
+                true
+            }
+            _ => true,
+        })
+        .count() as u32
+}
+
+fn is_walkable(mock: &MockWorld, pos: Position) -> bool {
+    let Some(room) = mock.rooms.get(&pos.room_name()) else {
+        return false;
+    };
+    let (x, y) = (pos.x().u8(), pos.y().u8());
+
+    if room.get_terrain(x, y) == Terrain::Wall {
+        return false;
+    }
+
+    let blocked_by_structure = room.structures.iter().any(|structure| {
+        structure.pos == pos
+            && !matches!(
+                structure.structure_type,
+                StructureType::Road | StructureType::Container | StructureType::Rampart
+            )
+    });
+    let blocked_by_object = room.sources.iter().any(|source| source.pos == pos)
+        || room.minerals.iter().any(|mineral| mineral.pos == pos)
+        || room
+            .controller
+            .as_ref()
+            .is_some_and(|controller| controller.pos == pos);
+
+    !blocked_by_structure && !blocked_by_object
+}
+
+fn resolve_suicides(mock: &mut MockWorld, intents: &[Intent]) {
+    for intent in intents {
+        let Intent::Suicide { creep_name } = intent else {
+            continue;
+        };
+
+        mock.creeps.remove(creep_name);
+    }
+}
+
+/// What the game does between ticks regardless of intents
+fn end_tick(mock: &mut MockWorld) {
+    // Creeps recover fatigue and age
+
+    for creep in mock.creeps.values_mut() {
+        let recovery = creep.get_active_bodyparts(Part::Move) as u32 * 2;
+        creep.fatigue = creep.fatigue.saturating_sub(recovery);
+
+        if let Some(ticks_to_live) = &mut creep.ticks_to_live {
+            *ticks_to_live -= 1;
+        }
+    }
+    mock.creeps
+        .retain(|_, creep| creep.ticks_to_live.map_or(true, |ticks_to_live| ticks_to_live > 0));
+
+    // Spawns make progress and let finished creeps out
+
+    let spawn_ids: Vec<_> = mock
+        .rooms
+        .values()
+        .flat_map(|room| room.structures.iter())
+        .filter(|structure| structure.spawning.is_some())
+        .map(|structure| structure.id)
+        .collect();
+
+    for spawn_id in spawn_ids {
+        let spawn = mock.structure_mut(spawn_id).unwrap();
+        let spawning = spawn.spawning.as_mut().unwrap();
+        spawning.remaining_time -= 1;
+        if spawning.remaining_time > 0 {
+            continue;
+        }
+
+        let name = spawning.name.clone();
+        let spawn_pos = spawn.pos;
+        spawn.spawning = None;
+
+        let exit = DIRECTIONS.iter().find_map(|direction| {
+            let pos = spawn_pos.checked_add_direction(*direction).ok()?;
+            let occupied = mock.creeps.values().any(|creep| creep.pos == pos);
+
+            (!occupied && is_walkable(mock, pos)).then_some(pos)
+        });
+
+        let Some(creep) = mock.creeps.get_mut(&name) else {
+            continue;
+        };
+        creep.spawning = false;
+        creep.ticks_to_live = Some(CREEP_LIFE_TIME);
+        if let Some(exit) = exit {
+            creep.pos = exit;
+        }
+    }
+
+    // Sources refill once their regeneration runs out
+
+    for source in mock
+        .rooms
+        .values_mut()
+        .flat_map(|room| room.sources.iter_mut())
+    {
+        let Some(ticks_to_regeneration) = &mut source.ticks_to_regeneration else {
+            continue;
+        };
+
+        *ticks_to_regeneration -= 1;
+        if *ticks_to_regeneration == 0 {
+            source.energy = source.energy_capacity;
+            source.ticks_to_regeneration = None;
+        }
+    }
+
+    // Spawns regenerate energy while the room is low
+
+    for room in mock.rooms.values_mut() {
+        if room.energy_available() >= SPAWN_ENERGY_CAPACITY {
+            continue;
+        }
+
+        for structure in room.structures.iter_mut() {
+            if structure.structure_type != StructureType::Spawn {
+                continue;
+            }
+            let Some(store) = &mut structure.store else {
+                continue;
+            };
+
+            let energy = store.resources.entry(ResourceType::Energy).or_insert(0);
+            if *energy < SPAWN_ENERGY_CAPACITY {
+                *energy += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use screeps::RoomName;
+
+    use super::{world_from_map, Simulation};
+    use crate::world::mock_world;
+
+    const OPEN_ROOM: &str = "
+        ##################################################
+        #................................................#
+        #................................................#
+        #................................................#
+        #.........O......................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #.........~~~~~..................................#
+        #.........~~~~~..................................#
+        #.........~~~~~..................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #........................S.......................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #...........M....................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #........................K.......................#
+        #................................................#
+        #................................................#
+        #................................................#
+        #.......................................O........#
+        #................................................#
+        #................................................#
+        #................................................#
+        #................................................#
+        ##################################################
+    ";
+
+    #[test]
+    fn map_sets_terrain_and_objects() {
+        let room_name = RoomName::new("W1N1").unwrap();
+        let world = world_from_map(room_name, OPEN_ROOM);
+        let room = &world.rooms[&room_name];
+
+        assert_eq!(room.get_terrain(0, 0), screeps::Terrain::Wall);
+        assert_eq!(room.get_terrain(10, 10), screeps::Terrain::Swamp);
+        assert_eq!(room.get_terrain(1, 1), screeps::Terrain::Plain);
+        assert_eq!(room.sources.len(), 2);
+        assert_eq!(room.minerals.len(), 1);
+        assert_eq!(room.structures.len(), 1);
+        assert_eq!(room.controller.as_ref().unwrap().level, 1);
+        assert_eq!(room.energy_available(), 300);
+    }
+
+    #[test]
+    fn moving_builds_fatigue_by_terrain() {
+        let room_name = RoomName::new("W1N1").unwrap();
+        let mut simulation = Simulation::new(world_from_map(room_name, OPEN_ROOM));
+
+        mock_world::with(|mock| {
+            mock.add_creep(
+                "swamp_walker",
+                mock_world::pos(room_name, 9, 10),
+                &[screeps::Part::Work, screeps::Part::Move],
+            );
+            mock.intents.push(super::Intent::Move {
+                creep_name: "swamp_walker".to_string(),
+                direction: screeps::Direction::Right,
+            });
+
+            let intents = std::mem::take(&mut mock.intents);
+            super::resolve_moves(mock, &intents, &mut simulation.stats);
+            super::end_tick(mock);
+
+            let creep = &mock.creeps["swamp_walker"];
+            assert_eq!(creep.pos, mock_world::pos(room_name, 10, 10));
+            // 10 fatigue for the work part on swamp, less 2 recovered by the move part
+            assert_eq!(creep.fatigue, 8);
+        });
+    }
+
+    #[test]
+    fn rcl1_room_harvests_by_tick_1500() {
+        let room_name = RoomName::new("W1N1").unwrap();
+        let mut simulation = Simulation::new(world_from_map(room_name, OPEN_ROOM));
+
+        simulation.run_until(1500);
+
+        assert!(simulation.stats.creeps_spawned > 0);
+        assert!(simulation.stats.moves > 0);
+        assert!(
+            simulation.stats.energy_harvested >= 1000,
+            "only harvested {} energy",
+            simulation.stats.energy_harvested
+        );
+    }
+}